@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+use crate::path::glob_match;
+
+/// Patterns of files made by operating systems and file managers, see [`ExcludeRules`].
+pub const BUILTIN_PATTERNS: &[&str] = &[
+    "__MACOSX/**",
+    "._*",
+    ".DS_Store",
+    "Thumbs.db",
+    "desktop.ini",
+    ".thumbs/**",
+    ".thumbnails/**",
+    "@eaDir/**",
+];
+
+/// Rules excluding junk entries from books, e.g. resource forks, credit pages and spacer
+/// images.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExcludeRules {
+    /// exclude files matching [`BUILTIN_PATTERNS`].
+    pub builtin: bool,
+    /// glob patterns, pattern without `/` is matched against the file name and the other
+    /// against the path of entry or any of its parents.
+    pub patterns: Vec<String>,
+    /// image narrower or shorter than this is excluded, 0 to disable.
+    pub min_dimension: u32,
+}
+
+impl Default for ExcludeRules {
+    fn default() -> Self {
+        Self {
+            builtin: true,
+            patterns: vec![],
+            min_dimension: 0,
+        }
+    }
+}
+
+impl ExcludeRules {
+    /// Returns `true` if entry `name` separated by `/` matches any pattern.
+    pub fn is_excluded(&self, name: &str) -> bool {
+        let builtin = match self.builtin {
+            true => BUILTIN_PATTERNS,
+            false => &[],
+        };
+
+        builtin
+            .iter()
+            .copied()
+            .chain(self.patterns.iter().map(String::as_str))
+            .filter(|it| !it.trim().is_empty())
+            .any(|it| matches(it.trim(), name))
+    }
+
+    /// Returns `true` if image of `size` is smaller than [`min_dimension`].
+    ///
+    /// [`min_dimension`]: Self::min_dimension
+    pub fn is_too_small(&self, [width, height]: [u32; 2]) -> bool {
+        width.min(height) < self.min_dimension
+    }
+}
+
+fn matches(pattern: &str, name: &str) -> bool {
+    let name = name.trim_start_matches('/');
+    if !pattern.contains('/') {
+        let file_name = name.rsplit('/').next().unwrap_or(name);
+        return glob_match(pattern, file_name);
+    }
+
+    let pattern = pattern.trim_start_matches('/');
+    std::iter::once(name)
+        .chain(name.match_indices('/').map(|(i, _)| &name[i + 1..]))
+        .any(|it| glob_match(pattern, it))
+}
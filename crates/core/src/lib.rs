@@ -1,4 +1,8 @@
+pub mod exclude;
+pub mod password;
 pub mod path;
+pub mod source;
+pub mod watch;
 
 /// RAII that abort the handle when dropped.
 pub struct AbortOnDropHandle<T>(pub tokio::task::JoinHandle<T>);
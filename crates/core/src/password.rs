@@ -0,0 +1,194 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use futures::Future;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+/// Passwords of encrypted archives, shared by loaders and the prompt asking for them.
+///
+/// Password is remembered for the session and the one saved with [`Passwords::answer`] is
+/// serialized as plain text by archive path.
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct Passwords {
+    saved: Arc<Mutex<BTreeMap<String, String>>>,
+    #[serde(skip)]
+    session: Arc<Mutex<PasswordSession>>,
+}
+
+#[derive(Default)]
+struct PasswordSession {
+    passwords: HashMap<PathBuf, String>,
+    // archives waiting for password in the order they're asked.
+    pending: Vec<PendingPassword>,
+    senders: HashMap<PathBuf, Vec<oneshot::Sender<Option<String>>>>,
+    // archive the prompt is dismissed, it isn't asked again in this session.
+    dismissed: HashSet<PathBuf>,
+    notify: Option<Arc<dyn Fn() + Send + Sync>>,
+}
+
+impl PasswordSession {
+    // forget archives nobody waits for anymore, e.g. the book is closed before it's answered.
+    fn prune(&mut self) {
+        self.senders.retain(|_, senders| {
+            senders.retain(|it| !it.is_closed());
+            !senders.is_empty()
+        });
+
+        let senders = &self.senders;
+        self.pending.retain(|it| senders.contains_key(&it.archive));
+    }
+}
+
+impl std::fmt::Debug for PasswordSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PasswordSession")
+            .field("pending", &self.pending)
+            .field("dismissed", &self.dismissed)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Archive waiting for its password.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingPassword {
+    pub archive: PathBuf,
+    /// the last password given is wrong.
+    pub is_retry: bool,
+}
+
+impl Passwords {
+    /// Set function called when a password is asked, e.g. to repaint the prompt.
+    pub fn set_notify(&self, notify: impl Fn() + Send + Sync + 'static) {
+        self.session.lock().notify = Some(Arc::new(notify));
+    }
+
+    /// Known password of `archive`.
+    pub fn get(&self, archive: &Path) -> Option<String> {
+        let session = self.session.lock();
+        match session.passwords.get(archive) {
+            Some(password) => Some(password.clone()),
+            None => self.saved.lock().get(&key(archive)).cloned(),
+        }
+    }
+
+    /// Ask password of `archive`, resolved to `None` when the prompt is dismissed.
+    ///
+    /// `rejected` is the password that failed, it's forgotten and the prompt is asked again
+    /// unless another password is known already.
+    pub fn request(
+        &self,
+        archive: &Path,
+        rejected: Option<&str>,
+    ) -> impl Future<Output = Option<String>> + Send + 'static {
+        let mut session = self.session.lock();
+
+        if let Some(rejected) = rejected {
+            if session.passwords.get(archive).map(String::as_str) == Some(rejected) {
+                session.passwords.remove(archive);
+            }
+
+            let mut saved = self.saved.lock();
+            if saved.get(&key(archive)).map(String::as_str) == Some(rejected) {
+                saved.remove(&key(archive));
+            }
+        }
+
+        let known = match session.passwords.get(archive) {
+            Some(password) => Some(password.clone()),
+            None => self.saved.lock().get(&key(archive)).cloned(),
+        };
+
+        let (sender, receiver) = oneshot::channel();
+        if known.is_some() || session.dismissed.contains(archive) {
+            sender.send(known).ok();
+        } else {
+            if !session.pending.iter().any(|it| it.archive == archive) {
+                session.pending.push(PendingPassword {
+                    archive: archive.to_path_buf(),
+                    is_retry: rejected.is_some(),
+                });
+            }
+            session
+                .senders
+                .entry(archive.to_path_buf())
+                .or_default()
+                .push(sender);
+
+            if let Some(notify) = &session.notify {
+                notify();
+            }
+        }
+
+        async move { receiver.await.ok().flatten() }
+    }
+
+    /// Archive the prompt should ask password for.
+    pub fn pending(&self) -> Option<PendingPassword> {
+        let mut session = self.session.lock();
+        session.prune();
+        session.pending.first().cloned()
+    }
+
+    /// Answer the prompt of `archive`, `None` dismisses it for the session. The password is
+    /// saved unencrypted under the profile when `save` is `true`.
+    pub fn answer(&self, archive: &Path, password: Option<String>, save: bool) {
+        let mut session = self.session.lock();
+        session.pending.retain(|it| it.archive != archive);
+
+        match &password {
+            Some(password) => {
+                session
+                    .passwords
+                    .insert(archive.to_path_buf(), password.clone());
+                if save {
+                    self.saved.lock().insert(key(archive), password.clone());
+                }
+            }
+            None => {
+                session.dismissed.insert(archive.to_path_buf());
+            }
+        }
+
+        for sender in session.senders.remove(archive).unwrap_or_default() {
+            sender.send(password.clone()).ok();
+        }
+    }
+}
+
+fn key(archive: &Path) -> String {
+    archive.to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closed_request_is_not_asked() {
+        let passwords = Passwords::default();
+        let (a, b) = (Path::new("a.zip"), Path::new("b.zip"));
+
+        let first = passwords.request(a, None);
+        let second = passwords.request(a, None);
+        let other = passwords.request(b, None);
+        assert_eq!(passwords.pending().unwrap().archive, a);
+
+        drop(first);
+        assert_eq!(passwords.pending().unwrap().archive, a);
+
+        drop(second);
+        assert_eq!(passwords.pending().unwrap().archive, b);
+
+        drop(other);
+        assert_eq!(passwords.pending(), None);
+
+        // it's asked again by a new request.
+        let _request = passwords.request(a, None);
+        assert_eq!(passwords.pending().unwrap().archive, a);
+    }
+}
@@ -3,12 +3,15 @@ use std::{
     fs::DirEntry,
     path::{Path, PathBuf},
     sync::Arc,
+    time::SystemTime,
 };
 
 use serde::{Deserialize, Serialize};
 
 pub type PathSorterType = Arc<dyn (Fn(&Path, &Path) -> std::cmp::Ordering) + Send + Sync>;
 
+pub type NameSorterType = Arc<dyn (Fn(&str, &str) -> std::cmp::Ordering) + Send + Sync>;
+
 pub fn get_folder_by(
     path: impl AsRef<Path>,
     direction: isize,
@@ -58,6 +61,258 @@ pub fn compare_natural(a: &OsStr, b: &OsStr) -> std::cmp::Ordering {
     compare_str(a, b, natord::compare)
 }
 
+/// Compare file name by volume, chapter and part number found by [`ChapterKey::parse`],
+/// falling back to natural order.
+pub fn compare_chapter(a: &OsStr, b: &OsStr) -> std::cmp::Ordering {
+    compare_str(a, b, compare_chapter_str)
+}
+
+/// Same as [`compare_chapter`] for `str`.
+pub fn compare_chapter_str(a: &str, b: &str) -> std::cmp::Ordering {
+    ChapterKey::parse(a)
+        .cmp(&ChapterKey::parse(b))
+        .then_with(|| natord::compare_ignore_case(a, b))
+        .then_with(|| a.cmp(b))
+}
+
+/// Volume, chapter and part number of a name.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ChapterKey {
+    pub volume: Option<f64>,
+    pub chapter: Option<f64>,
+    pub part: Option<f64>,
+}
+
+#[derive(Clone, Copy)]
+enum ChapterMarker {
+    Volume,
+    Chapter,
+    Part,
+}
+
+impl ChapterKey {
+    /// Find numbers marked as volume (`Vol.2`, `v02`, `2巻`), chapter (`Ch. 10.5`, `c003`,
+    /// `第十話`) and part (`Part 2`) in `name`, the first number is the chapter when none is
+    /// marked. Full-width digits and CJK numerals next to a marker are recognized.
+    pub fn parse(name: &str) -> Self {
+        let chars = normalize_numbers(name);
+        let mut key = Self::default();
+        let mut first = None;
+
+        let mut i = 0;
+        while i < chars.len() {
+            if !chars[i].is_ascii_digit() {
+                i += 1;
+                continue;
+            }
+
+            let start = i;
+            while chars.get(i).is_some_and(char::is_ascii_digit) {
+                i += 1;
+            }
+            if chars.get(i) == Some(&'.') && chars.get(i + 1).is_some_and(char::is_ascii_digit) {
+                i += 1;
+                while chars.get(i).is_some_and(char::is_ascii_digit) {
+                    i += 1;
+                }
+            }
+
+            let number = chars[start..i].iter().collect::<String>();
+            let Ok(number) = number.parse::<f64>() else {
+                continue;
+            };
+            first.get_or_insert(number);
+
+            let slot = match chapter_marker(&chars[..start], &chars[i..]) {
+                Some(ChapterMarker::Volume) => &mut key.volume,
+                Some(ChapterMarker::Chapter) => &mut key.chapter,
+                Some(ChapterMarker::Part) => &mut key.part,
+                None => continue,
+            };
+            slot.get_or_insert(number);
+        }
+
+        if key == Self::default() {
+            key.chapter = first;
+        }
+
+        key
+    }
+}
+
+impl Eq for ChapterKey {}
+
+impl PartialOrd for ChapterKey {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ChapterKey {
+    /// Compare volume, then chapter, then part, name without the number is ordered last.
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        fn compare(a: Option<f64>, b: Option<f64>) -> std::cmp::Ordering {
+            match (a, b) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                (a, b) => b.is_some().cmp(&a.is_some()),
+            }
+        }
+
+        compare(self.volume, other.volume)
+            .then_with(|| compare(self.chapter, other.chapter))
+            .then_with(|| compare(self.part, other.part))
+    }
+}
+
+// marker of number that's between `before` and `after`.
+fn chapter_marker(before: &[char], after: &[char]) -> Option<ChapterMarker> {
+    match after.first() {
+        Some('話' | '话' | '章' | '回') => return Some(ChapterMarker::Chapter),
+        Some('巻' | '卷' | '冊' | '册') => return Some(ChapterMarker::Volume),
+        Some('部') => return Some(ChapterMarker::Part),
+        _ => {}
+    }
+
+    let end = before
+        .iter()
+        .rposition(|it| !matches!(it, ' ' | '.' | '_' | '-' | '#' | ':'))
+        .map_or(0, |it| it + 1);
+    let before = &before[..end];
+    if before.last() == Some(&'第') {
+        return Some(ChapterMarker::Chapter);
+    }
+
+    let start = before
+        .iter()
+        .rposition(|it| !it.is_ascii_alphabetic())
+        .map_or(0, |it| it + 1);
+
+    match before[start..].iter().collect::<String>().as_str() {
+        "v" | "vol" | "volume" | "tome" => Some(ChapterMarker::Volume),
+        "c" | "ch" | "chap" | "chapter" | "ep" | "episode" => Some(ChapterMarker::Chapter),
+        "part" | "pt" => Some(ChapterMarker::Part),
+        _ => None,
+    }
+}
+
+// lowercase `name` with full-width characters and CJK numerals next to a marker replaced by
+// their ASCII form.
+fn normalize_numbers(name: &str) -> Vec<char> {
+    let chars = name
+        .chars()
+        .map(|it| match it {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(it as u32 - 0xFEE0).unwrap_or(it),
+            '\u{3000}' => ' ',
+            it => it,
+        })
+        .flat_map(char::to_lowercase)
+        .collect::<Vec<_>>();
+
+    let mut normalized = Vec::with_capacity(chars.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let len = chars[i..]
+            .iter()
+            .take_while(|it| cjk_numeral(**it).is_some())
+            .count();
+        if len == 0 {
+            normalized.push(chars[i]);
+            i += 1;
+            continue;
+        }
+
+        let run = &chars[i..i + len];
+        let is_marked = normalized.last() == Some(&'第')
+            || matches!(
+                chars.get(i + len),
+                Some('話' | '话' | '章' | '回' | '巻' | '卷' | '冊' | '册' | '部')
+            );
+
+        match parse_cjk_numerals(run) {
+            Some(number) if is_marked => normalized.extend(number.to_string().chars()),
+            _ => normalized.extend_from_slice(run),
+        }
+        i += len;
+    }
+
+    normalized
+}
+
+// digit or multiplier of CJK numeral.
+fn cjk_numeral(c: char) -> Option<(u64, bool)> {
+    let it = match c {
+        '〇' | '零' => (0, false),
+        '一' => (1, false),
+        '二' | '两' => (2, false),
+        '三' => (3, false),
+        '四' => (4, false),
+        '五' => (5, false),
+        '六' => (6, false),
+        '七' => (7, false),
+        '八' => (8, false),
+        '九' => (9, false),
+        '十' => (10, true),
+        '百' => (100, true),
+        '千' => (1000, true),
+        _ => return None,
+    };
+
+    Some(it)
+}
+
+fn parse_cjk_numerals(run: &[char]) -> Option<u64> {
+    let numerals = run
+        .iter()
+        .map(|it| cjk_numeral(*it))
+        .collect::<Option<Vec<_>>>()?;
+
+    // written digit by digit, e.g. 二〇.
+    if numerals.iter().all(|(_, is_multiplier)| !is_multiplier) {
+        return Some(numerals.iter().fold(0, |it, (digit, _)| it * 10 + digit));
+    }
+
+    let mut total = 0;
+    let mut digit = None;
+    for (value, is_multiplier) in numerals {
+        if is_multiplier {
+            total += digit.take().unwrap_or(1) * value;
+        } else {
+            digit = Some(value);
+        }
+    }
+
+    Some(total + digit.unwrap_or(0))
+}
+
+/// Returns `true` if `text` matches glob `pattern`, ignoring case.
+///
+/// `*` matches anything except `/`, `**` matches anything and `?` matches a single character
+/// except `/`.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_lowercase().chars().collect::<Vec<_>>();
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+
+    glob_match_chars(&pattern, &text)
+}
+
+fn glob_match_chars(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', rest @ ..] => {
+            // `**/` also matches no directory at all.
+            let skip_dir = matches!(rest, ['/', rest @ ..] if glob_match_chars(rest, text));
+            skip_dir || (0..=text.len()).any(|i| glob_match_chars(rest, &text[i..]))
+        }
+        ['*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != '/')
+            .any(|i| glob_match_chars(rest, &text[i..])),
+        ['?', rest @ ..] => {
+            matches!(text, [c, ..] if *c != '/') && glob_match_chars(rest, &text[1..])
+        }
+        [c, rest @ ..] => text.first() == Some(c) && glob_match_chars(rest, &text[1..]),
+    }
+}
+
 pub fn compare_str(
     a: &OsStr,
     b: &OsStr,
@@ -69,11 +324,117 @@ pub fn compare_str(
     }
 }
 
+/// Key paths are ordered by before [`PathSorterInner::last_modified`] and name.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PathSortKey {
+    /// Order only by last modified time and name.
+    #[default]
+    Name,
+    Size,
+    Created,
+    Extension,
+    /// Number of pages given by [`PathSortInfo`].
+    PageCount,
+    /// Reading progress given by [`PathSortInfo`], unread first.
+    Progress,
+    /// Shuffled by [`PathSorterInner::seed`].
+    Random,
+}
+
+impl PathSortKey {
+    pub const ALL: [Self; 7] = [
+        Self::Name,
+        Self::Size,
+        Self::Created,
+        Self::Extension,
+        Self::PageCount,
+        Self::Progress,
+        Self::Random,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Name => "Name",
+            Self::Size => "Size",
+            Self::Created => "Created",
+            Self::Extension => "Type",
+            Self::PageCount => "Page Count",
+            Self::Progress => "Reading Progress",
+            Self::Random => "Random",
+        }
+    }
+}
+
+/// Information about path that isn't found in its metadata, used by [`PathSortKey`].
+pub trait PathSortInfo: Send + Sync {
+    /// Number of pages of book at `path`.
+    fn page_count(&self, path: &Path) -> Option<usize>;
+    /// Read ratio of book at `path` from 0 to 1, `None` if it's unread.
+    fn progress(&self, path: &Path) -> Option<f32>;
+}
+
+/// Values of a path read from the file system and [`PathSortInfo`] that
+/// [`PathSorterInner`] orders it by.
+#[derive(Clone, Debug, Default)]
+pub struct PathSortValues {
+    // only read when directories are placed first.
+    is_dir: bool,
+    key: SortKeyValue,
+    // only read when ordered by last modified time.
+    modified: Option<SystemTime>,
+}
+
+// value of path for its `PathSortKey`.
+#[derive(Clone, Debug, Default)]
+enum SortKeyValue {
+    #[default]
+    None,
+    Size(Option<u64>),
+    Time(Option<SystemTime>),
+    Extension(Option<String>),
+    PageCount(Option<usize>),
+    Progress(f32),
+    Random(u64),
+}
+
+impl SortKeyValue {
+    // value that couldn't be read, it's placed last in both directions.
+    fn is_unknown(&self) -> bool {
+        matches!(
+            self,
+            Self::Size(None) | Self::Time(None) | Self::PageCount(None)
+        )
+    }
+
+    fn compare(&self, other: &Self) -> std::cmp::Ordering {
+        match (self, other) {
+            (Self::Size(a), Self::Size(b)) => a.cmp(b),
+            (Self::Time(a), Self::Time(b)) => a.cmp(b),
+            (Self::Extension(a), Self::Extension(b)) => a.cmp(b),
+            (Self::PageCount(a), Self::PageCount(b)) => a.cmp(b),
+            (Self::Progress(a), Self::Progress(b)) => a.total_cmp(b),
+            (Self::Random(a), Self::Random(b)) => a.cmp(b),
+            _ => std::cmp::Ordering::Equal,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct PathSorterInner {
     pub natural: bool,
     pub last_modified: bool,
     pub ascending: bool,
+    /// Natural order compares volume and chapter number, see [`compare_chapter`].
+    #[serde(default)]
+    pub chapter: bool,
+    #[serde(default)]
+    pub key: PathSortKey,
+    /// Directories are placed before files regardless of the order.
+    #[serde(default)]
+    pub directories_first: bool,
+    /// Seed of [`PathSortKey::Random`].
+    #[serde(default)]
+    pub seed: u64,
 }
 
 impl Default for PathSorterInner {
@@ -82,24 +443,114 @@ impl Default for PathSorterInner {
             natural: true,
             last_modified: false,
             ascending: true,
+            chapter: false,
+            key: PathSortKey::Name,
+            directories_first: false,
+            seed: 0,
         }
     }
 }
 
 impl PathSorterInner {
-    pub fn compare_path(&self, a: &Path, b: &Path) -> std::cmp::Ordering {
-        let (a, b) = if self.ascending { (a, b) } else { (b, a) };
-
-        if self.last_modified {
-            let ord = compare_last_modified(a, b);
+    /// Sort `paths` with `info` used by [`PathSortKey::PageCount`] and
+    /// [`PathSortKey::Progress`], they're ignored without it. Values of each path are read
+    /// once instead of on every comparison, it still reads the file system so it should be
+    /// called outside of the UI thread.
+    pub fn sort_paths_with(&self, paths: &mut Vec<PathBuf>, info: Option<&dyn PathSortInfo>) {
+        let mut sorted = std::mem::take(paths)
+            .into_iter()
+            .map(|it| (self.values(&it, info), it))
+            .collect::<Vec<_>>();
+        sorted.sort_by(|(a_values, a), (b_values, b)| {
+            self.compare_values((a, a_values), (b, b_values))
+        });
+
+        paths.extend(sorted.into_iter().map(|(_, it)| it));
+    }
 
-            if !ord.is_eq() {
-                return ord;
+    /// Values `path` is ordered by with this order, see [`compare_values`].
+    ///
+    /// [`compare_values`]: Self::compare_values
+    pub fn values(&self, path: &Path, info: Option<&dyn PathSortInfo>) -> PathSortValues {
+        let needs_metadata =
+            self.directories_first || self.last_modified || self.key == PathSortKey::Created;
+        let metadata = needs_metadata.then(|| path.metadata().ok()).flatten();
+        let key = match (self.key, info) {
+            (PathSortKey::Name, _) => SortKeyValue::None,
+            (PathSortKey::Size, _) => SortKeyValue::Size(path_size(path)),
+            (PathSortKey::Created, _) => {
+                SortKeyValue::Time(metadata.as_ref().and_then(|it| it.created().ok()))
             }
+            (PathSortKey::Extension, _) => SortKeyValue::Extension(extension_of(path)),
+            (PathSortKey::PageCount, Some(info)) => SortKeyValue::PageCount(info.page_count(path)),
+            (PathSortKey::Progress, Some(info)) => {
+                SortKeyValue::Progress(info.progress(path).unwrap_or(-1.0))
+            }
+            (PathSortKey::Random, _) => SortKeyValue::Random(shuffled(self.seed, path)),
+            (PathSortKey::PageCount | PathSortKey::Progress, None) => SortKeyValue::None,
+        };
+
+        PathSortValues {
+            is_dir: self.directories_first && metadata.as_ref().is_some_and(|it| it.is_dir()),
+            key,
+            modified: self
+                .last_modified
+                .then(|| metadata.and_then(|it| it.modified().ok()))
+                .flatten(),
+        }
+    }
+
+    /// Compare paths by their values read by [`values`] with this order.
+    ///
+    /// [`values`]: Self::values
+    pub fn compare_values(
+        &self,
+        (a, a_values): (&Path, &PathSortValues),
+        (b, b_values): (&Path, &PathSortValues),
+    ) -> std::cmp::Ordering {
+        let ord = b_values.is_dir.cmp(&a_values.is_dir);
+        if !ord.is_eq() {
+            return ord;
         }
 
+        let directed = |ord: std::cmp::Ordering| if self.ascending { ord } else { ord.reverse() };
+
+        // values that can't be read are placed last regardless of the direction,
+        // so the order stays transitive.
+        let ord = a_values
+            .key
+            .is_unknown()
+            .cmp(&b_values.key.is_unknown())
+            .then_with(|| directed(a_values.key.compare(&b_values.key)))
+            .then_with(|| {
+                a_values
+                    .modified
+                    .is_none()
+                    .cmp(&b_values.modified.is_none())
+            })
+            .then_with(|| directed(a_values.modified.cmp(&b_values.modified)));
+        if !ord.is_eq() {
+            return ord;
+        }
+
+        directed(self.compare_file_name(a, b))
+    }
+
+    /// Compare `a` and `b` only by their name, the key and last modified time are ignored
+    /// so it doesn't read the file system and the order stays the same between calls.
+    pub fn compare_path_name(&self, a: &Path, b: &Path) -> std::cmp::Ordering {
+        let (a, b) = if self.ascending { (a, b) } else { (b, a) };
+        self.compare_file_name(a, b)
+    }
+
+    fn compare_file_name(&self, a: &Path, b: &Path) -> std::cmp::Ordering {
         if self.natural {
-            let ord = compare_natural(a.file_name().unwrap(), b.file_name().unwrap());
+            let (a, b) = (a.file_name().unwrap(), b.file_name().unwrap());
+            let ord = if self.chapter {
+                compare_chapter(a, b)
+            } else {
+                compare_natural(a, b)
+            };
 
             if !ord.is_eq() {
                 return ord;
@@ -108,6 +559,15 @@ impl PathSorterInner {
 
         a.cmp(b)
     }
+
+    /// Compare page name in archive or folder, it's always ascending.
+    pub fn compare_name(&self, a: &str, b: &str) -> std::cmp::Ordering {
+        match (self.natural, self.chapter) {
+            (true, true) => compare_chapter_str(a, b),
+            (true, false) => natord::compare_ignore_case(a, b),
+            (false, _) => a.cmp(b),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -164,6 +624,10 @@ impl Default for PathSorterSetting {
                 natural: true,
                 last_modified: false,
                 ascending: true,
+                chapter: false,
+                key: PathSortKey::Name,
+                directories_first: false,
+                seed: 0,
             },
 
             subscriber: PathSorterSender::default(),
@@ -171,6 +635,90 @@ impl Default for PathSorterSetting {
     }
 }
 
+/// Copy file or directory with its content from `from` to `to`, which shouldn't exist.
+pub fn copy_recursive(from: &Path, to: &Path) -> std::io::Result<()> {
+    if !from.is_dir() {
+        return std::fs::copy(from, to).map(|_| ());
+    }
+
+    std::fs::create_dir(to)?;
+    for it in std::fs::read_dir(from)? {
+        let it = it?;
+        copy_recursive(&it.path(), &to.join(it.file_name()))?;
+    }
+
+    Ok(())
+}
+
+/// Move `from` to `to`, it's copied then removed when it can't be renamed, e.g. `to` is in
+/// another file system. Partial copy is removed when copying fails.
+pub fn move_path(from: &Path, to: &Path) -> std::io::Result<()> {
+    if to.exists() {
+        return Err(std::io::ErrorKind::AlreadyExists.into());
+    }
+
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    if let Err(err) = copy_recursive(from, to) {
+        let removed = if to.is_dir() {
+            std::fs::remove_dir_all(to)
+        } else {
+            std::fs::remove_file(to)
+        };
+        if let Err(err) = removed {
+            log::warn!("failed to remove partial copy {:?}: {}", to, err);
+        }
+
+        return Err(err);
+    }
+
+    if from.is_dir() {
+        std::fs::remove_dir_all(from)
+    } else {
+        std::fs::remove_file(from)
+    }
+}
+
+/// Size of file, or total size of files directly inside directory.
+pub fn path_size(path: &Path) -> Option<u64> {
+    let metadata = path.metadata().ok()?;
+    if !metadata.is_dir() {
+        return Some(metadata.len());
+    }
+
+    let size = std::fs::read_dir(path)
+        .ok()?
+        .flatten()
+        .filter_map(|it| it.metadata().ok())
+        .filter(|it| it.is_file())
+        .map(|it| it.len())
+        .sum();
+
+    Some(size)
+}
+
+/// Compare extension ignoring case, path without extension is ordered first.
+pub fn compare_extension(a: &Path, b: &Path) -> std::cmp::Ordering {
+    extension_of(a).cmp(&extension_of(b))
+}
+
+fn extension_of(path: &Path) -> Option<String> {
+    path.extension()
+        .map(|it| it.to_string_lossy().to_lowercase())
+}
+
+// position of path in order shuffled by seed.
+fn shuffled(seed: u64, path: &Path) -> u64 {
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    seed.hash(&mut hasher);
+    path.hash(&mut hasher);
+    hasher.finish()
+}
+
 pub fn compare_last_modified(a: &Path, b: &Path) -> std::cmp::Ordering {
     let a = a.metadata().and_then(|it| it.modified());
     let b = b.metadata().and_then(|it| it.modified());
@@ -183,19 +731,87 @@ pub fn compare_last_modified(a: &Path, b: &Path) -> std::cmp::Ordering {
     a.cmp(&b)
 }
 
-pub struct PathSorterSubscriber(tokio::sync::watch::Receiver<PathSorterInner>);
+#[derive(Clone)]
+pub struct PathSorterSubscriber {
+    receiver: tokio::sync::watch::Receiver<PathSorterInner>,
+    info: Option<Arc<dyn PathSortInfo>>,
+}
+
+impl Default for PathSorterSubscriber {
+    /// Subscriber of the default order that never changes.
+    fn default() -> Self {
+        PathSorterSetting::default().subscribe()
+    }
+}
 
 impl PathSorterSubscriber {
-    pub fn compare_path(&self, a: &Path, b: &Path) -> std::cmp::Ordering {
-        self.0.borrow().compare_path(a, b)
+    pub fn compare_name(&self, a: &str, b: &str) -> std::cmp::Ordering {
+        self.receiver.borrow().compare_name(a, b)
+    }
+
+    /// Sort `paths` by the current order, see [`PathSorterInner::sort_paths_with`].
+    pub fn sort_paths(&self, paths: &mut Vec<PathBuf>) {
+        let inner = self.inner();
+        inner.sort_paths_with(paths, self.info.as_deref());
+    }
+
+    /// Values `path` is ordered by with the current order, see [`PathSorterInner::values`].
+    pub fn values(&self, path: &Path) -> PathSortValues {
+        let inner = self.inner();
+        inner.values(path, self.info.as_deref())
+    }
+
+    /// Compare paths by their values read by [`values`], see
+    /// [`PathSorterInner::compare_values`].
+    ///
+    /// [`values`]: Self::values
+    pub fn compare_values(
+        &self,
+        a: (&Path, &PathSortValues),
+        b: (&Path, &PathSortValues),
+    ) -> std::cmp::Ordering {
+        self.receiver.borrow().compare_values(a, b)
+    }
+
+    /// Compare paths only by their name, see [`PathSorterInner::compare_path_name`].
+    pub fn compare_path_name(&self, a: &Path, b: &Path) -> std::cmp::Ordering {
+        self.receiver.borrow().compare_path_name(a, b)
+    }
+
+    /// The current order.
+    pub fn inner(&self) -> PathSorterInner {
+        self.receiver.borrow().clone()
+    }
+
+    /// Number of pages of book at `path` given by [`PathSortInfo`].
+    pub fn page_count(&self, path: &Path) -> Option<usize> {
+        self.info.as_ref()?.page_count(path)
+    }
+
+    /// Wait until the order is changed, `false` if the setting is dropped.
+    pub async fn changed(&mut self) -> bool {
+        self.receiver.changed().await.is_ok()
     }
 }
 
 impl PathSorterSetting {
     pub fn subscribe(&self) -> PathSorterSubscriber {
-        let it = self.subscriber.0.subscribe();
+        let receiver = self.subscriber.0.subscribe();
         self.sync();
-        PathSorterSubscriber(it)
+        PathSorterSubscriber {
+            receiver,
+            info: None,
+        }
+    }
+
+    /// Same as [`subscribe`] but page count and progress are given by `info`.
+    ///
+    /// [`subscribe`]: Self::subscribe
+    pub fn subscribe_with(&self, info: Arc<dyn PathSortInfo>) -> PathSorterSubscriber {
+        PathSorterSubscriber {
+            info: Some(info),
+            ..self.subscribe()
+        }
     }
 
     pub fn sync(&self) {
@@ -204,8 +820,8 @@ impl PathSorterSetting {
         }
     }
 
-    pub fn compare_path(&self, a: &Path, b: &Path) -> std::cmp::Ordering {
-        self.inner.compare_path(a, b)
+    pub fn inner(&self) -> &PathSorterInner {
+        &self.inner
     }
 
     pub fn natural_mut(&mut self) -> &mut bool {
@@ -219,6 +835,31 @@ impl PathSorterSetting {
     pub fn ascending_mut(&mut self) -> &mut bool {
         &mut self.inner.ascending
     }
+
+    /// Replace the whole order, e.g. it's derived from another setting.
+    pub fn set_inner(&mut self, inner: PathSorterInner) {
+        self.inner = inner;
+    }
+
+    pub fn chapter_mut(&mut self) -> &mut bool {
+        &mut self.inner.chapter
+    }
+
+    pub fn key_mut(&mut self) -> &mut PathSortKey {
+        &mut self.inner.key
+    }
+
+    pub fn directories_first_mut(&mut self) -> &mut bool {
+        &mut self.inner.directories_first
+    }
+
+    /// Shuffle [`PathSortKey::Random`] order again.
+    pub fn reshuffle(&mut self) {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |it| it.as_nanos());
+        self.inner.seed = nanos as u64;
+    }
 }
 
 pub mod path_serde {
@@ -258,3 +899,95 @@ pub mod path_serde {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cmp::Ordering;
+
+    fn key(volume: Option<f64>, chapter: Option<f64>, part: Option<f64>) -> ChapterKey {
+        ChapterKey {
+            volume,
+            chapter,
+            part,
+        }
+    }
+
+    #[test]
+    fn chapter_with_fraction() {
+        assert_eq!(ChapterKey::parse("Ch. 10.5"), key(None, Some(10.5), None));
+        assert_eq!(compare_chapter_str("Ch. 10.5", "Ch. 100"), Ordering::Less);
+        assert_eq!(compare_chapter_str("Ch. 10", "Ch. 10.5"), Ordering::Less);
+        assert_eq!(compare_chapter_str("Ch. 9", "Ch. 10"), Ordering::Less);
+    }
+
+    #[test]
+    fn volume_and_chapter_aliases() {
+        let expected = key(Some(2.0), Some(3.0), None);
+        assert_eq!(ChapterKey::parse("v02c003"), expected);
+        assert_eq!(ChapterKey::parse("Vol.2 Ch.3"), expected);
+        assert_eq!(ChapterKey::parse("Volume 2 - Chapter 3"), expected);
+        assert_eq!(
+            ChapterKey::parse("v02c003").cmp(&ChapterKey::parse("Vol.2 Ch.3")),
+            Ordering::Equal
+        );
+
+        assert_eq!(compare_chapter_str("v01c010", "Vol.2 Ch.1"), Ordering::Less);
+        assert_eq!(
+            ChapterKey::parse("Vol.1 Ch.2 Part 2"),
+            key(Some(1.0), Some(2.0), Some(2.0))
+        );
+        assert_eq!(
+            compare_chapter_str("Vol.1 Ch.2 Part 2", "Vol.1 Ch.2 Part 10"),
+            Ordering::Less
+        );
+    }
+
+    #[test]
+    fn cjk_and_full_width_numbers() {
+        assert_eq!(ChapterKey::parse("第十話"), key(None, Some(10.0), None));
+        assert_eq!(ChapterKey::parse("第二十一話"), key(None, Some(21.0), None));
+        assert_eq!(ChapterKey::parse("第１２話"), key(None, Some(12.0), None));
+        assert_eq!(ChapterKey::parse("３巻"), key(Some(3.0), None, None));
+        assert_eq!(ChapterKey::parse("二〇巻"), key(Some(20.0), None, None));
+        assert_eq!(
+            ChapterKey::parse("第2巻 第百五話"),
+            key(Some(2.0), Some(105.0), None)
+        );
+
+        assert_eq!(compare_chapter_str("第三話", "第十話"), Ordering::Less);
+        assert_eq!(compare_chapter_str("第九話", "第１０話"), Ordering::Less);
+    }
+
+    #[test]
+    fn normalize_full_width_and_marked_numerals() {
+        let normalize = |it: &str| normalize_numbers(it).into_iter().collect::<String>();
+
+        assert_eq!(normalize("Ｃｈ．０５"), "ch.05");
+        assert_eq!(normalize("第十二話"), "第12話");
+        assert_eq!(normalize("三巻"), "3巻");
+        assert_eq!(normalize("第二〇〇話"), "第200話");
+        // numerals without a marker are part of the title.
+        assert_eq!(normalize("一二三"), "一二三");
+        assert_eq!(normalize("A\u{3000}B"), "a b");
+    }
+
+    #[test]
+    fn fallback_without_marker() {
+        // the first number is the chapter.
+        assert_eq!(
+            ChapterKey::parse("page 12 of 30"),
+            key(None, Some(12.0), None)
+        );
+        assert_eq!(compare_chapter_str("page 9", "page 10"), Ordering::Less);
+
+        // same or no number is ordered naturally, ignoring case.
+        assert_eq!(ChapterKey::parse("cover"), ChapterKey::default());
+        assert_eq!(compare_chapter_str("Alpha", "beta"), Ordering::Less);
+        assert_eq!(compare_chapter_str("b 1 x2", "B 1 x10"), Ordering::Less);
+        assert_eq!(compare_chapter_str("Cover", "cover"), Ordering::Less);
+
+        // name without the number is ordered last.
+        assert_eq!(compare_chapter_str("cover", "1"), Ordering::Greater);
+    }
+}
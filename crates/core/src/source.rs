@@ -0,0 +1,362 @@
+use std::{
+    collections::HashMap,
+    io::Read,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::path::NameSorterType;
+
+/// File listed by [`PageSource`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PageEntry {
+    /// name relative to the source, separated by `/`.
+    pub name: String,
+    pub size: Option<u64>,
+    pub modified: Option<SystemTime>,
+}
+
+impl PageEntry {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            ..Default::default()
+        }
+    }
+}
+
+/// Error of reading an encrypted entry without its password or with a wrong one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PasswordRequired;
+
+impl PasswordRequired {
+    pub fn error() -> std::io::Error {
+        std::io::Error::new(std::io::ErrorKind::PermissionDenied, Self)
+    }
+
+    /// Returns `true` if `err` is [`PasswordRequired`].
+    pub fn is(err: &std::io::Error) -> bool {
+        err.get_ref().is_some_and(|it| it.is::<Self>())
+    }
+}
+
+impl std::fmt::Display for PasswordRequired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "password required")
+    }
+}
+
+impl std::error::Error for PasswordRequired {}
+
+/// Where pages of a book are read from, e.g. a folder or an archive.
+///
+/// Implement this to let loaders read pages from another container format.
+pub trait PageSource: Send {
+    /// Every file in the source, directory isn't listed.
+    fn entries(&mut self) -> std::io::Result<Vec<PageEntry>>;
+
+    /// Read the whole content of entry `name`, encrypted entry fails with
+    /// [`PasswordRequired`] until the right password is set.
+    fn read(&mut self, name: &str) -> std::io::Result<Vec<u8>>;
+
+    /// Read at most `len` bytes from the start of entry `name`, e.g. to find the size of an
+    /// image from its header.
+    fn read_head(&mut self, name: &str, len: usize) -> std::io::Result<Vec<u8>> {
+        let mut data = self.read(name)?;
+        data.truncate(len);
+        Ok(data)
+    }
+
+    /// Set password used to read encrypted entries.
+    fn set_password(&mut self, _password: &str) {}
+
+    /// Path of entry `name` in file system, loader opens the file directly when it's `Some`
+    /// instead of reading it into memory.
+    fn local_path(&self, _name: &str) -> Option<PathBuf> {
+        None
+    }
+
+    /// Another handle to this source that reads independently of it, used to read entries
+    /// from different threads. `None` if every read has to go through this source.
+    fn try_clone(&self) -> Option<Box<dyn PageSource>> {
+        None
+    }
+
+    /// Archive entry `name` is read from when it isn't the one the source is opened from,
+    /// e.g. a part of [`CombinedSource`]. Password of the entry is keyed by it.
+    fn archive_of(&self, _name: &str) -> Option<PathBuf> {
+        None
+    }
+}
+
+impl<S: PageSource + ?Sized> PageSource for Box<S> {
+    fn entries(&mut self) -> std::io::Result<Vec<PageEntry>> {
+        (**self).entries()
+    }
+
+    fn read(&mut self, name: &str) -> std::io::Result<Vec<u8>> {
+        (**self).read(name)
+    }
+
+    fn read_head(&mut self, name: &str, len: usize) -> std::io::Result<Vec<u8>> {
+        (**self).read_head(name, len)
+    }
+
+    fn set_password(&mut self, password: &str) {
+        (**self).set_password(password)
+    }
+
+    fn local_path(&self, name: &str) -> Option<PathBuf> {
+        (**self).local_path(name)
+    }
+
+    fn try_clone(&self) -> Option<Box<dyn PageSource>> {
+        (**self).try_clone()
+    }
+
+    fn archive_of(&self, name: &str) -> Option<PathBuf> {
+        (**self).archive_of(name)
+    }
+}
+
+/// Files directly inside a folder.
+#[derive(Clone, Debug)]
+pub struct FolderSource {
+    path: PathBuf,
+    // listed file by its lossy name, for file name that isn't valid UTF-8.
+    files: HashMap<String, PathBuf>,
+}
+
+impl FolderSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            files: HashMap::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn file(&self, name: &str) -> PathBuf {
+        match self.files.get(name) {
+            Some(path) => path.clone(),
+            None => self.path.join(name),
+        }
+    }
+}
+
+impl PageSource for FolderSource {
+    fn entries(&mut self) -> std::io::Result<Vec<PageEntry>> {
+        let mut entries = vec![];
+        for it in std::fs::read_dir(&self.path)?.flatten() {
+            // follow symlink.
+            let Ok(metadata) = std::fs::metadata(it.path()) else {
+                continue;
+            };
+
+            if metadata.is_file() {
+                let name = it.file_name().to_string_lossy().to_string();
+                self.files.insert(name.clone(), it.path());
+                entries.push(PageEntry {
+                    name,
+                    size: Some(metadata.len()),
+                    modified: metadata.modified().ok(),
+                });
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn read(&mut self, name: &str) -> std::io::Result<Vec<u8>> {
+        std::fs::read(self.file(name))
+    }
+
+    fn read_head(&mut self, name: &str, len: usize) -> std::io::Result<Vec<u8>> {
+        let mut data = vec![];
+        let file = std::fs::File::open(self.file(name))?;
+        file.take(len as u64).read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    fn local_path(&self, name: &str) -> Option<PathBuf> {
+        Some(self.file(name))
+    }
+
+    fn try_clone(&self) -> Option<Box<dyn PageSource>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// Pages of several sources one after another, e.g. books read as one.
+///
+/// Entry is named by index of its source followed by its name in the source, entries of
+/// each source are ordered by the sorter. Source that can't be listed is skipped.
+pub struct CombinedSource {
+    // archive or folder each source is opened from.
+    parts: Vec<(PathBuf, Box<dyn PageSource>)>,
+    sorter: Option<NameSorterType>,
+}
+
+impl CombinedSource {
+    /// Combine sources opened from the paths, in the given order.
+    pub fn new(parts: Vec<(PathBuf, Box<dyn PageSource>)>) -> Self {
+        Self {
+            parts,
+            sorter: None,
+        }
+    }
+
+    /// Set order of entries inside each source.
+    pub fn with_sorter(mut self, sorter: NameSorterType) -> Self {
+        self.sorter = Some(sorter);
+        self
+    }
+
+    // source and its entry name of combined `name`.
+    fn part(name: &str) -> Option<(usize, &str)> {
+        let (index, name) = name.split_once('/')?;
+        Some((index.parse().ok()?, name))
+    }
+
+    fn part_mut(&mut self, name: &str) -> std::io::Result<(&mut Box<dyn PageSource>, String)> {
+        let (index, name) =
+            Self::part(name).ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound))?;
+
+        match self.parts.get_mut(index) {
+            Some((_, part)) => Ok((part, name.to_string())),
+            None => Err(std::io::ErrorKind::NotFound.into()),
+        }
+    }
+}
+
+impl PageSource for CombinedSource {
+    fn entries(&mut self) -> std::io::Result<Vec<PageEntry>> {
+        let mut combined = vec![];
+        let mut error = None;
+        for (i, (path, part)) in self.parts.iter_mut().enumerate() {
+            let mut entries = match part.entries() {
+                Ok(entries) => entries,
+                Err(err) => {
+                    log::warn!("skipping {:?} that can't be listed: {}", path, err);
+                    error = Some(err);
+                    continue;
+                }
+            };
+            if let Some(sorter) = &self.sorter {
+                entries.sort_by(|a, b| sorter(&a.name, &b.name));
+            }
+
+            combined.extend(entries.into_iter().map(|it| PageEntry {
+                name: format!("{i:04}/{}", it.name),
+                ..it
+            }));
+        }
+
+        // the book fails only when none of its parts can be read.
+        match error {
+            Some(err) if combined.is_empty() => Err(err),
+            _ => Ok(combined),
+        }
+    }
+
+    fn read(&mut self, name: &str) -> std::io::Result<Vec<u8>> {
+        let (part, name) = self.part_mut(name)?;
+        part.read(&name)
+    }
+
+    fn read_head(&mut self, name: &str, len: usize) -> std::io::Result<Vec<u8>> {
+        let (part, name) = self.part_mut(name)?;
+        part.read_head(&name, len)
+    }
+
+    fn set_password(&mut self, password: &str) {
+        for (_, it) in &mut self.parts {
+            it.set_password(password);
+        }
+    }
+
+    fn local_path(&self, name: &str) -> Option<PathBuf> {
+        let (index, name) = Self::part(name)?;
+        self.parts.get(index)?.1.local_path(name)
+    }
+
+    fn try_clone(&self) -> Option<Box<dyn PageSource>> {
+        let parts = self
+            .parts
+            .iter()
+            .map(|(path, it)| Some((path.clone(), it.try_clone()?)))
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Box::new(Self {
+            parts,
+            sorter: self.sorter.clone(),
+        }))
+    }
+
+    fn archive_of(&self, name: &str) -> Option<PathBuf> {
+        let (index, name) = Self::part(name)?;
+        let (path, part) = self.parts.get(index)?;
+        part.archive_of(name).or_else(|| Some(path.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // source with entries `names`, it fails to list when there's none.
+    struct Names(Vec<&'static str>);
+
+    impl PageSource for Names {
+        fn entries(&mut self) -> std::io::Result<Vec<PageEntry>> {
+            if self.0.is_empty() {
+                return Err(std::io::ErrorKind::InvalidData.into());
+            }
+
+            Ok(self
+                .0
+                .iter()
+                .map(|it| PageEntry::new(it.to_string()))
+                .collect())
+        }
+
+        fn read(&mut self, name: &str) -> std::io::Result<Vec<u8>> {
+            Ok(name.as_bytes().to_vec())
+        }
+    }
+
+    fn part(path: &str, names: Vec<&'static str>) -> (PathBuf, Box<dyn PageSource>) {
+        (PathBuf::from(path), Box::new(Names(names)))
+    }
+
+    #[test]
+    fn combined_skips_failing_part() {
+        let mut source = CombinedSource::new(vec![
+            part("a.zip", vec!["1.jpg"]),
+            part("broken.zip", vec![]),
+            part("c.zip", vec!["2.jpg"]),
+        ]);
+
+        let names = source.entries().unwrap().into_iter().map(|it| it.name);
+        assert_eq!(names.collect::<Vec<_>>(), ["0000/1.jpg", "0002/2.jpg"]);
+        assert_eq!(source.read("0002/2.jpg").unwrap(), b"2.jpg");
+
+        let mut source = CombinedSource::new(vec![part("broken.zip", vec![])]);
+        assert!(source.entries().is_err());
+    }
+
+    #[test]
+    fn combined_keys_password_by_part() {
+        let source = CombinedSource::new(vec![
+            part("a.zip", vec!["1.jpg"]),
+            part("b.zip", vec!["1.jpg"]),
+        ]);
+
+        assert_eq!(source.archive_of("0000/1.jpg"), Some("a.zip".into()));
+        assert_eq!(source.archive_of("0001/1.jpg"), Some("b.zip".into()));
+        assert_eq!(source.archive_of("0002/1.jpg"), None);
+    }
+}
@@ -0,0 +1,391 @@
+use std::path::{Path, PathBuf};
+
+use tokio::sync::mpsc;
+
+/// Change of an entry directly inside the watched directory.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DirChange {
+    Created(PathBuf),
+    Removed(PathBuf),
+    /// entry is renamed within the directory.
+    Renamed {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    /// file is written and closed.
+    Modified(PathBuf),
+    /// changes are lost, e.g. too many of them happened at once, the whole directory
+    /// should be read again.
+    Rescan,
+}
+
+/// Watch entries directly inside a directory, changes happened close together are
+/// received at once. The watch stops when it's dropped.
+pub struct DirWatcher {
+    receiver: mpsc::Receiver<Vec<DirChange>>,
+}
+
+impl DirWatcher {
+    /// Start watching `path`, it's only supported on linux.
+    pub fn new(path: &Path) -> std::io::Result<Self> {
+        let (sender, receiver) = mpsc::channel(16);
+        inotify::watch(path, sender)?;
+
+        Ok(Self { receiver })
+    }
+
+    /// Wait for next changes, `None` if the directory can't be watched anymore, e.g. it's
+    /// removed.
+    pub async fn changed(&mut self) -> Option<Vec<DirChange>> {
+        self.receiver.recv().await
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod inotify {
+    use std::{
+        collections::{HashMap, HashSet},
+        ffi::{CString, OsStr},
+        os::{
+            fd::{AsRawFd, FromRawFd, OwnedFd},
+            unix::ffi::OsStrExt,
+        },
+        path::{Path, PathBuf},
+        time::Instant,
+    };
+
+    use tokio::sync::mpsc;
+
+    use super::DirChange;
+
+    // how long to wait for more events before changes are sent.
+    const DEBOUNCE_MS: i32 = 100;
+    // changes are sent at least this often while events keep coming, e.g. downloading.
+    const MAX_BATCH_MS: i32 = 1000;
+    // how often to check whether the watcher is dropped.
+    const CLOSED_CHECK_MS: i32 = 500;
+
+    pub fn watch(path: &Path, sender: mpsc::Sender<Vec<DirChange>>) -> std::io::Result<()> {
+        let name = CString::new(path.as_os_str().as_bytes())?;
+
+        // SAFETY: no pointer is passed.
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        // SAFETY: `fd` is a new descriptor owned by nothing else, it's closed when dropped.
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let mask = libc::IN_CREATE
+            | libc::IN_DELETE
+            | libc::IN_MOVED_FROM
+            | libc::IN_MOVED_TO
+            | libc::IN_CLOSE_WRITE
+            | libc::IN_DELETE_SELF
+            | libc::IN_MOVE_SELF
+            | libc::IN_ONLYDIR;
+        // SAFETY: `name` is a valid nul terminated string.
+        let wd = unsafe { libc::inotify_add_watch(fd.as_raw_fd(), name.as_ptr(), mask) };
+        if wd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let path = path.to_path_buf();
+        std::thread::Builder::new()
+            .name("dir-watcher".to_string())
+            .spawn(move || run(fd, path, sender))?;
+
+        Ok(())
+    }
+
+    fn run(fd: OwnedFd, path: PathBuf, sender: mpsc::Sender<Vec<DirChange>>) {
+        let mut buffer = vec![0u8; 4096];
+        // entries moved out whose pair may come in the next batch.
+        let mut moved_from = HashMap::new();
+
+        loop {
+            let mut changes = vec![];
+            let mut is_gone = false;
+            let carried = moved_from.keys().copied().collect::<HashSet<_>>();
+
+            let mut started = None;
+            let mut timeout = CLOSED_CHECK_MS;
+            while poll(&fd, timeout) {
+                // SAFETY: `buffer` is valid for `buffer.len()` bytes.
+                let len =
+                    unsafe { libc::read(fd.as_raw_fd(), buffer.as_mut_ptr().cast(), buffer.len()) };
+                if len <= 0 {
+                    break;
+                }
+
+                is_gone |= parse(
+                    &buffer[..len as usize],
+                    &path,
+                    &mut changes,
+                    &mut moved_from,
+                );
+
+                let age = started
+                    .get_or_insert_with(Instant::now)
+                    .elapsed()
+                    .as_millis();
+                let left = MAX_BATCH_MS.saturating_sub(age.try_into().unwrap_or(i32::MAX));
+                if left <= 0 {
+                    break;
+                }
+                timeout = DEBOUNCE_MS.min(left);
+            }
+
+            changes.extend(take_moved_out(&mut moved_from, &carried));
+
+            if sender.is_closed() || is_gone {
+                return;
+            }
+            if !changes.is_empty() && sender.blocking_send(changes).is_err() {
+                return;
+            }
+        }
+    }
+
+    // entries moved out in the previous batch whose pair hasn't come are removed, the
+    // others wait for one more batch.
+    fn take_moved_out(
+        moved_from: &mut HashMap<u32, PathBuf>,
+        carried: &HashSet<u32>,
+    ) -> Vec<DirChange> {
+        let mut removed = vec![];
+        moved_from.retain(|cookie, path| {
+            let is_out = carried.contains(cookie);
+            if is_out {
+                removed.push(DirChange::Removed(std::mem::take(path)));
+            }
+            !is_out
+        });
+        removed
+    }
+
+    // returns true if `fd` is readable within `timeout` milliseconds.
+    fn poll(fd: &OwnedFd, timeout: i32) -> bool {
+        let mut pollfd = libc::pollfd {
+            fd: fd.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+
+        // SAFETY: `pollfd` is valid for one element.
+        let ready = unsafe { libc::poll(&mut pollfd, 1, timeout) };
+        ready > 0 && pollfd.revents & libc::POLLIN != 0
+    }
+
+    // parse events in `buffer` into `changes`, returns true if the directory is removed.
+    fn parse(
+        buffer: &[u8],
+        path: &Path,
+        changes: &mut Vec<DirChange>,
+        moved_from: &mut HashMap<u32, PathBuf>,
+    ) -> bool {
+        let header = std::mem::size_of::<libc::inotify_event>();
+        let mut is_gone = false;
+        let mut offset = 0;
+
+        while offset + header <= buffer.len() {
+            // SAFETY: the kernel writes whole events, `header` bytes are in the buffer.
+            let event = unsafe {
+                std::ptr::read_unaligned(buffer[offset..].as_ptr().cast::<libc::inotify_event>())
+            };
+            let name_start = offset + header;
+            let name_end = (name_start + event.len as usize).min(buffer.len());
+            offset = name_end;
+
+            // the name is padded with nul.
+            let name = &buffer[name_start..name_end];
+            let name = match name.iter().position(|it| *it == 0) {
+                Some(end) => &name[..end],
+                None => name,
+            };
+            let child = path.join(OsStr::from_bytes(name));
+
+            let mask = event.mask;
+            if mask & libc::IN_Q_OVERFLOW != 0 {
+                changes.push(DirChange::Rescan);
+            } else if mask & (libc::IN_DELETE_SELF | libc::IN_MOVE_SELF | libc::IN_IGNORED) != 0 {
+                is_gone = true;
+            } else if mask & libc::IN_CREATE != 0 {
+                changes.push(DirChange::Created(child));
+            } else if mask & libc::IN_DELETE != 0 {
+                changes.push(DirChange::Removed(child));
+            } else if mask & libc::IN_MOVED_FROM != 0 {
+                moved_from.insert(event.cookie, child);
+            } else if mask & libc::IN_MOVED_TO != 0 {
+                changes.push(match moved_from.remove(&event.cookie) {
+                    Some(from) => DirChange::Renamed { from, to: child },
+                    None => DirChange::Created(child),
+                });
+            } else if mask & libc::IN_CLOSE_WRITE != 0 {
+                changes.push(DirChange::Modified(child));
+            }
+        }
+
+        is_gone
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        // inotify event of `name` padded with nul like the kernel does.
+        fn event(mask: u32, cookie: u32, name: &str) -> Vec<u8> {
+            let len = match name.len() {
+                0 => 0,
+                len => (len + 1).next_multiple_of(4),
+            };
+            let header = libc::inotify_event {
+                wd: 1,
+                mask,
+                cookie,
+                len: len as u32,
+            };
+
+            let mut buffer = vec![0u8; std::mem::size_of::<libc::inotify_event>()];
+            // SAFETY: `buffer` is valid for one unaligned event.
+            unsafe { std::ptr::write_unaligned(buffer.as_mut_ptr().cast(), header) };
+
+            let mut name = name.as_bytes().to_vec();
+            name.resize(len, 0);
+            buffer.extend(name);
+            buffer
+        }
+
+        fn parse_all(events: &[Vec<u8>]) -> (Vec<DirChange>, HashMap<u32, PathBuf>, bool) {
+            let (mut changes, mut moved_from) = (vec![], HashMap::new());
+            let is_gone = parse(
+                &events.concat(),
+                Path::new("/dir"),
+                &mut changes,
+                &mut moved_from,
+            );
+            (changes, moved_from, is_gone)
+        }
+
+        #[test]
+        fn parse_changes() {
+            let (changes, moved_from, is_gone) = parse_all(&[
+                event(libc::IN_CREATE, 0, "a.zip"),
+                event(libc::IN_CLOSE_WRITE, 0, "a.zip"),
+                event(libc::IN_DELETE, 0, "long name.cbz"),
+            ]);
+
+            assert_eq!(
+                changes,
+                vec![
+                    DirChange::Created("/dir/a.zip".into()),
+                    DirChange::Modified("/dir/a.zip".into()),
+                    DirChange::Removed("/dir/long name.cbz".into()),
+                ]
+            );
+            assert!(moved_from.is_empty());
+            assert!(!is_gone);
+        }
+
+        #[test]
+        fn parse_rename() {
+            let (changes, moved_from, _) = parse_all(&[
+                event(libc::IN_MOVED_FROM, 7, "old"),
+                event(libc::IN_MOVED_TO, 7, "new"),
+                event(libc::IN_MOVED_TO, 8, "moved in"),
+                event(libc::IN_MOVED_FROM, 9, "moved out"),
+            ]);
+
+            assert_eq!(
+                changes,
+                vec![
+                    DirChange::Renamed {
+                        from: "/dir/old".into(),
+                        to: "/dir/new".into()
+                    },
+                    DirChange::Created("/dir/moved in".into()),
+                ]
+            );
+            // moved out is removed once no event pairs with it.
+            assert_eq!(moved_from.get(&9), Some(&PathBuf::from("/dir/moved out")));
+        }
+
+        #[test]
+        fn move_waits_for_next_batch() {
+            let (mut changes, mut moved_from) = (vec![], HashMap::new());
+            let path = Path::new("/dir");
+            parse(
+                &event(libc::IN_MOVED_FROM, 1, "a"),
+                path,
+                &mut changes,
+                &mut moved_from,
+            );
+            assert!(take_moved_out(&mut moved_from, &HashSet::new()).is_empty());
+
+            // paired in the next batch.
+            let carried = moved_from.keys().copied().collect();
+            parse(
+                &event(libc::IN_MOVED_TO, 1, "b"),
+                path,
+                &mut changes,
+                &mut moved_from,
+            );
+            parse(
+                &event(libc::IN_MOVED_FROM, 2, "c"),
+                path,
+                &mut changes,
+                &mut moved_from,
+            );
+            assert!(take_moved_out(&mut moved_from, &carried).is_empty());
+            assert_eq!(
+                changes,
+                vec![DirChange::Renamed {
+                    from: "/dir/a".into(),
+                    to: "/dir/b".into()
+                }]
+            );
+
+            // unpaired after a whole batch.
+            let carried = moved_from.keys().copied().collect();
+            assert_eq!(
+                take_moved_out(&mut moved_from, &carried),
+                vec![DirChange::Removed("/dir/c".into())]
+            );
+            assert!(moved_from.is_empty());
+        }
+
+        #[test]
+        fn parse_overflow_and_gone() {
+            let (changes, _, is_gone) = parse_all(&[event(libc::IN_Q_OVERFLOW, 0, "")]);
+            assert_eq!(changes, vec![DirChange::Rescan]);
+            assert!(!is_gone);
+
+            let (changes, _, is_gone) = parse_all(&[event(libc::IN_DELETE_SELF, 0, "")]);
+            assert!(changes.is_empty());
+            assert!(is_gone);
+        }
+
+        #[test]
+        fn parse_truncated_event() {
+            let mut buffer = event(libc::IN_CREATE, 0, "a.zip");
+            buffer.truncate(buffer.len() - 2);
+            let header = event(libc::IN_CREATE, 0, "");
+
+            let (changes, _, _) = parse_all(&[buffer, header[..4].to_vec()]);
+            assert_eq!(changes, vec![DirChange::Created("/dir/a.zip".into())]);
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod inotify {
+    use std::path::Path;
+
+    use tokio::sync::mpsc;
+
+    use super::DirChange;
+
+    pub fn watch(_path: &Path, _sender: mpsc::Sender<Vec<DirChange>>) -> std::io::Result<()> {
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+}
@@ -1,7 +1,15 @@
 use libarchive::reader::{ArchiveEntry, ReaderHandle};
 use std::future::Future;
-use std::{io::Read, path::Path};
+use std::{
+    collections::HashMap,
+    io::Read,
+    path::{Path, PathBuf},
+};
 
+use fmr_core::{
+    path::NameSorterType,
+    source::{PageEntry, PageSource},
+};
 use fmr_frame::ImageData;
 
 use super::image::load_image_from_memory_as_option;
@@ -70,26 +78,371 @@ pub fn load_image_from_opt_entry(
     flatten_future(it)
 }
 
-pub fn load_path_to_image(
-    reader: ReaderHandle,
-    name: String,
-) -> impl Future<Output = Option<ImageData>> + Send {
-    let time = std::time::Instant::now();
+/// Bytes of extracted entries kept in memory by [`ArchiveSource`], the rest is written to a
+/// temporary directory.
+pub const MEMORY_LIMIT: usize = 256 * 1024 * 1024;
+
+/// Bytes of extracted entries written to the temporary directory by [`ArchiveSource`], the
+/// least recently used entry is removed after it.
+pub const SPILL_LIMIT: usize = 2 * 1024 * 1024 * 1024;
 
-    let it = reader
-        .into_iter()
-        .filter_map(|it| it.ok())
-        .find(|it| it.pathname() == Some(name.clone()));
-    tracing::trace!("finding archive entry in {:?}", time.elapsed());
+/// Entries read after the requested one in the same pass by default.
+pub const READ_AHEAD: usize = 8;
 
-    load_image_from_opt_entry(it)
+/// [`PageSource`] reading files of archive supported by libarchive.
+///
+/// libarchive can only read an archive forward from the start, so entries are indexed once
+/// and read by a stream that keeps its position between reads. A missing entry is read
+/// together with the entries after it in page order, which fills the preload window in a
+/// single pass. Extracted entries are cached and evicted by least recent use.
+pub struct ArchiveSource {
+    path: PathBuf,
+    // file entries in archive order.
+    index: Vec<PageEntry>,
+    sorter: Option<NameSorterType>,
+    // name of images in page order, used to choose entries read ahead.
+    order: Vec<String>,
+    cache: EntryCache,
+    stream: Option<ArchiveStream>,
+    read_ahead: usize,
 }
 
-pub fn load_path_to_image_from_opt(
-    reader: Option<ReaderHandle>,
-    name: String,
-) -> impl Future<Output = Option<ImageData>> + Send {
-    let it = reader.map(|it| load_path_to_image(it, name));
+impl ArchiveSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            index: vec![],
+            sorter: None,
+            order: vec![],
+            cache: EntryCache::default(),
+            stream: None,
+            read_ahead: READ_AHEAD,
+        }
+    }
 
-    flatten_future(it)
+    /// Set number of entries read after the requested one.
+    pub fn with_read_ahead(mut self, read_ahead: usize) -> Self {
+        self.read_ahead = read_ahead;
+        self
+    }
+
+    /// Set order of pages, entries are read ahead in this order instead of archive order.
+    pub fn with_sorter(mut self, sorter: NameSorterType) -> Self {
+        self.sorter = Some(sorter);
+        self
+    }
+
+    fn open(&self) -> std::io::Result<ReaderHandle> {
+        open(&self.path).map_err(|err| std::io::Error::other(err.to_string()))
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.index.iter().position(|it| it.name == name)
+    }
+
+    // `name` followed by entries after it in page order that aren't cached yet.
+    fn wanted(&self, name: &str) -> Vec<String> {
+        let after = match self.order.iter().position(|it| it == name) {
+            Some(position) => &self.order[position + 1..],
+            None => &[],
+        };
+
+        std::iter::once(name.to_string())
+            .chain(
+                after
+                    .iter()
+                    .filter(|it| !self.cache.contains(it))
+                    .take(self.read_ahead)
+                    .cloned(),
+            )
+            .collect()
+    }
+}
+
+impl PageSource for ArchiveSource {
+    fn entries(&mut self) -> std::io::Result<Vec<PageEntry>> {
+        if self.index.is_empty() {
+            self.index = self
+                .open()?
+                .into_iter()
+                .filter_map(|it| it.ok())
+                .filter(|it| it.is_file())
+                .filter_map(|it| {
+                    let mut entry = PageEntry::new(it.pathname()?);
+                    entry.size = u64::try_from(it.size()).ok();
+                    Some(entry)
+                })
+                .collect();
+
+            self.order = self
+                .index
+                .iter()
+                .map(|it| it.name.clone())
+                .filter(|it| ImageData::can_read(it))
+                .collect();
+            if let Some(sorter) = &self.sorter {
+                self.order.sort_by(|a, b| sorter(a, b));
+            }
+        }
+
+        Ok(self.index.clone())
+    }
+
+    fn read(&mut self, name: &str) -> std::io::Result<Vec<u8>> {
+        if let Some(data) = self.cache.get(name) {
+            return data;
+        }
+
+        if self.index.is_empty() {
+            self.entries()?;
+        }
+
+        let Some(required) = self.position(name) else {
+            return Err(std::io::ErrorKind::NotFound.into());
+        };
+
+        let time = std::time::Instant::now();
+        let wanted = self.wanted(name);
+        let positions = wanted
+            .iter()
+            .filter_map(|it| Some((self.position(it)?, it.clone())))
+            .collect::<HashMap<_, _>>();
+
+        let stream = match &mut self.stream {
+            Some(stream) => stream,
+            None => self.stream.insert(ArchiveStream::spawn(self.path.clone())),
+        };
+        let read = stream.read(required, positions)?;
+
+        let mut found = None;
+        for (pathname, data) in read {
+            if pathname == name {
+                found = Some(data.clone());
+            }
+            self.cache.insert(pathname, data)?;
+        }
+        tracing::trace!(
+            "reading {} with {} entries ahead in {:?}",
+            name,
+            wanted.len() - 1,
+            time.elapsed()
+        );
+
+        found.ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound))
+    }
+}
+
+// extracted entries with their name.
+type ReadEntries = std::io::Result<Vec<(String, Vec<u8>)>>;
+
+// request to read entries at their position in archive, entry at `required` must be read
+// while the others are read only if they're ahead of the stream.
+struct StreamRequest {
+    required: usize,
+    positions: HashMap<usize, String>,
+    reply: std::sync::mpsc::Sender<ReadEntries>,
+}
+
+// archive opened in its own thread, the handle stays in that thread and is read forward
+// from where the previous read stopped. It's opened again only to read an entry before it.
+struct ArchiveStream {
+    sender: std::sync::mpsc::Sender<StreamRequest>,
+}
+
+impl ArchiveStream {
+    fn spawn(path: PathBuf) -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel::<StreamRequest>();
+
+        std::thread::spawn(move || {
+            let mut entries: Option<Box<dyn Iterator<Item = ArchiveEntry>>> = None;
+            // position of the next file entry of `entries`.
+            let mut next = 0;
+
+            for StreamRequest {
+                required,
+                mut positions,
+                reply,
+            } in receiver
+            {
+                if entries.is_none() || required < next {
+                    match open(&path) {
+                        Ok(it) => entries = Some(Box::new(it.into_iter().filter_map(|it| it.ok()))),
+                        Err(err) => {
+                            reply.send(Err(std::io::Error::other(err.to_string()))).ok();
+                            continue;
+                        }
+                    };
+                    next = 0;
+                }
+
+                // entries behind the stream are read when they're requested.
+                positions.retain(|position, _| *position >= next);
+
+                let Some(entries) = entries.as_mut() else {
+                    continue;
+                };
+                let read = Self::read_entries(entries, &mut next, required, positions);
+                reply.send(read).ok();
+            }
+        });
+
+        Self { sender }
+    }
+
+    fn read_entries(
+        entries: &mut dyn Iterator<Item = ArchiveEntry>,
+        next: &mut usize,
+        required: usize,
+        mut positions: HashMap<usize, String>,
+    ) -> ReadEntries {
+        let mut read = vec![];
+
+        while !positions.is_empty() {
+            let Some(mut entry) = entries.next() else {
+                break;
+            };
+
+            if !entry.is_file() {
+                continue;
+            }
+
+            let position = *next;
+            *next += 1;
+
+            let Some(pathname) = positions.remove(&position) else {
+                continue;
+            };
+
+            // the binding can't set passphrase, so there's no point asking for password.
+            if entry.is_encrypted() {
+                if position == required {
+                    let err = "encrypted entry of this archive format isn't supported";
+                    return Err(std::io::Error::new(std::io::ErrorKind::Unsupported, err));
+                }
+                continue;
+            }
+
+            let mut data = Vec::new();
+            entry.read_to_end(&mut data)?;
+            read.push((pathname, data));
+        }
+
+        Ok(read)
+    }
+
+    fn read(&mut self, required: usize, positions: HashMap<usize, String>) -> ReadEntries {
+        let (reply, receiver) = std::sync::mpsc::channel();
+        let closed = || std::io::Error::other("archive stream is closed");
+
+        let request = StreamRequest {
+            required,
+            positions,
+            reply,
+        };
+        self.sender.send(request).map_err(|_| closed())?;
+        receiver.recv().map_err(|_| closed())?
+    }
+}
+
+enum CachedEntry {
+    Memory(Vec<u8>),
+    Spilled(PathBuf),
+}
+
+// extracted entries with the time they're last used.
+#[derive(Default)]
+struct EntryCache {
+    entries: HashMap<String, (CachedEntry, usize, u64)>,
+    memory: usize,
+    spilled: usize,
+    time: u64,
+    // number of spilled file ever written, used to name them.
+    spill_count: usize,
+    spill: Option<tempfile::TempDir>,
+}
+
+impl EntryCache {
+    fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    fn get(&mut self, name: &str) -> Option<std::io::Result<Vec<u8>>> {
+        self.time += 1;
+        let (entry, _, used) = self.entries.get_mut(name)?;
+        *used = self.time;
+
+        match entry {
+            CachedEntry::Memory(data) => Some(Ok(data.clone())),
+            CachedEntry::Spilled(path) => Some(std::fs::read(path)),
+        }
+    }
+
+    fn insert(&mut self, name: String, data: Vec<u8>) -> std::io::Result<()> {
+        self.remove(&name);
+        self.time += 1;
+
+        let size = data.len();
+        self.memory += size;
+        self.entries
+            .insert(name, (CachedEntry::Memory(data), size, self.time));
+
+        while self.memory > MEMORY_LIMIT {
+            let Some(name) = self.least_recently_used(true) else {
+                break;
+            };
+            self.spill(&name)?;
+        }
+
+        while self.spilled > SPILL_LIMIT {
+            let Some(name) = self.least_recently_used(false) else {
+                break;
+            };
+            self.remove(&name);
+        }
+
+        Ok(())
+    }
+
+    fn least_recently_used(&self, in_memory: bool) -> Option<String> {
+        self.entries
+            .iter()
+            .filter(|(_, (entry, ..))| matches!(entry, CachedEntry::Memory(_)) == in_memory)
+            .min_by_key(|(_, (.., used))| *used)
+            .map(|(name, _)| name.clone())
+    }
+
+    // move entry from memory to the temporary directory.
+    fn spill(&mut self, name: &str) -> std::io::Result<()> {
+        let Some((entry, size, _)) = self.entries.get_mut(name) else {
+            return Ok(());
+        };
+        let CachedEntry::Memory(data) = entry else {
+            return Ok(());
+        };
+
+        let dir = match &self.spill {
+            Some(dir) => dir,
+            None => self.spill.insert(tempfile::tempdir()?),
+        };
+        let path = dir.path().join(self.spill_count.to_string());
+        self.spill_count += 1;
+        std::fs::write(&path, data.as_slice())?;
+
+        self.memory -= *size;
+        self.spilled += *size;
+        *entry = CachedEntry::Spilled(path);
+
+        Ok(())
+    }
+
+    fn remove(&mut self, name: &str) {
+        match self.entries.remove(name) {
+            Some((CachedEntry::Memory(_), size, _)) => self.memory -= size,
+            Some((CachedEntry::Spilled(path), size, _)) => {
+                self.spilled -= size;
+                std::fs::remove_file(path).ok();
+            }
+            None => {}
+        }
+    }
 }
@@ -0,0 +1,297 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use fmr_core::{
+    exclude::ExcludeRules,
+    source::{PageEntry, PageSource},
+};
+use fmr_frame::ImageData;
+
+use super::{source, zip::NameEncoding};
+
+/// Entry inside an archive, addressed as the archive path joined with the entry name, e.g.
+/// `books/volume.zip/chapter 1`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchivePath {
+    /// the archive in file system.
+    pub archive: PathBuf,
+    /// name of the entry separated by `/`, empty for the root of the archive.
+    pub entry: String,
+}
+
+impl ArchivePath {
+    /// Split `path` at the archive it's in, `None` if it isn't an archive or inside one.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let archive = path
+            .ancestors()
+            .filter(|it| !it.as_os_str().is_empty())
+            .find(|it| it.exists())?;
+
+        if !archive.is_file() || !is_archive(archive) {
+            return None;
+        }
+
+        let entry = path.strip_prefix(archive).ok()?;
+        let entry = entry
+            .components()
+            .map(|it| it.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        Some(Self {
+            archive: archive.to_path_buf(),
+            entry,
+        })
+    }
+
+    /// Returns `true` if `path` is an entry inside an archive rather than a file.
+    pub fn is_virtual(path: &Path) -> bool {
+        !path.exists() && Self::from_path(path).is_some()
+    }
+}
+
+/// Returns `true` if `path` is an archive in file system.
+pub fn is_archive(path: &Path) -> bool {
+    // only the format is checked, entry names aren't decoded.
+    let file = std::fs::File::open(path);
+    if file.is_ok_and(|it| zip::ZipArchive::new(it).is_ok()) {
+        return true;
+    }
+
+    #[cfg(feature = "libarchive")]
+    if super::archive::can_read(path) {
+        return true;
+    }
+
+    false
+}
+
+/// Returns `true` if entry `name` is an archive that can be entered, archive inside
+/// another archive is read into memory so only zip is supported.
+pub fn is_nested_archive(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .map(|it| it.eq_ignore_ascii_case("zip") || it.eq_ignore_ascii_case("cbz"))
+        .unwrap_or(false)
+}
+
+fn names_of(source: &mut dyn PageSource) -> Vec<String> {
+    let entries = source.entries().unwrap_or_default();
+    entries.into_iter().map(|it| it.name).collect()
+}
+
+/// Child of [`ArchiveDir`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchiveChild {
+    pub name: String,
+    /// directory or nested archive.
+    pub is_dir: bool,
+}
+
+/// Directory inside an archive, opened from [`ArchivePath`].
+///
+/// As [`PageSource`] it lists files in the directory and its subdirectories.
+pub struct ArchiveDir {
+    source: Box<dyn PageSource>,
+    names: Vec<String>,
+    // entry name of the directory ending with `/`, empty for root.
+    prefix: String,
+    // file the path points to when it isn't a directory.
+    file: Option<String>,
+    // tried first for zip entry name that isn't UTF-8, nested archive is opened with it.
+    encoding: NameEncoding,
+}
+
+impl ArchiveDir {
+    /// Open directory at `path`, nested archive in `path` is entered and path to a file
+    /// opens the directory it's in. `encoding` is tried first for zip entry name that isn't
+    /// UTF-8.
+    pub fn open(path: &ArchivePath, encoding: NameEncoding) -> Option<Self> {
+        let mut source = source::open_archive(&path.archive, encoding)?;
+        let mut names = names_of(&mut *source);
+        let mut rest = path.entry.trim_matches('/').to_string();
+
+        loop {
+            let nested = names
+                .iter()
+                .filter(|it| is_nested_archive(it))
+                .find(|it| rest == **it || rest.starts_with(&format!("{it}/")))
+                .cloned();
+
+            let Some(nested) = nested else {
+                break;
+            };
+
+            source = source::open_memory(source.read(&nested).ok()?, encoding)?;
+            names = names_of(&mut *source);
+            rest = rest[nested.len()..].trim_start_matches('/').to_string();
+        }
+
+        let file = names.contains(&rest).then(|| rest.clone());
+        if file.is_some() {
+            rest = match rest.rsplit_once('/') {
+                Some((parent, _)) => parent.to_string(),
+                None => String::new(),
+            };
+        }
+
+        let prefix = if rest.is_empty() {
+            rest
+        } else {
+            format!("{rest}/")
+        };
+
+        Some(Self {
+            source,
+            names,
+            prefix,
+            file,
+            encoding,
+        })
+    }
+
+    /// Open directory at virtual `path`, see [`ArchivePath::from_path`].
+    pub fn open_path(path: &Path, encoding: NameEncoding) -> Option<Self> {
+        Self::open(&ArchivePath::from_path(path)?, encoding)
+    }
+
+    // name of every file in this directory and its subdirectories, relative to archive.
+    fn files(&self) -> impl Iterator<Item = &String> {
+        self.names.iter().filter(|it| it.starts_with(&self.prefix))
+    }
+
+    /// Direct children of this directory, without entries excluded by `rules`.
+    pub fn children(&self, rules: &ExcludeRules) -> Vec<ArchiveChild> {
+        self.children_of(&self.prefix, rules)
+    }
+
+    fn children_of(&self, prefix: &str, rules: &ExcludeRules) -> Vec<ArchiveChild> {
+        let mut children = BTreeMap::new();
+        let names = self
+            .names
+            .iter()
+            .filter(|it| it.starts_with(prefix) && !rules.is_excluded(it));
+
+        for it in names {
+            let rest = &it[prefix.len()..];
+            match rest.split_once('/') {
+                Some((dir, _)) => children.insert(dir.to_string(), true),
+                None => children.insert(rest.to_string(), is_nested_archive(rest)),
+            };
+        }
+
+        children
+            .into_iter()
+            .map(|(name, is_dir)| ArchiveChild { name, is_dir })
+            .collect()
+    }
+
+    /// Returns `true` if this directory has a subdirectory or nested archive, such archive
+    /// is browsed in explorer instead of being read as a single book.
+    ///
+    /// Directory that only wraps a single directory is skipped, entries excluded by `rules`
+    /// such as `__MACOSX` aren't counted and EPUB is always read as a book.
+    pub fn is_browsable(&self, rules: &ExcludeRules) -> bool {
+        if self.names.iter().any(|it| it == "META-INF/container.xml") {
+            return false;
+        }
+
+        let mut prefix = self.prefix.clone();
+        loop {
+            let children = self.children_of(&prefix, rules);
+            match children.as_slice() {
+                [ArchiveChild { name, is_dir: true }] if !is_nested_archive(name) => {
+                    prefix = format!("{prefix}{name}/");
+                }
+                children => return children.iter().any(|it| it.is_dir),
+            }
+        }
+    }
+
+    /// First image ordered by name, looking into nested archive when there's none.
+    ///
+    /// Only the file is loaded when it's opened from path to a file.
+    pub async fn first_image(&mut self, rules: &ExcludeRules) -> Option<ImageData> {
+        let names = match self.file.clone() {
+            Some(file) => vec![file],
+            None => self.files().cloned().collect(),
+        };
+
+        if let Some(image) = source::first_image_of(self, names.clone(), rules).await {
+            return Some(image);
+        }
+
+        let mut nested = names
+            .into_iter()
+            .filter(|it| is_nested_archive(it))
+            .collect::<Vec<_>>();
+        nested.sort_by(|a, b| natord::compare(a, b));
+
+        for it in nested {
+            let source = self.source.read(&it).ok();
+            let source = source.and_then(|it| source::open_memory(it, self.encoding));
+            if let Some(mut source) = source {
+                if let Some(image) = source::first_image(&mut source, rules).await {
+                    return Some(image);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl PageSource for ArchiveDir {
+    fn entries(&mut self) -> std::io::Result<Vec<PageEntry>> {
+        let entries = self.source.entries()?;
+        let entries = entries
+            .into_iter()
+            .filter(|it| it.name.starts_with(&self.prefix))
+            .collect();
+
+        Ok(entries)
+    }
+
+    fn read(&mut self, name: &str) -> std::io::Result<Vec<u8>> {
+        self.source.read(name)
+    }
+
+    fn read_head(&mut self, name: &str, len: usize) -> std::io::Result<Vec<u8>> {
+        self.source.read_head(name, len)
+    }
+
+    fn set_password(&mut self, password: &str) {
+        self.source.set_password(password)
+    }
+
+    fn try_clone(&self) -> Option<Box<dyn PageSource>> {
+        self.source.try_clone()
+    }
+}
+
+/// Sibling directory of virtual `path` at `direction`, ordered by `sorter`.
+pub fn sibling_dir(
+    path: &Path,
+    direction: isize,
+    rules: &ExcludeRules,
+    encoding: NameEncoding,
+    sorter: impl Fn(&Path, &Path) -> std::cmp::Ordering,
+) -> Option<PathBuf> {
+    let parent = path.parent()?;
+    let dir = ArchiveDir::open_path(parent, encoding)?;
+
+    let mut siblings = dir
+        .children(rules)
+        .into_iter()
+        .filter(|it| it.is_dir)
+        .map(|it| parent.join(it.name))
+        .collect::<Vec<_>>();
+    siblings.sort_by(|a, b| sorter(a, b));
+
+    let index = siblings.iter().position(|it| it == path)?;
+    let index = index.checked_add_signed(direction)?;
+
+    siblings.get(index).cloned()
+}
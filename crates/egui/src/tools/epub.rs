@@ -0,0 +1,356 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{Read, Seek},
+};
+
+use quick_xml::events::{BytesStart, Event};
+use zip::ZipArchive;
+
+use fmr_frame::ImageData;
+
+/// Image pages and metadata of an EPUB, read from its OPF package document.
+#[derive(Clone, Debug, Default)]
+pub struct Epub {
+    pub title: Option<String>,
+    /// from `page-progression-direction` of the spine, `None` if it isn't specified.
+    pub read_from_right: Option<bool>,
+    /// zip path of the cover image.
+    pub cover: Option<String>,
+    /// zip path of images ordered by the spine.
+    pub pages: Vec<String>,
+    /// zip path of XHTML documents ordered by the spine.
+    pub documents: Vec<String>,
+}
+
+struct ManifestItem {
+    href: String,
+    media_type: String,
+    properties: String,
+}
+
+impl Epub {
+    /// Returns `None` if `zip` doesn't have EPUB container or package document.
+    pub fn from_zip<R: Read + Seek>(zip: &mut ZipArchive<R>) -> Option<Self> {
+        let container = read_to_string(zip, "META-INF/container.xml")?;
+
+        let mut opf_path = None;
+        for_each_element(&container, |element| {
+            if element.local_name().as_ref() == b"rootfile" && opf_path.is_none() {
+                opf_path = attribute(element, b"full-path");
+            }
+        });
+        let opf_path = opf_path?;
+        let opf = read_to_string(zip, &opf_path)?;
+
+        let mut epub = Self::default();
+        let mut manifest = HashMap::new();
+        let mut spine = vec![];
+        let mut cover_id = None;
+
+        let mut reader = quick_xml::Reader::from_str(&opf);
+        let mut in_title = false;
+        loop {
+            match reader.read_event() {
+                Ok(Event::Start(element) | Event::Empty(element)) => {
+                    match element.local_name().as_ref() {
+                        b"title" => in_title = epub.title.is_none(),
+                        b"meta" if attribute(&element, b"name").as_deref() == Some("cover") => {
+                            cover_id = attribute(&element, b"content");
+                        }
+                        b"item" => {
+                            let (Some(id), Some(href)) =
+                                (attribute(&element, b"id"), attribute(&element, b"href"))
+                            else {
+                                continue;
+                            };
+
+                            let item = ManifestItem {
+                                href: resolve(&opf_path, &href),
+                                media_type: attribute(&element, b"media-type").unwrap_or_default(),
+                                properties: attribute(&element, b"properties").unwrap_or_default(),
+                            };
+                            manifest.insert(id, item);
+                        }
+                        b"spine" => {
+                            epub.read_from_right =
+                                attribute(&element, b"page-progression-direction").and_then(|it| {
+                                    match it.as_str() {
+                                        "rtl" => Some(true),
+                                        "ltr" => Some(false),
+                                        _ => None,
+                                    }
+                                });
+                        }
+                        b"itemref" => spine.extend(attribute(&element, b"idref")),
+                        _ => {}
+                    }
+                }
+                Ok(Event::Text(text)) if in_title => {
+                    // badly escaped title is kept as it is.
+                    let title = match text.unescape() {
+                        Ok(title) => title.trim().to_string(),
+                        Err(_) => String::from_utf8_lossy(&text).trim().to_string(),
+                    };
+                    epub.title = Some(title).filter(|it| !it.is_empty());
+                    in_title = false;
+                }
+                Ok(Event::End(_)) => in_title = false,
+                Ok(Event::Eof) | Err(_) => break,
+                _ => {}
+            }
+        }
+
+        epub.cover = manifest
+            .values()
+            .find(|it| {
+                it.properties
+                    .split_whitespace()
+                    .any(|it| it == "cover-image")
+            })
+            .or_else(|| manifest.get(cover_id.as_ref()?))
+            .map(|it| it.href.clone());
+
+        let mut seen = HashSet::new();
+        for id in spine {
+            let Some(item) = manifest.get(&id) else {
+                continue;
+            };
+
+            let images = if item.media_type.starts_with("image/") {
+                vec![item.href.clone()]
+            } else {
+                epub.documents.push(item.href.clone());
+
+                read_to_string(zip, &item.href)
+                    .map(|document| document_images(&item.href, &document))
+                    .unwrap_or_default()
+            };
+
+            for image in images {
+                if ImageData::can_read(&image) && seen.insert(image.clone()) {
+                    epub.pages.push(image);
+                }
+            }
+        }
+
+        Some(epub)
+    }
+}
+
+/// Text of XHTML document at `path` in `zip` with its title.
+pub fn read_document_text<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    path: &str,
+) -> Option<(Option<String>, String)> {
+    read_to_string(zip, path).map(|it| document_text(&it))
+}
+
+/// Text content of XHTML `document`, with line break after each block element.
+///
+/// Returns the title from `title` or the first heading element along with the text.
+pub fn document_text(document: &str) -> (Option<String>, String) {
+    let mut reader = quick_xml::Reader::from_str(document);
+
+    let mut title = None;
+    let mut heading = None;
+    let mut text = String::new();
+    // element which text is collected as title, or skipped when it's not shown.
+    let mut inside: Option<Vec<u8>> = None;
+    let mut in_body = false;
+    let mut in_heading = false;
+
+    let is_block = |name: &[u8]| {
+        matches!(
+            name,
+            b"p" | b"div"
+                | b"br"
+                | b"li"
+                | b"tr"
+                | b"blockquote"
+                | b"section"
+                | b"h1"
+                | b"h2"
+                | b"h3"
+                | b"h4"
+                | b"h5"
+                | b"h6"
+        )
+    };
+    let push_newline = |text: &mut String| {
+        text.truncate(text.trim_end_matches(' ').len());
+        if !text.is_empty() && !text.ends_with('\n') {
+            text.push('\n');
+        }
+    };
+    let is_heading = |name: &[u8]| matches!(name, b"h1" | b"h2" | b"h3" | b"h4" | b"h5" | b"h6");
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element)) => {
+                let name = element.local_name().as_ref().to_vec();
+                match name.as_slice() {
+                    b"body" => in_body = true,
+                    // ruby annotation and its fallback parenthesis.
+                    b"title" | b"rt" | b"rp" | b"script" | b"style" if inside.is_none() => {
+                        inside = Some(name);
+                    }
+                    name if is_block(name) => {
+                        in_heading = heading.is_none() && is_heading(name);
+                        push_newline(&mut text);
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::Empty(element)) if is_block(element.local_name().as_ref()) => {
+                push_newline(&mut text);
+            }
+            Ok(Event::End(element)) => {
+                let name = element.local_name();
+                if inside.as_deref() == Some(name.as_ref()) {
+                    inside = None;
+                } else if is_block(name.as_ref()) {
+                    in_heading = false;
+                    push_newline(&mut text);
+                }
+            }
+            Ok(Event::Text(content)) => {
+                let Ok(content) = content.unescape() else {
+                    continue;
+                };
+                let words = content.split_whitespace().collect::<Vec<_>>().join(" ");
+                if words.is_empty() {
+                    if in_body
+                        && inside.is_none()
+                        && !text.is_empty()
+                        && !text.ends_with([' ', '\n'])
+                    {
+                        text.push(' ');
+                    }
+                    continue;
+                }
+
+                match inside.as_deref() {
+                    Some(b"title") if title.is_none() => title = Some(words),
+                    Some(_) => {}
+                    None if in_body => {
+                        if in_heading {
+                            heading = Some(words.clone());
+                            in_heading = false;
+                        }
+
+                        // keep space between inline elements.
+                        if content.starts_with(char::is_whitespace)
+                            && !text.is_empty()
+                            && !text.ends_with([' ', '\n'])
+                        {
+                            text.push(' ');
+                        }
+                        text.push_str(&words);
+                        if content.ends_with(char::is_whitespace) {
+                            text.push(' ');
+                        }
+                    }
+                    None => {}
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+
+    (title.or(heading), text.trim().to_string())
+}
+
+// images referenced by `img` and svg `image` in order.
+fn document_images(path: &str, document: &str) -> Vec<String> {
+    let mut images = vec![];
+    for_each_element(document, |element| {
+        let href = match element.local_name().as_ref() {
+            b"img" => attribute(element, b"src"),
+            // `xlink:href` in SVG 1.1 and `href` in SVG 2
+            b"image" => attribute(element, b"href"),
+            _ => None,
+        };
+
+        images.extend(href.map(|it| resolve(path, &it)));
+    });
+
+    images
+}
+
+fn read_to_string<R: Read + Seek>(zip: &mut ZipArchive<R>, name: &str) -> Option<String> {
+    let mut file = zip.by_name(name).ok()?;
+    let mut string = String::new();
+    file.read_to_string(&mut string).ok()?;
+
+    Some(string)
+}
+
+fn for_each_element(xml: &str, mut f: impl FnMut(&BytesStart)) {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element) | Event::Empty(element)) => f(&element),
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+    }
+}
+
+// attribute by its local name, ignoring the namespace prefix.
+fn attribute(element: &BytesStart, name: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .filter_map(|it| it.ok())
+        .find(|it| it.key.local_name().as_ref() == name)
+        .and_then(|it| it.unescape_value().ok())
+        .map(|it| it.into_owned())
+}
+
+/// Resolve `href` relative to the file at `base` in the zip.
+fn resolve(base: &str, href: &str) -> String {
+    let href = href.split(['#', '?']).next().unwrap_or_default();
+    let href = percent_decode(href);
+
+    let mut parts = match base.rsplit_once('/') {
+        Some((dir, _)) => dir.split('/').collect::<Vec<_>>(),
+        None => vec![],
+    };
+    for it in href.split('/') {
+        match it {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            it => parts.push(it),
+        }
+    }
+
+    parts.join("/")
+}
+
+fn percent_decode(href: &str) -> String {
+    let bytes = href.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|it| std::str::from_utf8(it).ok())
+            .and_then(|it| u8::from_str_radix(it, 16).ok());
+
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
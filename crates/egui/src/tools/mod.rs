@@ -1,5 +1,11 @@
+pub mod archive_dir;
+pub mod epub;
 pub mod image;
+pub mod source;
 pub mod zip;
 
 #[cfg(feature = "libarchive")]
 pub mod archive;
+
+#[cfg(feature = "pdf")]
+pub mod pdf;
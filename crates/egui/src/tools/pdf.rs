@@ -0,0 +1,533 @@
+use std::path::Path;
+
+use lopdf::{content::Content, Dictionary, Document, Object, ObjectId, Stream};
+
+use fmr_frame::ImageData;
+
+/// Size of a page without `MediaBox`, A4 in PDF points.
+const DEFAULT_PAGE_SIZE: [f32; 2] = [595.0, 842.0];
+
+// PDF point is 1/72 inch, pages are shown with 96 pixels per inch.
+const PIXELS_PER_PDF_POINT: f32 = 96.0 / 72.0;
+
+// guard against cycle in page tree and nested form.
+const MAX_DEPTH: usize = 32;
+
+/// Returns `true` if `path` has pdf extension.
+pub fn is_pdf(path: &Path) -> bool {
+    path.extension()
+        .map(|it| it.eq_ignore_ascii_case("pdf"))
+        .unwrap_or(false)
+}
+
+/// Page of [`PdfBook`].
+pub enum PdfPage {
+    /// the scanned image of the page.
+    Image(ImageData),
+    /// page that can't be shown as an image, with its size in pixels.
+    Unsupported { size: [f32; 2], reason: String },
+}
+
+/// PDF whose pages are scanned images, e.g. scanned comics or books.
+pub struct PdfBook {
+    document: Document,
+    pages: Vec<ObjectId>,
+}
+
+impl PdfBook {
+    pub fn open(path: &Path) -> lopdf::Result<Self> {
+        let document = Document::load(path)?;
+        let pages = document.get_pages().into_values().collect();
+
+        Ok(Self { document, pages })
+    }
+
+    pub fn len(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+    }
+
+    /// Title from document information dictionary.
+    pub fn title(&self) -> Option<String> {
+        let info = self.document.trailer.get(b"Info").ok()?;
+        let title = self.resolve(info).as_dict().ok()?.get(b"Title").ok()?;
+        let title = decode_text_string(self.resolve(title).as_str().ok()?);
+
+        Some(title).filter(|it| !it.trim().is_empty())
+    }
+
+    /// Name of page at `index`, used as the name of its texture.
+    pub fn page_name(index: usize) -> String {
+        format!("Page {}", index + 1)
+    }
+
+    pub fn load_page(&self, index: usize) -> PdfPage {
+        let Some(&page) = self.pages.get(index) else {
+            return PdfPage::Unsupported {
+                size: DEFAULT_PAGE_SIZE.map(|it| it * PIXELS_PER_PDF_POINT),
+                reason: "Page not found".to_string(),
+            };
+        };
+
+        match self.page_image(page) {
+            Ok(image) => PdfPage::Image(image),
+            Err(reason) => PdfPage::Unsupported {
+                size: self.page_size(page).map(|it| it * PIXELS_PER_PDF_POINT),
+                reason,
+            },
+        }
+    }
+
+    /// Image of the first page, `None` if it isn't a scanned page.
+    pub fn first_image(&self) -> Option<ImageData> {
+        match self.load_page(0) {
+            PdfPage::Image(image) => Some(image),
+            PdfPage::Unsupported { .. } => None,
+        }
+    }
+
+    fn resolve<'a>(&'a self, object: &'a Object) -> &'a Object {
+        self.document
+            .dereference(object)
+            .map(|(_, object)| object)
+            .unwrap_or(object)
+    }
+
+    fn resolve_dict<'a>(&'a self, dict: &'a Dictionary, key: &[u8]) -> Option<&'a Dictionary> {
+        let object = dict.get(key).ok()?;
+        self.resolve(object).as_dict().ok()
+    }
+
+    // page attribute which may be inherited from its parent in page tree.
+    fn inherited(&self, page: ObjectId, key: &[u8]) -> Option<&Object> {
+        let mut dict = self.document.get_dictionary(page).ok()?;
+        for _ in 0..MAX_DEPTH {
+            if let Ok(object) = dict.get(key) {
+                return Some(self.resolve(object));
+            }
+            let parent = dict.get(b"Parent").and_then(Object::as_reference).ok()?;
+            dict = self.document.get_dictionary(parent).ok()?;
+        }
+        None
+    }
+
+    fn page_size(&self, page: ObjectId) -> [f32; 2] {
+        let media_box = self
+            .inherited(page, b"MediaBox")
+            .and_then(|it| it.as_array().ok())
+            .map(|it| {
+                it.iter()
+                    .filter_map(|it| self.resolve(it).as_float().ok())
+                    .collect::<Vec<_>>()
+            });
+
+        match media_box.as_deref() {
+            Some(&[x0, y0, x1, y1]) => {
+                let size = [(x1 - x0).abs(), (y1 - y0).abs()];
+                if size.contains(&0.0) {
+                    DEFAULT_PAGE_SIZE
+                } else {
+                    size
+                }
+            }
+            _ => DEFAULT_PAGE_SIZE,
+        }
+    }
+
+    fn page_image(&self, page: ObjectId) -> Result<ImageData, String> {
+        if self.document.is_encrypted() {
+            return Err("Encrypted PDF isn't supported".to_string());
+        }
+
+        let content = self
+            .document
+            .get_page_content(page)
+            .map_err(|err| err.to_string())?;
+        let resources = self
+            .inherited(page, b"Resources")
+            .and_then(|it| it.as_dict().ok());
+
+        let mut images = vec![];
+        self.collect_images(&content, resources, &mut images, 0)?;
+
+        // some scanner split the page into strips, only the largest one is shown.
+        let image = images
+            .into_iter()
+            .max_by_key(|it| {
+                let side = |key: &[u8]| {
+                    it.dict
+                        .get(key)
+                        .and_then(Object::as_i64)
+                        .unwrap_or(0)
+                        .max(0) as u64
+                };
+                side(b"Width") * side(b"Height")
+            })
+            .ok_or_else(|| "Page without image".to_string())?;
+
+        self.decode_image(image)
+    }
+
+    // collect image drawn by `content`, returns error if it draws anything else visible.
+    fn collect_images<'a>(
+        &'a self,
+        content: &[u8],
+        resources: Option<&'a Dictionary>,
+        images: &mut Vec<&'a Stream>,
+        depth: usize,
+    ) -> Result<(), String> {
+        if depth > MAX_DEPTH {
+            return Err("Form is nested too deep".to_string());
+        }
+
+        let content = Content::decode(content).map_err(|err| err.to_string())?;
+        let xobjects = resources.and_then(|it| self.resolve_dict(it, b"XObject"));
+
+        let mut text_render_mode = 0;
+        for operation in &content.operations {
+            match operation.operator.as_str() {
+                "Do" => {
+                    let stream = operation
+                        .operands
+                        .first()
+                        .and_then(|it| it.as_name().ok())
+                        .and_then(|name| xobjects?.get(name).ok())
+                        .and_then(|it| self.resolve(it).as_stream().ok());
+                    let Some(stream) = stream else {
+                        continue;
+                    };
+
+                    match stream.dict.get(b"Subtype").and_then(Object::as_name) {
+                        Ok(b"Image") => images.push(stream),
+                        Ok(b"Form") => {
+                            let content = plain_content(stream)?;
+                            let resources =
+                                self.resolve_dict(&stream.dict, b"Resources").or(resources);
+                            self.collect_images(&content, resources, images, depth + 1)?;
+                        }
+                        _ => {}
+                    }
+                }
+                "Tr" => {
+                    text_render_mode = operation
+                        .operands
+                        .first()
+                        .and_then(|it| it.as_i64().ok())
+                        .unwrap_or(0);
+                }
+                // invisible text is the OCR layer of scanned page.
+                "Tj" | "TJ" | "'" | "\"" if text_render_mode != 3 => {
+                    return Err("Page with text isn't supported".to_string());
+                }
+                // path painted before the image is the page background, it's covered by the
+                // scan.
+                "S" | "s" | "f" | "F" | "f*" | "B" | "B*" | "b" | "b*" | "sh"
+                    if !images.is_empty() =>
+                {
+                    return Err("Page with vector graphic isn't supported".to_string());
+                }
+                "BI" => {
+                    return Err("Page with inline image isn't supported".to_string());
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn decode_image(&self, stream: &Stream) -> Result<ImageData, String> {
+        let mut filters = stream.filters().unwrap_or_default();
+        let codec = match filters.last().map(String::as_str) {
+            Some("DCTDecode" | "DCT" | "JPXDecode" | "CCITTFaxDecode" | "CCF" | "JBIG2Decode") => {
+                filters.pop()
+            }
+            _ => None,
+        };
+
+        let data = decode_filters(stream, filters)?;
+
+        match codec.as_deref() {
+            Some("DCTDecode" | "DCT") => {
+                ImageData::load_from_memory_with_format(&data, image::ImageFormat::Jpeg)
+                    .map_err(|err| err.to_string())
+            }
+            #[cfg(feature = "jpeg2000")]
+            Some("JPXDecode") => decode_jpx(&data),
+            Some(codec) => Err(format!("{codec} image isn't supported")),
+            None => self.decode_raw(&stream.dict, &data),
+        }
+    }
+
+    // decode uncompressed samples of image.
+    fn decode_raw(&self, dict: &Dictionary, data: &[u8]) -> Result<ImageData, String> {
+        let get = |key: &[u8]| {
+            dict.get(key)
+                .map(|it| self.resolve(it))
+                .and_then(Object::as_i64)
+        };
+        let width = get(b"Width").map_err(|err| err.to_string())? as u32;
+        let height = get(b"Height").map_err(|err| err.to_string())? as u32;
+        let is_mask = dict
+            .get(b"ImageMask")
+            .and_then(Object::as_bool)
+            .unwrap_or(false);
+        let bits = if is_mask {
+            1
+        } else {
+            get(b"BitsPerComponent").unwrap_or(8) as u32
+        };
+
+        let color_space = match dict.get(b"ColorSpace") {
+            _ if is_mask => ColorSpace::Gray,
+            Ok(object) => self.color_space(object, 0)?,
+            Err(_) => return Err("Image without color space".to_string()),
+        };
+
+        let (components, scale) = match color_space {
+            ColorSpace::Indexed { .. } => (1, false),
+            ref color_space => (color_space.components(), true),
+        };
+        let samples = unpack_samples(data, [width, height], components, bits, scale)
+            .ok_or_else(|| "Image data is too short".to_string())?;
+
+        let samples = match &color_space {
+            ColorSpace::Indexed { base, lookup } => {
+                let components = base.components();
+                let mut colors = Vec::with_capacity(samples.len() * components);
+                for index in samples {
+                    let index = index as usize * components;
+                    match lookup.get(index..index + components) {
+                        Some(color) => colors.extend_from_slice(color),
+                        None => colors.resize(colors.len() + components, 0),
+                    }
+                }
+                colors
+            }
+            _ => samples,
+        };
+
+        let color_space = match color_space {
+            ColorSpace::Indexed { base, .. } => *base,
+            color_space => color_space,
+        };
+
+        let image = match color_space {
+            ColorSpace::Gray => image::GrayImage::from_raw(width, height, samples)
+                .map(image::DynamicImage::ImageLuma8),
+            ColorSpace::Rgb => image::RgbImage::from_raw(width, height, samples)
+                .map(image::DynamicImage::ImageRgb8),
+            ColorSpace::Cmyk => {
+                let samples = samples.chunks_exact(4).flat_map(cmyk_to_rgb).collect();
+                image::RgbImage::from_raw(width, height, samples)
+                    .map(image::DynamicImage::ImageRgb8)
+            }
+            ColorSpace::Indexed { .. } => None,
+        };
+
+        image
+            .map(ImageData::StaticImage)
+            .ok_or_else(|| "Image data is too short".to_string())
+    }
+
+    fn color_space(&self, object: &Object, depth: usize) -> Result<ColorSpace, String> {
+        let object = self.resolve(object);
+        let unsupported = || {
+            let name = match object {
+                Object::Array(array) => array.first().and_then(|it| it.as_name().ok()),
+                object => object.as_name().ok(),
+            };
+            let name = String::from_utf8_lossy(name.unwrap_or(b"Unknown"));
+            format!("{name} color space isn't supported")
+        };
+
+        if depth > 1 {
+            return Err(unsupported());
+        }
+
+        match object {
+            Object::Name(name) => ColorSpace::from_name(name).ok_or_else(unsupported),
+            Object::Array(array) => {
+                let family = array.first().and_then(|it| it.as_name().ok());
+                match family {
+                    Some(b"ICCBased") => {
+                        let profile = array
+                            .get(1)
+                            .and_then(|it| self.resolve(it).as_stream().ok())
+                            .ok_or_else(unsupported)?;
+                        let components = profile.dict.get(b"N").and_then(Object::as_i64);
+                        match components {
+                            Ok(1) => Ok(ColorSpace::Gray),
+                            Ok(3) => Ok(ColorSpace::Rgb),
+                            Ok(4) => Ok(ColorSpace::Cmyk),
+                            _ => Err(unsupported()),
+                        }
+                    }
+                    Some(b"Indexed" | b"I") => {
+                        let base = array.get(1).ok_or_else(unsupported)?;
+                        let base = self.color_space(base, depth + 1)?;
+                        let lookup = match array.get(3).map(|it| self.resolve(it)) {
+                            Some(Object::String(lookup, _)) => lookup.clone(),
+                            Some(Object::Stream(stream)) => plain_content(stream)?,
+                            _ => return Err(unsupported()),
+                        };
+
+                        Ok(ColorSpace::Indexed {
+                            base: Box::new(base),
+                            lookup,
+                        })
+                    }
+                    Some(b"CalGray") => Ok(ColorSpace::Gray),
+                    Some(b"CalRGB" | b"Lab") => Ok(ColorSpace::Rgb),
+                    _ => Err(unsupported()),
+                }
+            }
+            _ => Err(unsupported()),
+        }
+    }
+}
+
+enum ColorSpace {
+    Gray,
+    Rgb,
+    Cmyk,
+    Indexed {
+        base: Box<ColorSpace>,
+        lookup: Vec<u8>,
+    },
+}
+
+impl ColorSpace {
+    fn from_name(name: &[u8]) -> Option<Self> {
+        match name {
+            b"DeviceGray" | b"G" | b"CalGray" => Some(Self::Gray),
+            b"DeviceRGB" | b"RGB" | b"CalRGB" => Some(Self::Rgb),
+            b"DeviceCMYK" | b"CMYK" => Some(Self::Cmyk),
+            _ => None,
+        }
+    }
+
+    fn components(&self) -> usize {
+        match self {
+            ColorSpace::Gray => 1,
+            ColorSpace::Rgb => 3,
+            ColorSpace::Cmyk => 4,
+            ColorSpace::Indexed { base, .. } => base.components(),
+        }
+    }
+}
+
+// content of stream after applying its filters.
+fn plain_content(stream: &Stream) -> Result<Vec<u8>, String> {
+    stream.get_plain_content().map_err(|err| err.to_string())
+}
+
+// apply `filters` to content of image `stream`.
+fn decode_filters(stream: &Stream, filters: Vec<String>) -> Result<Vec<u8>, String> {
+    if filters.is_empty() {
+        return Ok(stream.content.clone());
+    }
+
+    // lopdf doesn't decompress image stream, so decode it as a plain stream instead.
+    let mut stream = stream.clone();
+    stream.dict.remove(b"Subtype");
+    let filters = filters
+        .into_iter()
+        .map(|it| Object::Name(it.into_bytes()))
+        .collect();
+    stream.dict.set("Filter", Object::Array(filters));
+
+    stream
+        .decompressed_content()
+        .map_err(|err| format!("Failed to decode image: {err}"))
+}
+
+// split rows of `bits` samples into one byte per sample, samples with less than 8 bits
+// are scaled to 8 bits when `scale` is true.
+fn unpack_samples(
+    data: &[u8],
+    [width, height]: [u32; 2],
+    components: usize,
+    bits: u32,
+    scale: bool,
+) -> Option<Vec<u8>> {
+    let samples_per_row = width as usize * components;
+    let row_size = (samples_per_row * bits as usize).div_ceil(8);
+    let data = data.get(..row_size * height as usize)?;
+
+    let samples = match bits {
+        8 => data.to_vec(),
+        // take the most significant byte.
+        16 => data.iter().step_by(2).copied().collect(),
+        1 | 2 | 4 => {
+            let max = (1u16 << bits) - 1;
+            let mut samples = Vec::with_capacity(samples_per_row * height as usize);
+            for row in data.chunks_exact(row_size) {
+                for index in 0..samples_per_row {
+                    let bit = index * bits as usize;
+                    let value = (row[bit / 8] >> (8 - bits as usize - bit % 8)) as u16 & max;
+                    let value = if scale { value * 255 / max } else { value };
+                    samples.push(value as u8);
+                }
+            }
+            samples
+        }
+        _ => return None,
+    };
+
+    Some(samples)
+}
+
+fn cmyk_to_rgb(cmyk: &[u8]) -> [u8; 3] {
+    let k = 255 - cmyk[3] as u16;
+    [0, 1, 2].map(|i| ((255 - cmyk[i] as u16) * k / 255) as u8)
+}
+
+// decode text string which is either UTF-16BE with BOM or PDFDocEncoding.
+fn decode_text_string(bytes: &[u8]) -> String {
+    match bytes {
+        [0xFE, 0xFF, rest @ ..] => {
+            let units = rest
+                .chunks_exact(2)
+                .map(|it| u16::from_be_bytes([it[0], it[1]]))
+                .collect::<Vec<_>>();
+            String::from_utf16_lossy(&units)
+        }
+        bytes => bytes.iter().map(|it| *it as char).collect(),
+    }
+}
+
+#[cfg(feature = "jpeg2000")]
+fn decode_jpx(data: &[u8]) -> Result<ImageData, String> {
+    let image = jpeg2k::Image::from_bytes(data).map_err(|err| err.to_string())?;
+    let (width, height) = (image.width(), image.height());
+    let components = image.components();
+
+    let sample = |component: &jpeg2k::ImageComponent, index: usize| {
+        let value = component.data().get(index).copied().unwrap_or(0);
+        let shift = component.precision().saturating_sub(8);
+        (value >> shift).clamp(0, 255) as u8
+    };
+
+    let size = width as usize * height as usize;
+    let image = match components {
+        [gray] | [gray, _] => {
+            let samples = (0..size).map(|i| sample(gray, i)).collect();
+            image::GrayImage::from_raw(width, height, samples).map(image::DynamicImage::ImageLuma8)
+        }
+        [r, g, b, ..] => {
+            let samples = (0..size)
+                .flat_map(|i| [sample(r, i), sample(g, i), sample(b, i)])
+                .collect();
+            image::RgbImage::from_raw(width, height, samples).map(image::DynamicImage::ImageRgb8)
+        }
+        [] => None,
+    };
+
+    image
+        .map(ImageData::StaticImage)
+        .ok_or_else(|| "Failed to decode JPEG 2000 image".to_string())
+}
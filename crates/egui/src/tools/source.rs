@@ -0,0 +1,125 @@
+use std::{io::Cursor, path::Path, sync::Arc};
+
+use fmr_core::{
+    exclude::ExcludeRules,
+    source::{FolderSource, PageSource, PasswordRequired},
+};
+use fmr_frame::ImageData;
+
+use super::{
+    image::load_image_from_memory_as_option,
+    zip::{NameEncoding, SharedFile, ZipSource},
+};
+
+/// Open archive at `path` as [`PageSource`], `None` if it isn't an archive. `encoding` is
+/// tried first for zip entry name that isn't UTF-8.
+pub fn open_archive(path: &Path, encoding: NameEncoding) -> Option<Box<dyn PageSource>> {
+    let file = SharedFile::open(path).ok()?;
+    if let Ok(zip) = zip::ZipArchive::new(file) {
+        return Some(Box::new(ZipSource::new(zip, encoding)));
+    }
+
+    #[cfg(feature = "libarchive")]
+    if super::archive::can_read(path) {
+        return Some(Box::new(super::archive::ArchiveSource::new(path)));
+    }
+
+    None
+}
+
+/// Open folder or archive at `path` as [`PageSource`].
+pub fn open(path: &Path, encoding: NameEncoding) -> Option<Box<dyn PageSource>> {
+    if path.is_dir() {
+        Some(Box::new(FolderSource::new(path)))
+    } else {
+        open_archive(path, encoding)
+    }
+}
+
+/// Open zip read into memory, e.g. an archive inside another archive.
+pub fn open_memory(data: Vec<u8>, encoding: NameEncoding) -> Option<Box<dyn PageSource>> {
+    let data: Arc<[u8]> = data.into();
+    let zip = zip::ZipArchive::new(Cursor::new(data)).ok()?;
+    Some(Box::new(ZipSource::new(zip, encoding)))
+}
+
+/// Bytes read from the start of an image to find its size.
+pub const HEADER_LEN: usize = 64 * 1024;
+
+/// Names of images in `source` that aren't excluded by `rules`.
+///
+/// Header of image is read to check its size when `rules` has minimum dimension, image whose
+/// size can't be found from it is kept.
+pub fn included_images<S: PageSource + ?Sized>(
+    source: &mut S,
+    names: Vec<String>,
+    rules: &ExcludeRules,
+) -> Vec<String> {
+    let mut names = names
+        .into_iter()
+        .filter(|it| !rules.is_excluded(it))
+        .collect::<Vec<_>>();
+
+    if rules.min_dimension > 0 {
+        names.retain(|it| {
+            let size = match source.local_path(it) {
+                Some(path) => image::image_dimensions(path).ok(),
+                None => source.read_head(it, HEADER_LEN).ok().and_then(|it| {
+                    let reader = image::io::Reader::new(Cursor::new(it));
+                    reader.with_guessed_format().ok()?.into_dimensions().ok()
+                }),
+            };
+
+            !size.is_some_and(|(width, height)| rules.is_too_small([width, height]))
+        });
+    }
+
+    names
+}
+
+/// Load the first image of `names` in `source` that isn't excluded by `rules`, ordered by
+/// name.
+pub async fn first_image_of<S: PageSource + ?Sized>(
+    source: &mut S,
+    names: Vec<String>,
+    rules: &ExcludeRules,
+) -> Option<ImageData> {
+    try_first_image_of(source, names, rules)
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Same as [`first_image_of`] but fails when the first image is encrypted, the caller asks
+/// password and sets it to `source` before trying again.
+pub async fn try_first_image_of<S: PageSource + ?Sized>(
+    source: &mut S,
+    mut names: Vec<String>,
+    rules: &ExcludeRules,
+) -> Result<Option<ImageData>, PasswordRequired> {
+    names.retain(|it| ImageData::can_read(it) && !rules.is_excluded(it));
+    names.sort_by(|a, b| natord::compare(a, b));
+
+    for it in names {
+        let data = match source.read(&it) {
+            Err(err) if PasswordRequired::is(&err) => return Err(PasswordRequired),
+            data => data.ok(),
+        };
+
+        let image = load_image_from_memory_as_option(data).await;
+        if let Some(image) = image.filter(|it| !rules.is_too_small(it.dimensions())) {
+            return Ok(Some(image));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Load the first image in `source` that isn't excluded by `rules`, ordered by name.
+pub async fn first_image<S: PageSource + ?Sized>(
+    source: &mut S,
+    rules: &ExcludeRules,
+) -> Option<ImageData> {
+    let names = source.entries().ok()?.into_iter().map(|it| it.name);
+    first_image_of(source, names.collect(), rules).await
+}
@@ -1,7 +1,15 @@
-use std::io::Read;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+    sync::Arc,
+};
 
+use serde::{Deserialize, Serialize};
 use zip::{read::ZipFile, ZipArchive};
 
+use fmr_core::source::{PageEntry, PageSource, PasswordRequired};
 use fmr_frame::ImageData;
 
 use super::image::load_image_from_memory_as_option;
@@ -51,3 +59,435 @@ where
         res
     }
 }
+
+/// File that keeps its own position, cloning it shares the opened file but not the position
+/// so each clone can be read from a different thread.
+///
+/// Targets without positioned read lock the file to seek and read it.
+#[derive(Clone, Debug)]
+pub struct SharedFile {
+    #[cfg(any(unix, windows))]
+    file: Arc<File>,
+    #[cfg(not(any(unix, windows)))]
+    file: Arc<std::sync::Mutex<File>>,
+    len: u64,
+    position: u64,
+}
+
+impl SharedFile {
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+
+        #[cfg(not(any(unix, windows)))]
+        let file = std::sync::Mutex::new(file);
+
+        Ok(Self {
+            file: Arc::new(file),
+            len,
+            position: 0,
+        })
+    }
+}
+
+impl Read for SharedFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        #[cfg(unix)]
+        let read = std::os::unix::fs::FileExt::read_at(&*self.file, buf, self.position)?;
+        #[cfg(windows)]
+        let read = std::os::windows::fs::FileExt::seek_read(&*self.file, buf, self.position)?;
+        #[cfg(not(any(unix, windows)))]
+        let read = {
+            let mut file = self.file.lock().unwrap_or_else(|err| err.into_inner());
+            file.seek(SeekFrom::Start(self.position))?;
+            file.read(buf)?
+        };
+
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for SharedFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+
+        self.position = position.ok_or(std::io::ErrorKind::InvalidInput)?;
+        Ok(self.position)
+    }
+}
+
+/// Encoding of zip entry name that isn't UTF-8.
+///
+/// Zip doesn't store the encoding of such name, it's whatever the archiver's system uses.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NameEncoding {
+    /// Shift-JIS of Japanese Windows.
+    #[default]
+    Cp932,
+    /// Simplified Chinese.
+    Gbk,
+    /// the encoding zip specifies, every name can be decoded with it.
+    Cp437,
+}
+
+impl NameEncoding {
+    pub const ALL: [Self; 3] = [Self::Cp932, Self::Gbk, Self::Cp437];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Cp932 => "Japanese (CP932)",
+            Self::Gbk => "Chinese (GBK)",
+            Self::Cp437 => "Western (CP437)",
+        }
+    }
+
+    // `cp437` is the name decoded by zip.
+    fn decode(&self, raw: &[u8], cp437: &str) -> Option<String> {
+        let encoding = match self {
+            Self::Cp932 => encoding_rs::SHIFT_JIS,
+            Self::Gbk => encoding_rs::GBK,
+            Self::Cp437 => return Some(cp437.to_string()),
+        };
+
+        let name = encoding.decode_without_bom_handling_and_without_replacement(raw)?;
+        Some(name.into_owned())
+    }
+}
+
+/// Name of every entry of `zip` by its index.
+///
+/// Name that isn't UTF-8 is decoded with the first of `fallback` and the other encodings
+/// that decodes all such names in the archive.
+pub fn decode_names<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    fallback: NameEncoding,
+) -> Vec<String> {
+    let names = (0..zip.len())
+        .map(|index| match zip.by_index_raw(index) {
+            Ok(file) => (file.name_raw().to_vec(), file.name().to_string()),
+            Err(_) => (vec![], String::new()),
+        })
+        .collect::<Vec<_>>();
+
+    let mut legacy = names
+        .iter()
+        .filter(|(raw, _)| std::str::from_utf8(raw).is_err())
+        .peekable();
+    if legacy.peek().is_none() {
+        return names.into_iter().map(|(_, name)| name).collect();
+    }
+
+    let encoding = std::iter::once(fallback)
+        .chain(NameEncoding::ALL)
+        .find(|encoding| {
+            let mut legacy = legacy.clone();
+            legacy.all(|(raw, name)| encoding.decode(raw, name).is_some())
+        })
+        .unwrap_or(NameEncoding::Cp437);
+    tracing::debug!("decoding zip entry names with {:?}", encoding);
+
+    names
+        .into_iter()
+        .map(|(raw, name)| match std::str::from_utf8(&raw) {
+            Ok(utf8) => utf8.to_string(),
+            Err(_) => encoding.decode(&raw, &name).unwrap_or(name),
+        })
+        .collect()
+}
+
+/// [`PageSource`] reading files of zip archive, entry is named by [`decode_names`].
+///
+/// Cloning it shares the index of the archive, entries are read in parallel when `R` is
+/// [`SharedFile`] or in memory.
+pub struct ZipSource<R> {
+    zip: ZipArchive<R>,
+    // decoded name of entries by index.
+    names: Arc<Vec<String>>,
+    indices: Arc<HashMap<String, usize>>,
+    password: Option<String>,
+}
+
+impl<R: Read + Seek> ZipSource<R> {
+    /// Open `zip`, `encoding` is tried first for name that isn't UTF-8.
+    pub fn new(mut zip: ZipArchive<R>, encoding: NameEncoding) -> Self {
+        let names = decode_names(&mut zip, encoding);
+        let indices = names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| (name.clone(), index))
+            .collect();
+
+        Self {
+            zip,
+            names: Arc::new(names),
+            indices: Arc::new(indices),
+            password: None,
+        }
+    }
+
+    pub fn into_inner(self) -> ZipArchive<R> {
+        self.zip
+    }
+
+    // entry `name` opened with the password.
+    fn open_entry(&mut self, name: &str) -> std::io::Result<ZipFile<'_>> {
+        let index = *self.indices.get(name).ok_or(std::io::ErrorKind::NotFound)?;
+
+        let file = match &self.password {
+            Some(password) => self.zip.by_index_decrypt(index, password.as_bytes()),
+            None => self.zip.by_index(index).map(Ok),
+        };
+
+        match file {
+            Ok(Ok(file)) => Ok(file),
+            Ok(Err(zip::result::InvalidPassword)) => Err(PasswordRequired::error()),
+            Err(zip::result::ZipError::UnsupportedArchive(
+                zip::result::ZipError::PASSWORD_REQUIRED,
+            )) => Err(PasswordRequired::error()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+impl<R: Read + Seek + Clone + Send + 'static> PageSource for ZipSource<R> {
+    fn entries(&mut self) -> std::io::Result<Vec<PageEntry>> {
+        let mut entries = Vec::with_capacity(self.zip.len());
+        for (index, name) in self.names.iter().enumerate() {
+            let file = self.zip.by_index_raw(index)?;
+            if file.is_file() {
+                entries.push(PageEntry {
+                    name: name.clone(),
+                    size: Some(file.size()),
+                    modified: to_system_time(file.last_modified()),
+                });
+            }
+        }
+
+        Ok(entries)
+    }
+
+    fn read(&mut self, name: &str) -> std::io::Result<Vec<u8>> {
+        let mut vec = Vec::new();
+        self.open_entry(name)?.read_to_end(&mut vec)?;
+        Ok(vec)
+    }
+
+    fn read_head(&mut self, name: &str, len: usize) -> std::io::Result<Vec<u8>> {
+        let mut vec = Vec::new();
+        self.open_entry(name)?
+            .take(len as u64)
+            .read_to_end(&mut vec)?;
+        Ok(vec)
+    }
+
+    fn set_password(&mut self, password: &str) {
+        self.password = Some(password.to_string());
+    }
+
+    fn try_clone(&self) -> Option<Box<dyn PageSource>> {
+        Some(Box::new(Self {
+            zip: self.zip.clone(),
+            names: self.names.clone(),
+            indices: self.indices.clone(),
+            password: self.password.clone(),
+        }))
+    }
+}
+
+// zip stores local time without time zone, so it's treated as UTC.
+fn to_system_time(time: zip::DateTime) -> Option<std::time::SystemTime> {
+    // days since unix epoch of the civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let (year, month, day) = (time.year() as i64, time.month() as i64, time.day() as i64);
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let seconds =
+        days * 86400 + time.hour() as i64 * 3600 + time.minute() as i64 * 60 + time.second() as i64;
+
+    let seconds = u64::try_from(seconds).ok()?;
+    std::time::UNIX_EPOCH.checked_add(std::time::Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use super::*;
+
+    // Shift-JIS of "表紙", it's also valid GBK.
+    const SHIFT_JIS: &[u8] = b"\x95\x5c\x8e\x86.jpg";
+    // GBK of "匑", it isn't valid Shift-JIS.
+    const GBK: &[u8] = b"\x85\x40.jpg";
+    // neither Shift-JIS nor GBK.
+    const LEGACY: &[u8] = b"\xff.jpg";
+
+    // zip whose entries are named by raw `names` with their name as content, the entries at
+    // `encrypted` are marked as encrypted.
+    fn zip_of(names: &[&[u8]], encrypted: &[usize]) -> ZipArchive<Cursor<Vec<u8>>> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+        let options = zip::write::FileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored)
+            .last_modified_time(
+                zip::DateTime::from_date_and_time(2000, 2, 29, 12, 34, 56).unwrap(),
+            );
+
+        // zip writes UTF-8 name only, so ASCII placeholder of the same length is replaced.
+        let placeholders = (0..names.len())
+            .map(|i| format!("{i}").repeat(names[i].len()))
+            .collect::<Vec<_>>();
+        for (placeholder, name) in placeholders.iter().zip(names) {
+            writer.start_file(placeholder.as_str(), options).unwrap();
+            writer.write_all(name).unwrap();
+        }
+        let mut data = writer.finish().unwrap().into_inner();
+
+        let mut central = vec![];
+        for i in 0..data.len().saturating_sub(4) {
+            if data[i..i + 4] == [0x50, 0x4b, 0x01, 0x02] {
+                central.push(i);
+            }
+        }
+        for (index, offset) in central.into_iter().enumerate() {
+            let local = u32::from_le_bytes(data[offset + 42..offset + 46].try_into().unwrap());
+            let local = local as usize;
+            let name = &mut data[offset + 46..offset + 46 + names[index].len()];
+            name.copy_from_slice(names[index]);
+            let name = &mut data[local + 30..local + 30 + names[index].len()];
+            name.copy_from_slice(names[index]);
+
+            if encrypted.contains(&index) {
+                data[offset + 8] |= 1;
+                data[local + 6] |= 1;
+            }
+        }
+
+        ZipArchive::new(Cursor::new(data)).unwrap()
+    }
+
+    fn decode(raw: &[u8], encoding: &'static encoding_rs::Encoding) -> String {
+        let name = encoding.decode_without_bom_handling_and_without_replacement(raw);
+        name.unwrap().into_owned()
+    }
+
+    #[test]
+    fn decode_names_in_fallback_order() {
+        assert!(encoding_rs::SHIFT_JIS
+            .decode_without_bom_handling_and_without_replacement(GBK)
+            .is_none());
+
+        let mut zip = zip_of(&[b"cover.jpg", SHIFT_JIS], &[]);
+        let names = decode_names(&mut zip, NameEncoding::Cp932);
+        assert_eq!(names, ["cover.jpg", "表紙.jpg"]);
+
+        // the fallback is tried first when both can decode the names.
+        let names = decode_names(&mut zip, NameEncoding::Gbk);
+        assert_eq!(names[1], decode(SHIFT_JIS, encoding_rs::GBK));
+        assert_ne!(names[1], "表紙.jpg");
+
+        // every name has to be decoded by the same encoding.
+        let mut zip = zip_of(&[SHIFT_JIS, GBK], &[]);
+        let names = decode_names(&mut zip, NameEncoding::Cp932);
+        assert_eq!(names[1], "匑.jpg");
+        assert_eq!(names[0], decode(SHIFT_JIS, encoding_rs::GBK));
+
+        // cp437 decodes anything.
+        let mut zip = zip_of(&[SHIFT_JIS, LEGACY], &[]);
+        let cp437 = zip.by_index_raw(1).unwrap().name().to_string();
+        let names = decode_names(&mut zip, NameEncoding::Cp932);
+        assert_eq!(names[1], cp437);
+    }
+
+    #[test]
+    fn zip_source_reads_decoded_names() {
+        let zip = zip_of(&[SHIFT_JIS, b"secret.jpg"], &[1]);
+        let mut source = ZipSource::new(zip, NameEncoding::Cp932);
+
+        let entries = source.entries().unwrap();
+        let names = entries
+            .iter()
+            .map(|it| it.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["表紙.jpg", "secret.jpg"]);
+        assert_eq!(entries[0].size, Some(SHIFT_JIS.len() as u64));
+
+        assert_eq!(source.read("表紙.jpg").unwrap(), SHIFT_JIS);
+        assert_eq!(source.read_head("表紙.jpg", 2).unwrap(), &SHIFT_JIS[..2]);
+
+        let err = source.read("secret.jpg").unwrap_err();
+        assert!(PasswordRequired::is(&err));
+        let err = source.try_clone().unwrap().read("secret.jpg").unwrap_err();
+        assert!(PasswordRequired::is(&err));
+
+        let err = source.read("missing.jpg").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn zip_time_as_utc() {
+        let time = |y, m, d, h, min, s| {
+            let time = zip::DateTime::from_date_and_time(y, m, d, h, min, s).unwrap();
+            to_system_time(time)
+                .unwrap()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+        };
+
+        assert_eq!(time(1980, 1, 1, 0, 0, 0), 315532800);
+        assert_eq!(time(2000, 2, 29, 12, 34, 56), 951827696);
+        assert_eq!(time(2000, 3, 1, 0, 0, 0), 951868800);
+        assert_eq!(time(2107, 12, 31, 23, 59, 58), 4354819198);
+
+        let zip = zip_of(&[b"cover.jpg"], &[]);
+        let mut source = ZipSource::new(zip, NameEncoding::Cp932);
+        let modified = source.entries().unwrap()[0].modified.unwrap();
+        assert_eq!(
+            modified
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            951827696
+        );
+    }
+
+    #[test]
+    fn shared_file_keeps_its_own_position() {
+        let path = std::env::temp_dir().join(format!("fmr-shared-file-{}", std::process::id()));
+        std::fs::write(&path, b"0123456789").unwrap();
+        let mut file = SharedFile::open(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let mut clone = file.clone();
+        let mut buf = [0; 4];
+        file.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"0123");
+        clone.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"0123");
+
+        assert_eq!(file.seek(SeekFrom::End(-2)).unwrap(), 8);
+        let mut rest = vec![];
+        file.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, b"89");
+
+        assert_eq!(file.seek(SeekFrom::Current(-6)).unwrap(), 4);
+        let err = file.seek(SeekFrom::Current(-5)).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(file.stream_position().unwrap(), 4);
+        assert!(file.seek(SeekFrom::End(-11)).is_err());
+
+        // reading past the end reads nothing.
+        assert_eq!(file.seek(SeekFrom::Start(20)).unwrap(), 20);
+        assert_eq!(file.read(&mut buf).unwrap(), 0);
+        assert_eq!(clone.read(&mut buf).unwrap(), 4);
+        assert_eq!(&buf, b"4567");
+    }
+}
@@ -56,11 +56,13 @@ impl ExplorerLoaderCache {
             target,
         );
 
-        let root = root.canonicalize().ok();
+        let canonical_root = root.canonicalize().ok();
 
         // TODO: test these.
         while let Some(parent) = path.parent() {
-            let is_root = root.as_ref() == path.canonicalize().as_ref().ok();
+            // entry inside archive can't be canonicalized.
+            let is_root =
+                path == root || canonical_root.as_ref() == path.canonicalize().as_ref().ok();
 
             self.insert_sha(path, target.to_string());
             path = parent;
@@ -0,0 +1,124 @@
+use fmr_core::path::glob_match;
+
+/// Query narrowing the items shown by the explorer.
+#[derive(Clone, Debug, Default)]
+pub struct ExplorerFilter {
+    pub query: String,
+    pub mode: FilterMode,
+    /// search names in the whole subtree instead of the current folder.
+    pub recursive: bool,
+    // regex compiled from the query.
+    regex: Option<(String, Option<regex::Regex>)>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FilterMode {
+    /// name contains the query ignoring case.
+    #[default]
+    Substring,
+    /// name matches glob pattern, see [`glob_match`].
+    Glob,
+    Regex,
+}
+
+impl FilterMode {
+    pub const ALL: [Self; 3] = [Self::Substring, Self::Glob, Self::Regex];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Substring => "Substring",
+            Self::Glob => "Glob",
+            Self::Regex => "Regex",
+        }
+    }
+}
+
+/// Matcher of name built from [`ExplorerFilter`].
+#[derive(Clone, Debug)]
+pub enum NameMatcher {
+    Substring(String),
+    Glob(String),
+    Regex(regex::Regex),
+}
+
+impl NameMatcher {
+    pub fn is_match(&self, name: &str) -> bool {
+        match self {
+            Self::Substring(query) => name.to_lowercase().contains(query),
+            Self::Glob(pattern) => glob_match(pattern, name),
+            Self::Regex(regex) => regex.is_match(name),
+        }
+    }
+}
+
+impl ExplorerFilter {
+    pub fn is_empty(&self) -> bool {
+        self.query.is_empty()
+    }
+
+    /// Is recursive search shown instead of the folder.
+    pub fn is_searching(&self) -> bool {
+        self.recursive && !self.is_empty()
+    }
+
+    /// Is the query a valid pattern of its mode.
+    pub fn is_valid(&mut self) -> bool {
+        self.is_empty() || self.matcher().is_some()
+    }
+
+    /// Matcher of the query, `None` if it's empty or isn't a valid regex.
+    pub fn matcher(&mut self) -> Option<NameMatcher> {
+        if self.is_empty() {
+            return None;
+        }
+
+        match self.mode {
+            FilterMode::Substring => Some(NameMatcher::Substring(self.query.to_lowercase())),
+            FilterMode::Glob => Some(NameMatcher::Glob(self.query.clone())),
+            FilterMode::Regex => {
+                let query = &self.query;
+                if !matches!(&self.regex, Some((it, _)) if it == query) {
+                    let regex = regex::RegexBuilder::new(query)
+                        .case_insensitive(true)
+                        .build()
+                        .ok();
+                    self.regex = Some((query.clone(), regex));
+                }
+
+                let (_, regex) = self.regex.as_ref()?;
+                regex.clone().map(NameMatcher::Regex)
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.query.clear();
+    }
+
+    /// Recursive search of the query, `None` if it isn't searching.
+    pub fn search(&self) -> Option<ExplorerSearch> {
+        self.is_searching().then(|| ExplorerSearch {
+            query: self.query.clone(),
+            mode: self.mode,
+        })
+    }
+}
+
+/// Names searched in the whole subtree of the explorer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExplorerSearch {
+    pub query: String,
+    pub mode: FilterMode,
+}
+
+impl ExplorerSearch {
+    pub fn matcher(&self) -> Option<NameMatcher> {
+        let mut filter = ExplorerFilter {
+            query: self.query.clone(),
+            mode: self.mode,
+            ..Default::default()
+        };
+
+        filter.matcher()
+    }
+}
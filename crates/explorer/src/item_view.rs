@@ -1,6 +1,6 @@
 use fmr_frame::{SplittedTextureWidget, TextureView};
 
-use super::ExplorerItem;
+use super::{ExplorerItem, ReadingStatus};
 
 pub struct ExplorerItemView<'a, Item: ExplorerItem> {
     item: &'a mut Item,
@@ -59,9 +59,10 @@ impl<'a, Item: ExplorerItem> ExplorerItemView<'a, Item> {
             image_container_size,
         } = self;
 
+        let status = item.status();
         let response = ui.vertical_centered(|ui| {
             ui.allocate_ui(container_size, |ui| {
-                ui.allocate_ui(image_container_size, |ui| {
+                let image = ui.allocate_ui(image_container_size, |ui| {
                     ui.centered_and_justified(|ui| {
                         TextureView::new(item.thumbnail()).show(ui, |_, handle| {
                             let scale = image_size / handle.size_vec2();
@@ -70,6 +71,10 @@ impl<'a, Item: ExplorerItem> ExplorerItemView<'a, Item> {
                     })
                 });
 
+                if let Some(status) = status {
+                    paint_status(ui, image.response.rect, status);
+                }
+
                 ui.allocate_ui(egui::vec2(ui.available_width() * 0.8, 0.0), |ui| {
                     ui.centered_and_justified(|ui| {
                         ui.add(egui::Label::new(item.name()).wrap());
@@ -124,3 +129,54 @@ impl<'a, Item: ExplorerItem> ExplorerItemView<'a, Item> {
         response
     }
 }
+
+/// Paint the "new" dot, progress bar or finished check over the image `rect`.
+fn paint_status(ui: &egui::Ui, rect: egui::Rect, status: ReadingStatus) {
+    let painter = ui.painter();
+    let badge_radius = (rect.width() * 0.04).clamp(5.0, 14.0);
+    let badge_center = rect.right_top() + egui::vec2(-badge_radius * 2.0, badge_radius * 2.0);
+
+    match status {
+        ReadingStatus::New => {
+            let color = ui.visuals().selection.bg_fill;
+            painter.circle(
+                badge_center,
+                badge_radius * 0.6,
+                color,
+                egui::Stroke::new(1.0, ui.visuals().extreme_bg_color),
+            );
+        }
+        ReadingStatus::Reading(ratio) => {
+            let height = (rect.height() * 0.02).clamp(3.0, 6.0);
+            let bar = egui::Rect::from_min_max(
+                egui::pos2(rect.left(), rect.bottom() - height),
+                rect.right_bottom(),
+            );
+            let rounding = height / 2.0;
+            painter.rect_filled(bar, rounding, ui.visuals().extreme_bg_color);
+
+            let filled = egui::Rect::from_min_max(
+                bar.min,
+                egui::pos2(
+                    bar.lerp_inside(egui::vec2(ratio.clamp(0.0, 1.0), 0.0)).x,
+                    bar.max.y,
+                ),
+            );
+            painter.rect_filled(filled, rounding, ui.visuals().selection.bg_fill);
+        }
+        ReadingStatus::Finished => {
+            painter.circle_filled(
+                badge_center,
+                badge_radius,
+                egui::Color32::from_rgb(60, 150, 80),
+            );
+            painter.text(
+                badge_center,
+                egui::Align2::CENTER_CENTER,
+                "✔",
+                egui::FontId::proportional(badge_radius * 1.4),
+                egui::Color32::WHITE,
+            );
+        }
+    }
+}
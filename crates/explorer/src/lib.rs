@@ -1,19 +1,25 @@
 mod cache;
+mod filter;
 mod item_view;
 mod loader;
 mod view;
 
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::PathBuf,
+    time::SystemTime,
+};
 
 pub use self::cache::*;
+pub use self::filter::*;
 pub use self::item_view::*;
 pub use self::loader::*;
 pub use self::view::*;
 use derivative::Derivative;
 use serde::{Deserialize, Serialize};
 
-use fmr_scroll::ScrollState;
 use fmr_frame::TextureViewState;
+use fmr_scroll::ScrollState;
 
 pub struct Explorer<Item: ExplorerItem> {
     pub scroll: ScrollState,
@@ -21,8 +27,81 @@ pub struct Explorer<Item: ExplorerItem> {
     current: usize,
     pub id: egui::Id,
     row_size: HashMap<usize, egui::Vec2>,
+    // size of each item in masonry with the image size it's measured with.
+    item_size: HashMap<usize, (egui::Vec2, egui::Vec2)>,
     pub column_count: usize,
     scroll_to_index: bool,
+    pub filter: ExplorerFilter,
+    /// items found by recursive search, shown instead of content while searching.
+    pub found: Vec<Item>,
+    /// recursive search has walked the whole subtree.
+    pub is_search_done: bool,
+    // indices of shown items matching the filter, `None` when nothing is filtered.
+    visible: Option<Vec<usize>>,
+    // current index of content while search is shown.
+    content_current: usize,
+    was_searching: bool,
+    // selected indices, the current item alone is selected when it's empty.
+    selection: BTreeSet<usize>,
+    // where range selection starts.
+    anchor: Option<usize>,
+    pub layout: ExplorerLayout,
+    // query, mode and hide finished the visible items are filtered by.
+    filtered_by: Option<(String, FilterMode, bool)>,
+}
+
+/// How items are laid out in the explorer.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExplorerLayout {
+    /// square cells of the same size.
+    #[default]
+    Grid,
+    /// list of rows with file information in columns.
+    Details,
+    /// columns of thumbnails keeping their aspect ratio.
+    Masonry,
+}
+
+impl ExplorerLayout {
+    pub const ALL: [Self; 3] = [Self::Grid, Self::Details, Self::Masonry];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Grid => "Grid",
+            Self::Details => "Details",
+            Self::Masonry => "Masonry",
+        }
+    }
+}
+
+/// Column of [`ExplorerLayout::Details`], items are sorted by clicking its header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DetailsColumn {
+    Name,
+    Size,
+    Modified,
+    PageCount,
+    Progress,
+}
+
+impl DetailsColumn {
+    pub const ALL: [Self; 5] = [
+        Self::Name,
+        Self::Size,
+        Self::Modified,
+        Self::PageCount,
+        Self::Progress,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Name => "Name",
+            Self::Size => "Size",
+            Self::Modified => "Modified",
+            Self::PageCount => "Pages",
+            Self::Progress => "Progress",
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize, Derivative)]
@@ -31,6 +110,8 @@ pub struct Explorer<Item: ExplorerItem> {
 pub struct ExplorerSetting {
     #[derivative(Default(value = "400"))]
     pub minimum_column_width: u32,
+    /// hide items which are read to the end.
+    pub hide_finished: bool,
 }
 
 impl<Item: ExplorerItem> Default for Explorer<Item> {
@@ -41,18 +122,47 @@ impl<Item: ExplorerItem> Default for Explorer<Item> {
             current: 0,
             id: egui::Id::new("explorer"),
             row_size: HashMap::new(),
+            item_size: HashMap::new(),
             column_count: 0,
             scroll_to_index: false,
+            filter: Default::default(),
+            found: Vec::new(),
+            is_search_done: false,
+            visible: None,
+            content_current: 0,
+            was_searching: false,
+            selection: BTreeSet::new(),
+            anchor: None,
+            layout: ExplorerLayout::Grid,
+            filtered_by: None,
         }
     }
 }
 
 impl<Item: ExplorerItem> Explorer<Item> {
+    // forget measured sizes of items, e.g. items are moved.
+    fn clear_layout(&mut self) {
+        self.row_size.clear();
+        self.item_size.clear();
+    }
+
+    /// Move current index by `step` items among the items that are shown.
     pub fn move_by(&mut self, step: isize) -> bool {
-        if let Some(it) = self.current.checked_add_signed(step) {
-            self.set_index(it)
-        } else {
-            false
+        let Some(visible) = &self.visible else {
+            return match self.current.checked_add_signed(step) {
+                Some(it) => self.set_index(it),
+                None => false,
+            };
+        };
+
+        let position = visible.iter().position(|it| *it == self.current);
+        let index = position
+            .and_then(|it| it.checked_add_signed(step))
+            .and_then(|it| visible.get(it).copied());
+
+        match index {
+            Some(index) => self.set_index(index),
+            None => false,
         }
     }
 
@@ -61,7 +171,7 @@ impl<Item: ExplorerItem> Explorer<Item> {
     }
 
     pub fn set_index(&mut self, index: usize) -> bool {
-        if index < self.content.len() {
+        if index < self.items().len() {
             self.current = index;
             true
         } else {
@@ -77,24 +187,266 @@ impl<Item: ExplorerItem> Explorer<Item> {
         self.scroll_to_index = true;
     }
 
+    /// Items shown, which is search result while searching recursively.
     pub fn items(&self) -> &Vec<Item> {
-        &self.content
+        if self.filter.is_searching() {
+            &self.found
+        } else {
+            &self.content
+        }
     }
 
     pub fn current_item(&self) -> Option<&Item> {
-        self.content.get(self.current)
+        self.items().get(self.current)
+    }
+
+    /// Select item at `index` by click with `modifiers`, it's added to the selection with
+    /// command and the range from the last clicked item is selected with shift.
+    pub fn select(&mut self, index: usize, modifiers: egui::Modifiers) {
+        if modifiers.shift {
+            self.select_range(index);
+        } else if modifiers.command {
+            if self.selection.is_empty() {
+                self.selection.insert(self.current);
+            }
+            if !self.selection.remove(&index) {
+                self.selection.insert(index);
+            }
+            self.anchor = Some(index);
+        } else {
+            self.selection.clear();
+            self.anchor = Some(index);
+        }
+
+        self.set_index(index);
+    }
+
+    /// Select shown items from the anchor to `index`.
+    pub fn select_range(&mut self, index: usize) {
+        let anchor = *self.anchor.get_or_insert(self.current);
+        let shown = match &self.visible {
+            Some(visible) => visible.clone(),
+            None => (0..self.items().len()).collect(),
+        };
+
+        let position = |index| shown.iter().position(|it| *it == index);
+        if let (Some(start), Some(end)) = (position(anchor), position(index)) {
+            let range = start.min(end)..=start.max(end);
+            self.selection = shown[range].iter().copied().collect();
+        }
+    }
+
+    pub fn is_selected(&self, index: usize) -> bool {
+        if self.selection.is_empty() {
+            index == self.current
+        } else {
+            self.selection.contains(&index)
+        }
+    }
+
+    /// Selected indices in order, which is the current one when nothing else is selected.
+    pub fn selection(&self) -> Vec<usize> {
+        if self.selection.is_empty() {
+            (self.current < self.items().len())
+                .then_some(self.current)
+                .into_iter()
+                .collect()
+        } else {
+            self.selection.iter().copied().collect()
+        }
+    }
+
+    pub fn selected_items(&self) -> Vec<&Item> {
+        let items = self.items();
+        self.selection()
+            .into_iter()
+            .filter_map(|it| items.get(it))
+            .collect()
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selection.clear();
+        self.anchor = None;
+    }
+
+    /// Indices of items matching the filter, `None` if every item is shown.
+    pub fn visible(&self) -> Option<&[usize]> {
+        self.visible.as_deref()
+    }
+
+    /// Apply the filter to items, current index is moved to the first match when it's
+    /// filtered out. Finished items are filtered out too if `hide_finished`.
+    pub fn update_filter(&mut self, hide_finished: bool) {
+        let is_searching = self.filter.is_searching();
+        if is_searching != self.was_searching {
+            std::mem::swap(&mut self.current, &mut self.content_current);
+            self.was_searching = is_searching;
+            self.clear_selection();
+            self.scroll_to_index = true;
+        }
+
+        // search result only contains matches already.
+        let is_filtered = !self.filter.is_empty() && !is_searching;
+        let visible = match (is_filtered || hide_finished, self.filter.matcher()) {
+            (false, _) => None,
+            (true, None) if is_filtered => Some(vec![]),
+            (true, matcher) => Some(
+                self.items()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, it)| {
+                        !(hide_finished && it.status() == Some(ReadingStatus::Finished))
+                    })
+                    .filter(|(_, it)| {
+                        !is_filtered || matcher.as_ref().is_some_and(|m| m.is_match(it.name()))
+                    })
+                    .map(|(i, _)| i)
+                    .collect(),
+            ),
+        };
+
+        let filtered_by = Some((self.filter.query.clone(), self.filter.mode, hide_finished));
+        let is_filter_changed = filtered_by != self.filtered_by;
+        self.filtered_by = filtered_by;
+
+        if visible != self.visible {
+            self.clear_layout();
+            self.visible = visible;
+
+            // items are changed without changing the filter, e.g. by file system.
+            if !is_filter_changed {
+                if let Some(visible) = &self.visible {
+                    self.selection.retain(|it| visible.contains(it));
+                }
+                return;
+            }
+
+            self.clear_selection();
+
+            if let Some(visible) = &self.visible {
+                if !visible.contains(&self.current) {
+                    if let Some(first) = visible.first() {
+                        self.current = *first;
+                    }
+                }
+            }
+            self.scroll_to_index = true;
+        }
+    }
+
+    /// Insert `item` into content at `index`, the current and selected items are kept.
+    pub fn insert(&mut self, index: usize, item: Item) {
+        let index = index.min(self.content.len());
+        self.content.insert(index, item);
+        self.clear_layout();
+
+        let shift = |it: &mut usize| {
+            if *it >= index {
+                *it += 1;
+            }
+        };
+
+        if self.filter.is_searching() {
+            shift(&mut self.content_current);
+        } else {
+            shift(&mut self.current);
+            if let Some(anchor) = &mut self.anchor {
+                shift(anchor);
+            }
+            self.selection = self
+                .selection
+                .iter()
+                .map(|it| if *it >= index { it + 1 } else { *it })
+                .collect();
+        }
+    }
+
+    /// Remove item at `index` of content, the current item moves to the next one if it's
+    /// removed.
+    pub fn remove(&mut self, index: usize) -> Option<Item> {
+        if index >= self.content.len() {
+            return None;
+        }
+        let item = self.content.remove(index);
+        self.clear_layout();
+
+        let last = self.content.len().saturating_sub(1);
+        let shift = |it: &mut usize| {
+            if *it > index {
+                *it -= 1;
+            }
+            *it = (*it).min(last);
+        };
+
+        if self.filter.is_searching() {
+            shift(&mut self.content_current);
+        } else {
+            shift(&mut self.current);
+            if let Some(anchor) = &mut self.anchor {
+                shift(anchor);
+            }
+            self.selection = self
+                .selection
+                .iter()
+                .filter(|it| **it != index)
+                .map(|it| if *it > index { it - 1 } else { *it })
+                .collect();
+        }
+
+        Some(item)
     }
 }
 
 pub trait ExplorerItem {
     fn name(&self) -> &str;
     fn thumbnail(&mut self) -> &mut TextureViewState;
+
+    /// Reading status shown as a badge on the item, nothing is shown if `None`.
+    fn status(&self) -> Option<ReadingStatus> {
+        None
+    }
+
+    /// File information shown in [`ExplorerLayout::Details`].
+    fn details(&self) -> Option<&ItemDetails> {
+        None
+    }
+}
+
+/// File information of an item, each of them is `None` if it's unknown.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ItemDetails {
+    /// size of file or files directly in folder.
+    pub size: Option<u64>,
+    pub modified: Option<SystemTime>,
+    pub page_count: Option<usize>,
+}
+
+/// How far an item is read.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReadingStatus {
+    /// never opened.
+    New,
+    /// opened and read to the ratio in `0.0..=1.0`.
+    Reading(f32),
+    Finished,
+}
+
+/// Reading progress that [`ExplorerLoader`] reads status of items from.
+pub trait ReadingStatusInfo: Send + Sync {
+    /// Reading status of `path`, a folder never opened as a book is rolled up from its
+    /// `children` books.
+    fn status(&self, path: &std::path::Path, children: &[PathBuf]) -> ReadingStatus;
+    /// Receiver notified when the progress is changed.
+    fn subscribe(&self) -> tokio::sync::watch::Receiver<()>;
 }
 
 pub struct PathExplorerItem {
     name: String,
     pub path: PathBuf,
     pub thumbnail: TextureViewState,
+    pub status: Option<ReadingStatus>,
+    /// loaded only when the details are shown.
+    pub details: Option<ItemDetails>,
 }
 
 impl PathExplorerItem {
@@ -104,8 +456,19 @@ impl PathExplorerItem {
             name: path.file_name()?.to_string_lossy().to_string(),
             path,
             thumbnail: TextureViewState::Loading(Default::default()),
+            status: None,
+            details: None,
         })
     }
+
+    /// Change path of the item, e.g. it's renamed.
+    pub fn rename(&mut self, path: PathBuf) {
+        if let Some(name) = path.file_name() {
+            self.name = name.to_string_lossy().to_string();
+        }
+        self.path = path;
+        self.details = None;
+    }
 }
 
 impl ExplorerItem for PathExplorerItem {
@@ -116,4 +479,95 @@ impl ExplorerItem for PathExplorerItem {
     fn thumbnail(&mut self) -> &mut TextureViewState {
         &mut self.thumbnail
     }
+
+    fn status(&self) -> Option<ReadingStatus> {
+        self.status
+    }
+
+    fn details(&self) -> Option<&ItemDetails> {
+        self.details.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // explorer of items named by their index, the range from 1 to 3 is selected.
+    fn explorer(len: usize) -> Explorer<PathExplorerItem> {
+        let mut explorer = Explorer {
+            content: (0..len).map(|it| item(&it.to_string())).collect(),
+            ..Default::default()
+        };
+        explorer.select(1, egui::Modifiers::NONE);
+        explorer.select(3, egui::Modifiers::SHIFT);
+        explorer
+    }
+
+    fn item(name: &str) -> PathExplorerItem {
+        PathExplorerItem::new(name.into()).unwrap()
+    }
+
+    #[test]
+    fn insert_keeps_selection() {
+        let mut explorer = explorer(5);
+        explorer.insert(2, item("new"));
+
+        assert_eq!(explorer.content[2].name(), "new");
+        assert_eq!(explorer.current_index(), 4);
+        assert_eq!(explorer.anchor, Some(1));
+        assert_eq!(explorer.selection(), vec![1, 3, 4]);
+        assert_eq!(explorer.current_item().unwrap().name(), "3");
+
+        explorer.insert(usize::MAX, item("last"));
+        assert_eq!(explorer.content[6].name(), "last");
+        assert_eq!(explorer.current_index(), 4);
+    }
+
+    #[test]
+    fn remove_keeps_selection() {
+        let mut explorer = explorer(5);
+        let removed = explorer.remove(0);
+
+        assert_eq!(removed.unwrap().name(), "0");
+        assert_eq!(explorer.current_index(), 2);
+        assert_eq!(explorer.anchor, Some(0));
+        assert_eq!(explorer.selection(), vec![0, 1, 2]);
+
+        // selected item is removed from the selection.
+        explorer.remove(1);
+        assert_eq!(explorer.selection(), vec![0, 1]);
+        assert_eq!(explorer.current_item().unwrap().name(), "3");
+
+        assert!(explorer.remove(10).is_none());
+    }
+
+    #[test]
+    fn remove_current_moves_to_next() {
+        let mut explorer = explorer(5);
+        explorer.remove(3);
+        assert_eq!(explorer.current_item().unwrap().name(), "4");
+
+        // the last item moves to the one before it.
+        explorer.remove(3);
+        assert_eq!(explorer.current_item().unwrap().name(), "2");
+        assert_eq!(explorer.current_index(), 2);
+    }
+
+    #[test]
+    fn search_keeps_current_of_content() {
+        let mut explorer = explorer(5);
+        explorer.filter.query = "name".to_string();
+        explorer.filter.recursive = true;
+        explorer.content_current = 3;
+
+        explorer.insert(0, item("new"));
+        assert_eq!(explorer.content_current, 4);
+        explorer.remove(1);
+        assert_eq!(explorer.content_current, 3);
+
+        // current item and selection are of found items.
+        assert_eq!(explorer.current_index(), 3);
+        assert_eq!(explorer.selection(), vec![1, 2, 3]);
+    }
 }
@@ -1,7 +1,7 @@
 use std::{
     collections::HashMap,
     ops::Range,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicUsize, Ordering},
         Arc, Weak,
@@ -10,7 +10,7 @@ use std::{
 };
 
 use derivative::Derivative;
-use futures::StreamExt;
+use futures::{FutureExt, StreamExt};
 use itertools::Itertools;
 use parking_lot::{Mutex, RwLock};
 use serde::{Deserialize, Serialize};
@@ -22,11 +22,21 @@ use tracing::instrument;
 use zip::ZipArchive;
 
 use fmr_core::{
-    path::PathSorterType, spawn_and_abort_on_drop, split_and_interleave_at, AbortOnDropHandle,
+    exclude::ExcludeRules,
+    password::Passwords,
+    path::PathSorterSubscriber,
+    source::PasswordRequired,
+    spawn_and_abort_on_drop, split_and_interleave_at,
+    watch::{DirChange, DirWatcher},
+    AbortOnDropHandle,
 };
+use fmr_egui::tools::zip::NameEncoding;
 use fmr_frame::TextureOption;
 
-use super::{cache::ExplorerLoaderCache, sha_path, Explorer, PathExplorerItem};
+use super::{
+    cache::ExplorerLoaderCache, sha_path, Explorer, ExplorerSearch, ItemDetails, PathExplorerItem,
+    ReadingStatusInfo,
+};
 use fmr_frame::FilterType;
 
 #[derive(Clone, Derivative)]
@@ -38,16 +48,54 @@ pub struct ExplorerLoader {
     pub selected_entry: Option<PathBuf>,
     pub setting_receiver: watch::Receiver<ExplorerLoaderSetting>,
     pub cache: ExplorerLoaderCache,
+    pub passwords: Passwords,
+    pub exclude: ExcludeRules,
+    /// tried first for zip entry name that isn't UTF-8.
+    pub zip_name_encoding: NameEncoding,
+    /// order of content, the explorer is sorted again when it's changed.
+    #[derivative(Debug = "ignore")]
+    pub sorter: PathSorterSubscriber,
+    /// reading status of items is read from it, nothing is shown if `None`.
     #[derivative(Debug = "ignore")]
-    pub sorter: PathSorterType,
+    pub reading_status: Option<Arc<dyn ReadingStatusInfo>>,
     #[derivative(Debug = "ignore")]
     pub ctx: egui::Context,
 }
 
+// what woke up the loader.
+enum LoaderEvent {
+    Setting,
+    Sorted,
+    /// reading progress is changed.
+    Progress,
+    /// changes of the directory, `None` if it's not watched anymore.
+    Changed(Option<Vec<DirChange>>),
+}
+
+/// Maximum number of items found by recursive search.
+pub const SEARCH_LIMIT: usize = 5000;
+
+/// Time the query has to stay the same before it's searched, so typing it doesn't walk the
+/// subtree for every key.
+pub const SEARCH_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(300);
+
+/// Extensions of files counted as books when reading status of a folder is rolled up.
+const BOOK_EXTENSIONS: [&str; 11] = [
+    "zip", "cbz", "rar", "cbr", "7z", "cb7", "tar", "cbt", "pdf", "epub", "txt",
+];
+
+// child books of each item, empty for files.
+type ChildBooks = Arc<Mutex<HashMap<PathBuf, Vec<PathBuf>>>>;
+
 #[derive(Clone, PartialEq, Eq, Debug, Default)]
 pub struct ExplorerLoaderSetting {
     pub index: usize,
     pub entry_setting: ExplorerEntryLoaderSetting,
+    /// indices of content matching the filter, thumbnail of them is loaded first.
+    pub visible: Option<Vec<usize>>,
+    pub search: Option<ExplorerSearch>,
+    /// file information of items is shown, it's loaded only when it's needed.
+    pub details: bool,
 }
 
 impl ExplorerLoaderSetting {
@@ -64,28 +112,47 @@ impl ExplorerLoader {
             path,
             selected_entry,
             mut setting_receiver,
-            sorter,
+            mut sorter,
             ctx,
             cache: _,
+            passwords: _,
+            exclude,
+            zip_name_encoding,
+            reading_status,
         } = self.clone();
 
-        let dir = match std::fs::read_dir(&path) {
-            Ok(dir) => dir,
-            _ => return,
-        };
-
-        let mut content = vec![];
-
-        for it in dir.flatten() {
-            content.push(it);
-        }
+        // listing and sorting read the file system, which may be slow.
+        let (list_path, list_sorter) = (path.clone(), sorter.clone());
+        let content = tokio::task::spawn_blocking(move || {
+            let mut content = match std::fs::read_dir(&list_path) {
+                Ok(dir) => dir.flatten().map(|it| it.path()).collect::<Vec<_>>(),
+                // browse archive as directory.
+                Err(_) => {
+                    let dir = fmr_egui::tools::archive_dir::ArchiveDir::open_path(
+                        &list_path,
+                        zip_name_encoding,
+                    )?;
+                    dir.children(&exclude)
+                        .into_iter()
+                        .map(|it| list_path.join(it.name))
+                        .collect()
+                }
+            };
 
-        content.sort_by(|a, b| sorter(&a.path(), &b.path()));
+            list_sorter.sort_paths(&mut content);
+            Some(content)
+        });
+        let Ok(Some(content)) = content.await else {
+            return;
+        };
         explorer.write().content.reserve(content.len());
         let priority = Arc::new(Mutex::new(PriorityRange::new(0..content.len())));
-        let semaphore = PrioritySemaphore::new(4, 0, priority);
+        let semaphore = PrioritySemaphore::new(4, 0, priority.clone());
 
-        let mut child = Vec::new();
+        // thumbnail being loaded of each entry, it's aborted when the entry is removed.
+        let mut loading = HashMap::new();
+        // entry added later is loaded by the index after the others.
+        let mut next_index = content.len();
         let size = 300;
 
         let entry_setting = Arc::new(Mutex::new(
@@ -112,8 +179,8 @@ impl ExplorerLoader {
             );
 
             if let Some(it) = future {
-                child.push(it);
-                if Some(entry.path()) == selected_entry {
+                loading.insert(entry.clone(), it);
+                if Some(&entry) == selected_entry.as_ref() {
                     explorer.write().set_index(i);
                 }
             }
@@ -121,77 +188,759 @@ impl ExplorerLoader {
 
         explorer.write().scroll_to_current();
 
+        // entries are loaded by their position in content when it's loaded.
+        let mut load_order = explorer
+            .read()
+            .content
+            .iter()
+            .enumerate()
+            .map(|(i, it)| (it.path.clone(), i))
+            .collect::<HashMap<_, _>>();
+
+        // archive browsed as directory isn't watched.
+        let mut watcher = DirWatcher::new(&path)
+            .inspect_err(|err| tracing::debug!("can't watch {:?}: {}", path, err))
+            .ok();
+
+        let mut progress = reading_status.as_ref().map(|it| it.subscribe());
+        let children = ChildBooks::default();
+
+        let mut search: Option<(ExplorerSearch, AbortOnDropHandle<()>)> = None;
+        let mut details: Option<AbortOnDropHandle<()>> = None;
+        // dropping it aborts reading statuses that are outdated.
+        let mut _statuses: Option<AbortOnDropHandle<()>> = None;
+        let mut is_status_outdated = true;
         loop {
-            {
+            let (search_setting, is_details_shown) = {
                 let it = setting_receiver.borrow();
                 semaphore
                     .interleave_index
                     .store(it.index, Ordering::Release);
                 *entry_setting.lock() = it.entry_setting.clone();
+
+                let mut priority = priority.lock();
+                priority.clear_filter();
+                if let Some(visible) = &it.visible {
+                    let len = priority.range.len();
+                    let visible = visible.iter().collect::<std::collections::HashSet<_>>();
+                    for i in (0..len).filter(|it| !visible.contains(it)) {
+                        priority.filter(i);
+                    }
+                }
+
+                (it.search.clone(), it.details)
+            };
+
+            if search.as_ref().map(|(it, _)| it) != search_setting.as_ref() {
+                search = search_setting.map(|it| {
+                    let handle =
+                        self.search(it.clone(), default_texture.clone(), entry_setting.clone());
+                    (it, handle)
+                });
+
+                if search.is_none() {
+                    explorer.write().found.clear();
+                }
+                is_status_outdated = true;
+            }
+
+            if is_status_outdated {
+                _statuses = self.statuses(children.clone());
+                is_status_outdated = false;
+            }
+
+            let is_details_loading = details.as_ref().is_some_and(|it| !it.0.is_finished());
+            if is_details_shown && !is_details_loading {
+                details = Some(self.details());
+            }
+
+            let event = {
+                let waiter = setting_receiver.changed().fuse();
+                let sorter_waiter = async {
+                    // the order never changes once the setting is dropped.
+                    if !sorter.changed().await {
+                        futures::future::pending::<()>().await;
+                    }
+                };
+                let sorter_waiter = sorter_waiter.fuse();
+                let watcher_waiter = async {
+                    match &mut watcher {
+                        Some(watcher) => watcher.changed().await,
+                        None => futures::future::pending().await,
+                    }
+                };
+                let watcher_waiter = watcher_waiter.fuse();
+                let progress_waiter = async {
+                    let changed = match &mut progress {
+                        Some(progress) => progress.changed().await.is_ok(),
+                        None => false,
+                    };
+                    if !changed {
+                        futures::future::pending::<()>().await;
+                    }
+                };
+                let progress_waiter = progress_waiter.fuse();
+                futures::pin_mut!(waiter);
+                futures::pin_mut!(sorter_waiter);
+                futures::pin_mut!(watcher_waiter);
+                futures::pin_mut!(progress_waiter);
+
+                futures::select! {
+                    changed = waiter => {
+                        if changed.is_err() {
+                            break;
+                        }
+                        LoaderEvent::Setting
+                    }
+                    _ = sorter_waiter => LoaderEvent::Sorted,
+                    _ = progress_waiter => LoaderEvent::Progress,
+                    changes = watcher_waiter => LoaderEvent::Changed(changes),
+                }
+            };
+
+            match event {
+                LoaderEvent::Setting => {}
+                LoaderEvent::Sorted => {
+                    Self::sort(&explorer, &sorter, &load_order, &priority).await;
+                    ctx.request_repaint();
+                }
+                LoaderEvent::Progress => is_status_outdated = true,
+                LoaderEvent::Changed(None) => watcher = None,
+                LoaderEvent::Changed(Some(mut changes)) => {
+                    // changes are lost, the whole directory is read again.
+                    if changes.contains(&DirChange::Rescan) {
+                        changes = self.rescan().await;
+                        children.lock().clear();
+                    }
+
+                    // books in changed folders are listed again.
+                    for change in &changes {
+                        let mut children = children.lock();
+                        match change {
+                            DirChange::Created(it)
+                            | DirChange::Modified(it)
+                            | DirChange::Removed(it) => {
+                                children.remove(it);
+                            }
+                            DirChange::Renamed { from, to } => {
+                                children.remove(to);
+                                children.remove(from);
+                            }
+                            DirChange::Rescan => {}
+                        }
+                    }
+                    is_status_outdated = true;
+                    // file information of changed items is read again.
+                    details = None;
+
+                    let reload = self.apply_changes(changes).await;
+
+                    // removed entries stop loading, new ones are given an index to load by.
+                    {
+                        let explorer = explorer.read();
+                        let paths = explorer
+                            .content
+                            .iter()
+                            .map(|it| &it.path)
+                            .collect::<std::collections::HashSet<_>>();
+                        load_order.retain(|it, _| paths.contains(it));
+                        loading.retain(|it, _| paths.contains(it));
+
+                        for path in &reload {
+                            load_order.entry(path.clone()).or_insert_with(|| {
+                                next_index += 1;
+                                next_index - 1
+                            });
+                        }
+                        Self::reorder(&explorer, &load_order, &priority);
+                    }
+
+                    // loading the same entry again aborts the previous one.
+                    for path in reload {
+                        let Some(index) = load_order.get(&path).copied() else {
+                            continue;
+                        };
+                        let handle = self.load_thumbnail(
+                            index,
+                            path.clone(),
+                            default_texture.clone(),
+                            semaphore.clone(),
+                            entry_setting.clone(),
+                        );
+                        loading.insert(path, handle);
+                    }
+                    ctx.request_repaint();
+                }
             }
+        }
+    }
 
-            if setting_receiver.changed().await.is_err() {
-                break;
+    // apply changes of the directory to content keeping the current and selected items,
+    // returns new and modified entries whose thumbnail should be loaded again.
+    async fn apply_changes(&self, changes: Vec<DirChange>) -> Vec<PathBuf> {
+        let Self {
+            explorer,
+            path,
+            cache,
+            ..
+        } = self;
+
+        let position = |path: &std::path::Path| {
+            explorer
+                .read()
+                .content
+                .iter()
+                .position(|it| it.path == path)
+        };
+
+        let mut reload = vec![];
+        for change in changes {
+            tracing::debug!("{:?} changed: {:?}", path, change);
+
+            match change {
+                DirChange::Created(it) | DirChange::Modified(it) => {
+                    cache.remove_path(&it);
+                    match position(&it) {
+                        Some(index) => explorer.write().content[index].details = None,
+                        None => {
+                            if let Some(item) = PathExplorerItem::new(it.clone()) {
+                                self.insert(item).await;
+                            }
+                        }
+                    }
+                    reload.push(it);
+                }
+                DirChange::Removed(it) => {
+                    cache.remove_path(&it);
+                    if let Some(index) = position(&it) {
+                        explorer.write().remove(index);
+                    }
+                }
+                DirChange::Renamed { from, to } => {
+                    cache.remove_path(&from);
+                    cache.remove_path(&to);
+                    // entry it replaces is removed.
+                    if let Some(index) = position(&to) {
+                        explorer.write().remove(index);
+                    }
+
+                    let item = position(&from).and_then(|it| explorer.write().remove(it));
+                    let item = match item {
+                        Some(mut item) => {
+                            item.rename(to.clone());
+                            Some(item)
+                        }
+                        None => PathExplorerItem::new(to.clone()),
+                    };
+                    if let Some(item) = item {
+                        self.insert(item).await;
+                    }
+                    reload.push(to);
+                }
+                // it's read by `rescan` before changes are applied.
+                DirChange::Rescan => {}
             }
         }
+
+        // cover of the directory may be one of the changed entries.
+        cache.remove_path(path);
+        reload.sort();
+        reload.dedup();
+        reload
+    }
+
+    // insert `item` into content by the order, sort values may read the file system so
+    // they're read without locking the explorer.
+    async fn insert(&self, item: PathExplorerItem) {
+        let paths = self
+            .explorer
+            .read()
+            .content
+            .iter()
+            .map(|it| it.path.clone())
+            .collect::<Vec<_>>();
+
+        let (sorter, path) = (self.sorter.clone(), item.path.clone());
+        let index = tokio::task::spawn_blocking(move || {
+            let values = sorter.values(&path);
+            paths.partition_point(|it| {
+                let it = (it.as_path(), &sorter.values(it));
+                sorter.compare_values(it, (&path, &values)).is_lt()
+            })
+        })
+        .await;
+
+        // content is only changed by this task, the index is still right.
+        self.explorer
+            .write()
+            .insert(index.unwrap_or(usize::MAX), item);
+    }
+
+    // changes that bring content up to date with the directory, used when changes are lost.
+    // File information of every item is read again.
+    async fn rescan(&self) -> Vec<DirChange> {
+        let path = self.path.clone();
+        let listed = tokio::task::spawn_blocking(move || {
+            let dir = std::fs::read_dir(path)?;
+            let listed = dir.flatten().map(|it| it.path());
+            Ok::<_, std::io::Error>(listed.collect::<std::collections::HashSet<_>>())
+        })
+        .await;
+        let Ok(Ok(listed)) = listed else {
+            return vec![];
+        };
+
+        let mut explorer = self.explorer.write();
+        explorer.content.iter_mut().for_each(|it| it.details = None);
+
+        let content = explorer
+            .content
+            .iter()
+            .map(|it| it.path.clone())
+            .collect::<std::collections::HashSet<_>>();
+        let removed = content
+            .iter()
+            .filter(|it| !listed.contains(*it))
+            .cloned()
+            .map(DirChange::Removed);
+        let created = listed
+            .iter()
+            .filter(|it| !content.contains(*it))
+            .cloned()
+            .map(DirChange::Created);
+
+        removed.chain(created).collect()
+    }
+
+    // sort content again keeping the current item, entries are loaded by their new position.
+    async fn sort(
+        explorer: &RwLock<Explorer<PathExplorerItem>>,
+        sorter: &PathSorterSubscriber,
+        load_order: &HashMap<PathBuf, usize>,
+        priority: &Mutex<PriorityRange>,
+    ) {
+        let mut paths = explorer
+            .read()
+            .content
+            .iter()
+            .map(|it| it.path.clone())
+            .collect::<Vec<_>>();
+
+        // sort values may read metadata of every path.
+        let sorter = sorter.clone();
+        let Ok(paths) = tokio::task::spawn_blocking(move || {
+            sorter.sort_paths(&mut paths);
+            paths
+        })
+        .await
+        else {
+            return;
+        };
+
+        let mut explorer = explorer.write();
+        let current = explorer.current_item().map(|it| it.path.clone());
+
+        let mut items = std::mem::take(&mut explorer.content)
+            .into_iter()
+            .map(|it| (it.path.clone(), it))
+            .collect::<HashMap<_, _>>();
+        let mut content = paths
+            .iter()
+            .filter_map(|it| items.remove(it))
+            .collect::<Vec<_>>();
+        content.extend(items.into_values());
+        explorer.content = content;
+        explorer.clear_layout();
+
+        explorer.clear_selection();
+        if let Some(index) = explorer
+            .content
+            .iter()
+            .position(|it| Some(&it.path) == current.as_ref())
+        {
+            explorer.set_index(index);
+            explorer.scroll_to_current();
+        }
+
+        Self::reorder(&explorer, load_order, priority);
+    }
+
+    // entries are loaded by their current position.
+    fn reorder(
+        explorer: &Explorer<PathExplorerItem>,
+        load_order: &HashMap<PathBuf, usize>,
+        priority: &Mutex<PriorityRange>,
+    ) {
+        let order = explorer
+            .content
+            .iter()
+            .filter_map(|it| load_order.get(&it.path).copied())
+            .collect();
+        priority.lock().reorder(order);
     }
 
     pub fn load_entry(
         &self,
         index: usize,
-        entry: &std::fs::DirEntry,
+        path: &std::path::Path,
         default_texture: fmr_frame::TextureHandle,
         semaphore: PrioritySemaphore,
         entry_setting: Arc<Mutex<ExplorerEntryLoaderSetting>>,
     ) -> Option<AbortOnDropHandle<()>> {
-        let Self {
-            explorer,
-            cache,
-            ctx,
-            sorter: _,
-            path: _,
-            selected_entry: _,
-            setting_receiver: _,
-        } = self.clone();
-
-        let it = PathExplorerItem::new(entry.path())?;
+        let it = PathExplorerItem::new(path.to_path_buf())?;
+        self.explorer.write().content.push(it);
 
-        explorer.write().content.push(it);
+        Some(self.load_thumbnail(
+            index,
+            path.to_path_buf(),
+            default_texture,
+            semaphore,
+            entry_setting,
+        ))
+    }
 
-        let path = entry.path();
+    // load thumbnail of entry at `path` in content, waiting for its turn by `index`.
+    fn load_thumbnail(
+        &self,
+        index: usize,
+        path: PathBuf,
+        default_texture: fmr_frame::TextureHandle,
+        semaphore: PrioritySemaphore,
+        entry_setting: Arc<Mutex<ExplorerEntryLoaderSetting>>,
+    ) -> AbortOnDropHandle<()> {
+        let Self { explorer, ctx, .. } = self.clone();
 
-        Some(spawn_and_abort_on_drop(async move {
-            let waiter = wait_fn(move || {
-                // let semaphore = semaphore.clone();
-                async {}
-            });
+        // permit is taken for each file read, it's released while password is asked.
+        let waiter = wait_fn(move || semaphore.clone().acquire(index));
+        let thumbnail = self.thumbnail(path.clone(), default_texture, waiter, entry_setting);
 
+        spawn_and_abort_on_drop(async move {
             tokio::task::yield_now().await;
-            let _permit = semaphore.acquire(index).await.unwrap();
-            let image =
-                ExplorerEntryLoader::new(path.clone(), waiter, entry_setting.clone(), cache)
-                    .search()
-                    .await;
+            let texture = thumbnail.await;
+
+            // content may be sorted again since it's loaded.
+            if let Some(it) = explorer
+                .write()
+                .content
+                .iter_mut()
+                .find(|it| it.path == path)
+            {
+                it.thumbnail = texture.into_view_state();
+            }
+            ctx.request_repaint();
+        })
+    }
+}
+
+impl ExplorerLoader {
+    // load thumbnail of `path`, `default_texture` is used when there's no image. Reading
+    // a file waits for `waiter`.
+    fn thumbnail<W>(
+        &self,
+        path: PathBuf,
+        default_texture: fmr_frame::TextureHandle,
+        waiter: W,
+        entry_setting: Arc<Mutex<ExplorerEntryLoaderSetting>>,
+    ) -> impl std::future::Future<Output = fmr_frame::TextureHandle> + Send + 'static
+    where
+        W: Wait + Send + 'static,
+        W::Output: Send,
+        <W::Output as std::future::Future>::Output: Send,
+    {
+        let Self {
+            cache,
+            passwords,
+            exclude,
+            zip_name_encoding,
+            ctx,
+            ..
+        } = self.clone();
 
-            let texture = match image {
+        async move {
+            let image = ExplorerEntryLoader::new(
+                path.clone(),
+                waiter,
+                entry_setting.clone(),
+                cache,
+                passwords,
+                exclude,
+                zip_name_encoding,
+            )
+            .search()
+            .await;
+
+            match image {
                 Some(image) => image.alloc(
                     ctx.tex_manager(),
                     path.to_string_lossy().to_string(),
                     entry_setting.lock().texture_option,
                 ),
                 None => default_texture,
-            };
+            }
+        }
+    }
 
-            explorer.write().content[index].thumbnail = texture.into_view_state();
-            ctx.request_repaint();
+    // read file information of items without it, items found later by the running
+    // search are read too.
+    fn details(&self) -> AbortOnDropHandle<()> {
+        let Self {
+            explorer,
+            sorter,
+            ctx,
+            ..
+        } = self.clone();
+
+        spawn_and_abort_on_drop(async move {
+            loop {
+                let (paths, is_searching) = {
+                    let explorer = explorer.read();
+                    let paths = explorer
+                        .content
+                        .iter()
+                        .chain(explorer.found.iter())
+                        .filter(|it| it.details.is_none())
+                        .map(|it| it.path.clone())
+                        .collect::<Vec<_>>();
+                    let is_searching = explorer.filter.is_searching() && !explorer.is_search_done;
+                    (paths, is_searching)
+                };
+
+                for path in paths {
+                    let sorter = sorter.clone();
+                    let Ok((path, details)) = tokio::task::spawn_blocking(move || {
+                        let details = ItemDetails {
+                            size: fmr_core::path::path_size(&path),
+                            modified: path.metadata().and_then(|it| it.modified()).ok(),
+                            page_count: sorter.page_count(&path),
+                        };
+                        (path, details)
+                    })
+                    .await
+                    else {
+                        return;
+                    };
+
+                    let mut explorer = explorer.write();
+                    let Explorer { content, found, .. } = &mut *explorer;
+                    content
+                        .iter_mut()
+                        .chain(found.iter_mut())
+                        .filter(|it| it.path == path)
+                        .for_each(|it| it.details = Some(details.clone()));
+                    ctx.request_repaint();
+                }
+
+                if !is_searching {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            }
+        })
+    }
+
+    // read reading status of every item, items found later by the running search are read
+    // too. `None` if there's no reading progress.
+    fn statuses(&self, children: ChildBooks) -> Option<AbortOnDropHandle<()>> {
+        let Self {
+            explorer,
+            reading_status,
+            ctx,
+            ..
+        } = self.clone();
+        let reading_status = reading_status?;
+
+        Some(spawn_and_abort_on_drop(async move {
+            let mut is_first = true;
+            loop {
+                let (paths, is_searching) = {
+                    let explorer = explorer.read();
+                    let paths = explorer
+                        .content
+                        .iter()
+                        .chain(explorer.found.iter())
+                        .filter(|it| is_first || it.status.is_none())
+                        .map(|it| it.path.clone())
+                        .collect::<Vec<_>>();
+                    let is_searching = explorer.filter.is_searching() && !explorer.is_search_done;
+                    (paths, is_searching)
+                };
+                is_first = false;
+
+                // folder is read to find its books.
+                let (reading_status, children) = (reading_status.clone(), children.clone());
+                let Ok(statuses) = tokio::task::spawn_blocking(move || {
+                    paths
+                        .into_iter()
+                        .map(|path| {
+                            let cached = children.lock().get(&path).cloned();
+                            let books = cached.unwrap_or_else(|| {
+                                let books = child_books(&path);
+                                children.lock().insert(path.clone(), books.clone());
+                                books
+                            });
+                            let status = reading_status.status(&path, &books);
+                            (path, status)
+                        })
+                        .collect::<HashMap<_, _>>()
+                })
+                .await
+                else {
+                    return;
+                };
+
+                if !statuses.is_empty() {
+                    let mut explorer = explorer.write();
+                    let Explorer { content, found, .. } = &mut *explorer;
+                    for it in content.iter_mut().chain(found.iter_mut()) {
+                        if let Some(status) = statuses.get(&it.path) {
+                            it.status = Some(*status);
+                        }
+                    }
+                    ctx.request_repaint();
+                }
+
+                if !is_searching {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            }
         }))
     }
+
+    // search names in subtree of the path, matches are streamed into `Explorer::found`.
+    fn search(
+        &self,
+        search: ExplorerSearch,
+        default_texture: fmr_frame::TextureHandle,
+        entry_setting: Arc<Mutex<ExplorerEntryLoaderSetting>>,
+    ) -> AbortOnDropHandle<()> {
+        {
+            let mut explorer = self.explorer.write();
+            explorer.found.clear();
+            explorer.is_search_done = false;
+        }
+
+        let this = self.clone();
+        spawn_and_abort_on_drop(async move {
+            let Self {
+                explorer,
+                path,
+                exclude,
+                ctx,
+                ..
+            } = this.clone();
+
+            // the search is dropped when the query changes while waiting.
+            tokio::time::sleep(SEARCH_DEBOUNCE).await;
+
+            let (sender, mut receiver) = tokio::sync::mpsc::channel(64);
+            if let Some(matcher) = search.matcher() {
+                // walking stops when receiver is dropped.
+                tokio::task::spawn_blocking(move || {
+                    let entries = walkdir::WalkDir::new(&path)
+                        .min_depth(1)
+                        .same_file_system(true)
+                        .sort_by(|a, b| {
+                            fmr_core::path::compare_natural(a.file_name(), b.file_name())
+                        })
+                        .into_iter()
+                        .filter_entry(|it| {
+                            let name = it.path().strip_prefix(&path).unwrap_or(it.path());
+                            !exclude.is_excluded(&name.to_string_lossy().replace('\\', "/"))
+                        })
+                        .filter_map(|it| it.ok());
+
+                    for entry in entries {
+                        // stop even when nothing matches after the search is dropped.
+                        if sender.is_closed() {
+                            break;
+                        }
+
+                        let is_match = matcher.is_match(&entry.file_name().to_string_lossy());
+                        if is_match && sender.blocking_send(entry.into_path()).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+
+            let semaphore = Arc::new(Semaphore::new(2));
+            let mut loaders = vec![];
+            while let Some(path) = receiver.recv().await {
+                let Some(item) = PathExplorerItem::new(path.clone()) else {
+                    continue;
+                };
+
+                {
+                    let mut explorer = explorer.write();
+                    if explorer.found.len() >= SEARCH_LIMIT {
+                        break;
+                    }
+                    explorer.found.push(item);
+                }
+                ctx.request_repaint();
+
+                let semaphore = semaphore.clone();
+                let waiter = wait_fn(move || semaphore.clone().acquire_owned());
+                let thumbnail = this.thumbnail(
+                    path.clone(),
+                    default_texture.clone(),
+                    waiter,
+                    entry_setting.clone(),
+                );
+                let (explorer, ctx) = (explorer.clone(), ctx.clone());
+                loaders.push(spawn_and_abort_on_drop(async move {
+                    let texture = thumbnail.await;
+
+                    if let Some(it) = explorer.write().found.iter_mut().find(|it| it.path == path) {
+                        it.thumbnail = texture.into_view_state();
+                    }
+                    ctx.request_repaint();
+                }));
+            }
+            drop(receiver);
+
+            explorer.write().is_search_done = true;
+            ctx.request_repaint();
+
+            // thumbnails are aborted when the search is dropped.
+            futures::future::join_all(loaders).await;
+        })
+    }
+}
+
+// books directly in folder at `path`, empty if it isn't a folder.
+fn child_books(path: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return vec![];
+    };
+
+    let is_book = |path: &Path| {
+        path.extension()
+            .and_then(|it| it.to_str())
+            .is_some_and(|ext| {
+                BOOK_EXTENSIONS
+                    .iter()
+                    .any(|it| it.eq_ignore_ascii_case(ext))
+            })
+    };
+
+    entries
+        .filter_map(|it| it.ok())
+        .filter(|it| {
+            let is_dir = it.file_type().is_ok_and(|it| it.is_dir());
+            is_dir || is_book(&it.path())
+        })
+        .map(|it| it.path())
+        .collect()
 }
 
 #[derive(Clone, Debug)]
 pub struct PriorityRange {
     range: Range<usize>,
     filtered: HashMap<usize, bool>,
+    // entry at each position when the content is sorted again.
+    order: Option<Vec<usize>>,
 }
 
 #[derive(Clone, Debug)]
@@ -309,15 +1058,38 @@ impl PriorityRange {
         Self {
             range,
             filtered: Default::default(),
+            order: None,
         }
     }
 
+    /// Set entry shown at each position, used to prioritize by position after sorting or
+    /// entries are added and removed.
+    pub fn reorder(&mut self, order: Vec<usize>) {
+        self.range = 0..order.len();
+        self.order = Some(order);
+    }
+
+    /// Load `filter` after the others, e.g. it's hidden by the filter.
     pub fn filter(&mut self, filter: usize) {
         *self.filtered.entry(filter).or_default() = true;
     }
 
+    pub fn clear_filter(&mut self) {
+        self.filtered.clear();
+    }
+
     pub fn interleave_at(&mut self, index: usize) -> impl Iterator<Item = usize> + '_ {
-        split_and_interleave_at(self.range.clone(), index)
+        let (shown, filtered): (Vec<_>, Vec<_>) =
+            split_and_interleave_at(self.range.clone(), index)
+                .partition(|it| !self.filtered.get(it).copied().unwrap_or_default());
+
+        shown
+            .into_iter()
+            .chain(filtered)
+            .map(|it| match &self.order {
+                Some(order) => order.get(it).copied().unwrap_or(it),
+                None => it,
+            })
     }
 }
 
@@ -357,6 +1129,9 @@ pub struct ExplorerEntryLoader<W: Wait> {
     pub waiter: W,
     pub setting: Arc<Mutex<ExplorerEntryLoaderSetting>>,
     pub cache: ExplorerLoaderCache,
+    pub passwords: Passwords,
+    pub exclude: ExcludeRules,
+    pub zip_name_encoding: NameEncoding,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Derivative)]
@@ -380,12 +1155,18 @@ where
         waiter: W,
         setting: Arc<Mutex<ExplorerEntryLoaderSetting>>,
         cache: ExplorerLoaderCache,
+        passwords: Passwords,
+        exclude: ExcludeRules,
+        zip_name_encoding: NameEncoding,
     ) -> Self {
         Self {
             path,
             waiter,
             setting,
             cache,
+            passwords,
+            exclude,
+            zip_name_encoding,
         }
     }
 
@@ -395,7 +1176,21 @@ where
                 .same_file_system(true)
                 .sort_by(|a, b| fmr_core::path::compare_natural(a.file_name(), b.file_name()));
 
-            let dir = dir.into_iter().filter_map(|it| it.ok());
+            // skip excluded file and directory, the root itself is never excluded.
+            let (root, exclude) = (self.path.clone(), self.exclude.clone());
+            let dir = dir
+                .into_iter()
+                .filter_entry(move |it| {
+                    let name = it.path().strip_prefix(&root).unwrap_or(it.path());
+                    let name = name
+                        .components()
+                        .map(|it| it.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/");
+
+                    name.is_empty() || !exclude.is_excluded(&name)
+                })
+                .filter_map(|it| it.ok());
 
             let dir = futures::stream::unfold(dir, |mut dir| async {
                 tokio::task::spawn_blocking(|| {
@@ -416,6 +1211,8 @@ where
                     return Some(image);
                 }
             }
+        } else if fmr_egui::tools::archive_dir::ArchivePath::is_virtual(&self.path) {
+            return self.load_path(self.path.clone()).await;
         }
 
         None
@@ -426,7 +1223,7 @@ where
         log::debug!("opening {:?}", path);
         let mut image = None;
 
-        let _wait = self.waiter.wait().await;
+        let mut wait = Some(self.waiter.wait().await);
         let mut use_cache = false;
 
         let cache_dir =
@@ -472,19 +1269,37 @@ where
             }
         }
 
+        #[cfg(feature = "video")]
+        if image.is_none() && fmr_frame::is_video(&path) {
+            image = fmr_frame::load_video_frame(&path)
+                .map(fmr_frame::ImageData::StaticImage)
+                .ok();
+        }
+
+        // rasterize vector to the thumbnail size instead of resizing its declared size.
+        #[cfg(feature = "svg")]
+        if image.is_none() && fmr_frame::SvgImage::can_read(&path) {
+            let size = self.setting.lock().max_resize;
+            image = fmr_frame::SvgImage::open(&path)
+                .and_then(|it| it.render([size.0, size.1]))
+                .map(fmr_frame::ImageData::StaticImage)
+                .ok();
+        }
+
         if image.is_none() {
             if let Ok(reader) = fmr_frame::Reader::open(&path) {
                 image = reader
                     .into_frames()
                     .into_collector()
                     .collect_as_option_vector()
-                    .await;
+                    .await
+                    .filter(|it| !self.exclude.is_too_small(it.dimensions()));
             }
         }
 
         let image = match image {
             Some(image) => image,
-            None => self.search_file(path.clone()).await?,
+            None => self.search_file(path.clone(), &mut wait).await?,
         };
 
         let size = self.setting.lock().max_resize;
@@ -499,7 +1314,9 @@ where
         // doesn't need to update if already using cache
         if !use_cache && std::fs::create_dir_all(cache_dir).is_ok() {
             if let Ok(mut file) = std::fs::File::create(cache) {
-                if let Err(err) = image.write_to(&mut file, image::ImageFormat::Jpeg) {
+                if let Err(err) =
+                    image.write_to(&mut file, image::ImageFormat::Jpeg, image::ImageFormat::Png)
+                {
                     log::error!("error writing cache: {}", err);
                 }
             }
@@ -518,45 +1335,69 @@ where
         Some(image)
     }
 
-    async fn search_file(&mut self, path: PathBuf) -> Option<fmr_frame::ImageData> {
-        let file = std::fs::File::open(&path).ok()?;
-        if let Ok(mut zip) = ZipArchive::new(file) {
-            let mut names = zip
-                .file_names()
-                .map(|it| it.to_string())
-                .collect::<Vec<_>>();
-
-            names.sort_by(|a, b| natord::compare(a, b));
+    // `wait` is released while password is asked, so other entries keep loading.
+    async fn search_file(
+        &mut self,
+        path: PathBuf,
+        wait: &mut Option<<W::Output as std::future::Future>::Output>,
+    ) -> Option<fmr_frame::ImageData> {
+        #[cfg(feature = "pdf")]
+        if fmr_egui::tools::pdf::is_pdf(&path) {
+            let book = tokio::task::spawn_blocking(move || {
+                fmr_egui::tools::pdf::PdfBook::open(&path)
+                    .ok()?
+                    .first_image()
+            });
+            return book.await.ok().flatten();
+        }
 
-            for it in names {
-                if let Some(image) = fmr_egui::tools::zip::load_image(|| zip.by_name(&it)).await {
-                    return Some(image);
-                }
-            }
+        if fmr_egui::tools::archive_dir::ArchivePath::is_virtual(&path) {
+            let encoding = self.zip_name_encoding;
+            let dir = fmr_egui::tools::archive_dir::ArchiveDir::open_path(&path, encoding);
+            return dir?.first_image(&self.exclude).await;
         }
 
-        #[cfg(feature = "libarchive")]
-        if fmr_egui::tools::archive::can_read(&path) {
-            if let Some(image) = self.search_image_archive(path).await {
-                return Some(image);
+        let file = std::fs::File::open(&path).ok()?;
+        if let Ok(mut zip) = ZipArchive::new(file) {
+            // use the cover of epub, falling back to its first page.
+            if let Some(epub) = fmr_egui::tools::epub::Epub::from_zip(&mut zip) {
+                for it in epub.cover.iter().chain(epub.pages.first()) {
+                    if let Some(image) = fmr_egui::tools::zip::load_image(|| zip.by_name(it)).await
+                    {
+                        return Some(image);
+                    }
+                }
             }
         }
 
-        None
-    }
-
-    #[cfg(feature = "libarchive")]
-    async fn search_image_archive(&mut self, path: PathBuf) -> Option<fmr_frame::ImageData> {
-        let it = {
-            let reader = fmr_egui::tools::archive::open(&path)
-                .ok()?
-                .into_iter()
-                .filter_map(|it| it.ok())
-                .find(fmr_egui::tools::archive::is_image);
+        let mut source = fmr_egui::tools::source::open_archive(&path, self.zip_name_encoding)?;
+        let names = source.entries().ok()?.into_iter().map(|it| it.name);
+        let names = names.collect::<Vec<_>>();
 
-            fmr_egui::tools::archive::load_image_from_opt_entry(reader)
-        };
+        let mut rejected = self.passwords.get(&path);
+        if let Some(password) = &rejected {
+            source.set_password(password);
+        }
 
-        it.await
+        loop {
+            let image = fmr_egui::tools::source::try_first_image_of(
+                &mut source,
+                names.clone(),
+                &self.exclude,
+            );
+            match image.await {
+                Ok(image) => return image,
+                Err(PasswordRequired) => {
+                    let request = self.passwords.request(&path, rejected.as_deref());
+                    *wait = None;
+                    let password = request.await;
+                    *wait = Some(self.waiter.wait().await);
+
+                    let password = password?;
+                    source.set_password(&password);
+                    rejected = Some(password);
+                }
+            }
+        }
     }
 }
@@ -1,12 +1,17 @@
 use std::hash::Hash;
 
-use super::{Explorer, ExplorerItem, ExplorerItemView, ExplorerSetting};
+use super::{
+    DetailsColumn, Explorer, ExplorerItem, ExplorerItemView, ExplorerLayout, ExplorerSetting,
+    FilterMode, ReadingStatus,
+};
+use fmr_frame::{SplittedTextureWidget, TextureView};
 use fmr_scroll::ScrollArea;
 
 pub struct ExplorerView<'a, Item: ExplorerItem> {
     explorer: &'a mut Explorer<Item>,
     setting: &'a ExplorerSetting,
     id: egui::Id,
+    sorted_by: Option<(DetailsColumn, bool)>,
 }
 
 pub struct ExplorerOutput {
@@ -17,7 +22,40 @@ pub struct ExplorerOutput {
 
     pub grid_response: egui::Response,
 
+    /// is the filter bar focused, it shouldn't lose focus when events are handled.
+    pub is_filter_focused: bool,
+
+    /// header of the column clicked to sort items by it.
+    pub sort_by: Option<DetailsColumn>,
+
     responses: Vec<egui::Response>,
+    // index of item of each response.
+    indices: Vec<usize>,
+    // indices of items in each column of masonry from top, empty for other layouts.
+    masonry: Vec<Vec<usize>>,
+}
+
+impl ExplorerOutput {
+    fn response(&self, index: usize) -> Option<&egui::Response> {
+        let position = self.indices.iter().position(|it| *it == index)?;
+        self.responses.get(position)
+    }
+
+    // item `step` rows away from `index` in its column of masonry.
+    fn masonry_step(&self, index: usize, step: isize) -> Option<usize> {
+        self.masonry.iter().find_map(|column| {
+            let row = column.iter().position(|it| *it == index)?;
+            column.get(row.checked_add_signed(step)?).copied()
+        })
+    }
+
+    /// Show context menu opened by secondary click on an item, the clicked item is
+    /// selected unless it's part of the selection already.
+    pub fn context_menu(&self, mut add_contents: impl FnMut(&mut egui::Ui)) {
+        for it in &self.responses {
+            it.context_menu(&mut add_contents);
+        }
+    }
 }
 
 impl<'a, Item: ExplorerItem> ExplorerView<'a, Item> {
@@ -26,23 +64,43 @@ impl<'a, Item: ExplorerItem> ExplorerView<'a, Item> {
             explorer,
             setting,
             id: egui::Id::new("explorer"),
+            sorted_by: None,
         }
     }
 
+    /// Column items are sorted by and whether it's ascending, shown in the header of
+    /// [`ExplorerLayout::Details`].
+    pub fn sorted_by(mut self, sorted_by: Option<(DetailsColumn, bool)>) -> Self {
+        self.sorted_by = sorted_by;
+        self
+    }
+
     pub fn id_source(mut self, id: impl Hash) -> Self {
         self.id = egui::Id::new(id);
         self
     }
 
-    pub fn show(mut self, ui: &mut egui::Ui) -> ExplorerOutput {
+    pub fn show(self, ui: &mut egui::Ui) -> ExplorerOutput {
+        ui.vertical(|ui| self.show_inner(ui)).inner
+    }
+
+    fn show_inner(mut self, ui: &mut egui::Ui) -> ExplorerOutput {
+        let filter_id = self.id.with("filter");
+        self.show_filter(ui, filter_id);
+        self.explorer.update_filter(self.setting.hide_finished);
+
         let scroll = ScrollArea::vertical(self.explorer.scroll.clone())
             .id_source(self.id.with("scroll-area"))
             // .enable_scrolling(true)
-            .show(ui, |ui, _| self.show_grid(ui));
+            .show(ui, |ui, _| match self.explorer.layout {
+                ExplorerLayout::Grid => self.show_grid(ui),
+                ExplorerLayout::Details => self.show_details(ui),
+                ExplorerLayout::Masonry => self.show_masonry(ui),
+            });
 
         self.explorer.scroll = scroll.state;
         if self.explorer.scroll_to_index {
-            if let Some(response) = scroll.inner.responses.get(self.explorer.current) {
+            if let Some(response) = scroll.inner.response(self.explorer.current) {
                 // self.explorer.scroll
                 // println!("scroll to me");
                 // response.scroll_to_me(None);
@@ -52,6 +110,9 @@ impl<'a, Item: ExplorerItem> ExplorerView<'a, Item> {
             }
         }
 
+        fmr_egui::event::handles_ui(ui, |ui, event| {
+            Self::handle_filter_key(self.explorer, filter_id, ui, event)
+        });
         fmr_egui::event::handles_ui(ui, |ui, event| {
             Self::handle_key(self.explorer, &scroll.inner, ui, event)
         });
@@ -63,7 +124,137 @@ impl<'a, Item: ExplorerItem> ExplorerView<'a, Item> {
             handled
         });
 
-        scroll.inner
+        let mut output = scroll.inner;
+        output.is_filter_focused = ui.memory(|memory| memory.has_focus(filter_id));
+        output
+    }
+
+    fn show_filter(&mut self, ui: &mut egui::Ui, filter_id: egui::Id) {
+        let explorer = &mut *self.explorer;
+        let count = if explorer.filter.is_searching() {
+            Some(format!("{} found", explorer.found.len()))
+        } else {
+            let len = explorer.content.len();
+            explorer
+                .visible()
+                .map(|it| format!("{} / {}", it.len(), len))
+        };
+        let is_search_running = explorer.filter.is_searching() && !explorer.is_search_done;
+
+        let filter = &mut explorer.filter;
+        let is_valid = filter.is_valid();
+
+        let layout = explorer.layout;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source(self.id.with("filter-mode"))
+                .selected_text(filter.mode.label())
+                .show_ui(ui, |ui| {
+                    for it in FilterMode::ALL {
+                        ui.selectable_value(&mut filter.mode, it, it.label());
+                    }
+                });
+
+            ui.checkbox(&mut filter.recursive, "Recursive")
+                .on_hover_text("Search names in every subfolder");
+
+            egui::ComboBox::from_id_source(self.id.with("layout"))
+                .selected_text(layout.label())
+                .show_ui(ui, |ui| {
+                    for it in ExplorerLayout::ALL {
+                        ui.selectable_value(&mut explorer.layout, it, it.label());
+                    }
+                });
+
+            if is_search_running {
+                ui.spinner();
+            }
+            if let Some(count) = count {
+                ui.label(count);
+            }
+
+            let mut edit = egui::TextEdit::singleline(&mut filter.query)
+                .id(filter_id)
+                .hint_text("Type to filter")
+                .desired_width(ui.available_width());
+            if !is_valid {
+                edit = edit.text_color(ui.visuals().error_fg_color);
+            }
+            ui.add(edit);
+        });
+
+        if layout != explorer.layout {
+            explorer.clear_layout();
+            explorer.scroll_to_index = true;
+        }
+    }
+
+    // type to filter when the filter bar isn't focused, Esc clears the filter.
+    fn handle_filter_key(
+        explorer: &mut Explorer<Item>,
+        filter_id: egui::Id,
+        ui: &mut egui::Ui,
+        event: &egui::Event,
+    ) -> bool {
+        let focused = ui.memory(|memory| memory.focused());
+        let is_focused = focused == Some(filter_id);
+
+        // other input, e.g. password prompt, is being typed.
+        if focused.is_some() && !is_focused {
+            return false;
+        }
+
+        match event {
+            egui::Event::Text(text) if !is_focused => {
+                if text.chars().any(char::is_control) {
+                    return false;
+                }
+
+                explorer.filter.query.push_str(text);
+
+                // keep typing at the end of the query.
+                let len = explorer.filter.query.chars().count();
+                let mut state = egui::TextEdit::load_state(ui.ctx(), filter_id).unwrap_or_default();
+                let cursor = egui::text::CCursorRange::one(egui::text::CCursor::new(len));
+                state.cursor.set_char_range(Some(cursor));
+                state.store(ui.ctx(), filter_id);
+
+                ui.memory_mut(|memory| memory.request_focus(filter_id));
+                ui.ctx().request_repaint();
+                true
+            }
+            egui::Event::Key {
+                key: egui::Key::Escape,
+                pressed: true,
+                ..
+            } if is_focused || !explorer.filter.is_empty() => {
+                explorer.filter.clear();
+                ui.memory_mut(|memory| memory.surrender_focus(filter_id));
+                ui.ctx().request_repaint();
+                true
+            }
+            egui::Event::Key {
+                key: egui::Key::Backspace,
+                pressed: true,
+                ..
+            } if !is_focused && !explorer.filter.is_empty() => {
+                explorer.filter.query.pop();
+                ui.ctx().request_repaint();
+                true
+            }
+            // already handled by the focused filter bar.
+            egui::Event::Text(_) => true,
+            egui::Event::Key {
+                key:
+                    egui::Key::Backspace
+                    | egui::Key::Delete
+                    | egui::Key::ArrowLeft
+                    | egui::Key::ArrowRight
+                    | egui::Key::Home
+                    | egui::Key::End,
+                ..
+            } if is_focused => true,
+            _ => false,
+        }
     }
 
     fn show_grid(&mut self, ui: &mut egui::Ui) -> ExplorerOutput {
@@ -71,6 +262,7 @@ impl<'a, Item: ExplorerItem> ExplorerView<'a, Item> {
             explorer,
             setting,
             id,
+            ..
         } = self;
 
         let available_width = ui.available_width();
@@ -81,30 +273,42 @@ impl<'a, Item: ExplorerItem> ExplorerView<'a, Item> {
         let width = (available_width / (column as f32)).floor() - (ui.spacing().item_spacing.x);
 
         if explorer.column_count != column {
-            explorer.row_size.clear();
+            explorer.clear_layout();
         }
         explorer.column_count = column;
 
-        let mut is_double_clicked = false;
         let mut responses = vec![];
+        let indices = shown_indices(explorer);
+
+        let current = explorer.current;
+        let mut clicks = ItemClicks::new(explorer);
+        let row_size = &mut explorer.row_size;
+        let items = if explorer.filter.is_searching() {
+            &mut explorer.found
+        } else {
+            &mut explorer.content
+        };
 
         let grid = egui::Grid::new(id.with("grid"))
             .max_col_width(width)
             .show(ui, |ui| {
                 let mut row = 0;
-                for (i, it) in explorer.content.iter_mut().enumerate() {
+                for (position, i) in indices.iter().copied().enumerate() {
+                    let Some(it) = items.get_mut(i) else {
+                        continue;
+                    };
+
                     ui.push_id(i, |ui| {
                         let width = ui.available_width();
-                        let size = explorer
-                            .row_size
+                        let size = row_size
                             .entry(row)
                             .or_insert_with(|| egui::vec2(width, width));
 
                         let response = ExplorerItemView::new(it, egui::vec2(width, width))
                             .container_size(*size)
                             .image_size(egui::vec2(width * 0.8, width))
-                            .selected(i == explorer.current)
-                            .current_index(i == explorer.current)
+                            .selected(clicks.selected.contains(&i))
+                            .current_index(i == current)
                             .show(ui);
 
                         let response = response.interact(egui::Sense::click());
@@ -117,24 +321,16 @@ impl<'a, Item: ExplorerItem> ExplorerView<'a, Item> {
                         }
                         *size = response.rect.size().max(*size);
 
-                        if response.clicked() {
-                            explorer.current = i;
-                            ui.ctx().request_repaint();
-                        }
-
-                        if response.double_clicked() {
-                            is_double_clicked = true;
-                        }
-
                         // if is_double_clicked || response.clicked_by(egui::PointerButton::Extra2) {
                         //     is_double_clicked = true;
                         // }
 
+                        clicks.handle(ui, i, &response);
                         responses.push(response);
                     });
 
                     let column = column as isize;
-                    if ((i as isize) % column) == (column - 1) {
+                    if ((position as isize) % column) == (column - 1) {
                         row += 1;
                         ui.end_row();
                     }
@@ -142,10 +338,274 @@ impl<'a, Item: ExplorerItem> ExplorerView<'a, Item> {
             });
 
         ExplorerOutput {
-            is_double_clicked,
+            is_double_clicked: clicks.apply(explorer),
             column,
             grid_response: grid.response,
+            is_filter_focused: false,
+            sort_by: None,
+            responses,
+            indices,
+            masonry: vec![],
+        }
+    }
+
+    fn show_details(&mut self, ui: &mut egui::Ui) -> ExplorerOutput {
+        let Self {
+            explorer,
+            sorted_by,
+            ..
+        } = self;
+
+        let spacing = ui.spacing().item_spacing.x;
+        let row_height = 48.0;
+        let column_width = 100.0;
+        let name_width = (ui.available_width()
+            - row_height
+            - column_width * (DetailsColumn::ALL.len() - 1) as f32
+            - spacing * DetailsColumn::ALL.len() as f32)
+            .max(column_width);
+        let width_of = |column| match column {
+            DetailsColumn::Name => name_width,
+            _ => column_width,
+        };
+
+        let mut sort_by = None;
+        ui.horizontal(|ui| {
+            ui.add_space(row_height + spacing);
+
+            for column in DetailsColumn::ALL {
+                let mut text = column.label().to_string();
+                match sorted_by {
+                    Some((it, true)) if *it == column => text.push_str(" ⬆"),
+                    Some((it, false)) if *it == column => text.push_str(" ⬇"),
+                    _ => {}
+                }
+
+                let header = egui::Button::new(egui::RichText::new(text).strong()).frame(false);
+                let size = egui::vec2(width_of(column), ui.spacing().interact_size.y);
+                if ui.add_sized(size, header).clicked() {
+                    sort_by = Some(column);
+                }
+            }
+        });
+        ui.separator();
+
+        let mut responses = vec![];
+        let indices = shown_indices(explorer);
+        let current = explorer.current;
+        let mut clicks = ItemClicks::new(explorer);
+        let items = if explorer.filter.is_searching() {
+            &mut explorer.found
+        } else {
+            &mut explorer.content
+        };
+
+        let list = ui.vertical(|ui| {
+            for i in indices.iter().copied() {
+                let Some(it) = items.get_mut(i) else {
+                    continue;
+                };
+
+                // row outside of the viewport only takes its space.
+                let size = egui::vec2(ui.available_width(), row_height);
+                if !ui.is_rect_visible(egui::Rect::from_min_size(ui.next_widget_position(), size)) {
+                    let (_, response) = ui.allocate_exact_size(size, egui::Sense::click());
+                    responses.push(response);
+                    continue;
+                }
+
+                ui.push_id(i, |ui| {
+                    // painted behind the row once its size is known.
+                    let background = ui.painter().add(egui::Shape::Noop);
+
+                    let response = ui.horizontal(|ui| {
+                        ui.set_height(row_height);
+
+                        ui.allocate_ui(egui::vec2(row_height, row_height), |ui| {
+                            ui.centered_and_justified(|ui| {
+                                TextureView::new(it.thumbnail()).show(ui, |_, handle| {
+                                    let scale = egui::Vec2::splat(row_height) / handle.size_vec2();
+                                    SplittedTextureWidget::new_with_scale(handle, scale.min_elem())
+                                });
+                            });
+                        });
+
+                        for column in DetailsColumn::ALL {
+                            let size = egui::vec2(width_of(column), row_height);
+                            match column {
+                                DetailsColumn::Name => {
+                                    let name = egui::Label::new(it.name()).truncate();
+                                    ui.add_sized(size, name);
+                                }
+                                DetailsColumn::Progress => match it.status() {
+                                    Some(ReadingStatus::Reading(ratio)) => {
+                                        let bar = egui::ProgressBar::new(ratio)
+                                            .desired_width(column_width)
+                                            .show_percentage();
+                                        ui.add_sized(size, bar);
+                                    }
+                                    Some(ReadingStatus::Finished) => {
+                                        ui.add_sized(size, egui::Label::new("✔ Finished"));
+                                    }
+                                    Some(ReadingStatus::New) => {
+                                        ui.add_sized(size, egui::Label::new("New"));
+                                    }
+                                    None => {
+                                        ui.add_sized(size, egui::Label::new("-"));
+                                    }
+                                },
+                                _ => {
+                                    let details = it.details();
+                                    let text = match column {
+                                        DetailsColumn::Size => {
+                                            details.and_then(|it| it.size).map(format_size)
+                                        }
+                                        DetailsColumn::Modified => {
+                                            details.and_then(|it| it.modified).map(format_date)
+                                        }
+                                        DetailsColumn::PageCount => details
+                                            .and_then(|it| it.page_count)
+                                            .map(|it| it.to_string()),
+                                        _ => None,
+                                    };
+                                    let text = text.unwrap_or_else(|| "-".to_string());
+                                    ui.add_sized(size, egui::Label::new(text));
+                                }
+                            }
+                        }
+                    });
+
+                    let response = response.response.interact(egui::Sense::click());
+                    let visuals = ui.style().interact_selectable(&response, false);
+                    let fill = if clicks.selected.contains(&i) {
+                        ui.visuals().selection.bg_fill
+                    } else if response.hovered() {
+                        visuals.weak_bg_fill
+                    } else {
+                        egui::Color32::TRANSPARENT
+                    };
+                    ui.painter().set(
+                        background,
+                        egui::Shape::rect_filled(response.rect, visuals.rounding, fill),
+                    );
+                    if i == current {
+                        ui.painter().rect_stroke(
+                            response.rect,
+                            visuals.rounding,
+                            ui.visuals().selection.stroke,
+                        );
+                    }
+
+                    clicks.handle(ui, i, &response);
+                    responses.push(response);
+                });
+            }
+        });
+
+        ExplorerOutput {
+            is_double_clicked: clicks.apply(explorer),
+            column: 1,
+            grid_response: list.response,
+            is_filter_focused: false,
+            sort_by,
+            responses,
+            indices,
+            masonry: vec![],
+        }
+    }
+
+    fn show_masonry(&mut self, ui: &mut egui::Ui) -> ExplorerOutput {
+        let Self {
+            explorer, setting, ..
+        } = self;
+
+        let column = (ui.available_width() / (setting.minimum_column_width as f32))
+            .floor()
+            .max(1.0) as usize;
+        if explorer.column_count != column {
+            explorer.clear_layout();
+        }
+        explorer.column_count = column;
+
+        let mut responses = vec![];
+        let mut masonry = vec![vec![]; column];
+        let indices = shown_indices(explorer);
+        let current = explorer.current;
+        let mut clicks = ItemClicks::new(explorer);
+        let item_size = &mut explorer.item_size;
+        let items = if explorer.filter.is_searching() {
+            &mut explorer.found
+        } else {
+            &mut explorer.content
+        };
+
+        let label_height = ui.text_style_height(&egui::TextStyle::Body) * 2.0 + 10.0;
+        let response = ui.scope(|ui| {
+            ui.columns(column, |columns| {
+                for i in indices.iter().copied() {
+                    let Some(it) = items.get_mut(i) else {
+                        continue;
+                    };
+
+                    // the shortest column is filled next.
+                    let Some((position, ui)) =
+                        columns.iter_mut().enumerate().min_by(|(_, a), (_, b)| {
+                            a.next_widget_position()
+                                .y
+                                .total_cmp(&b.next_widget_position().y)
+                        })
+                    else {
+                        continue;
+                    };
+                    masonry[position].push(i);
+
+                    let width = ui.available_width();
+                    let [w, h] = it.thumbnail().max_size();
+                    let ratio = match (w, h) {
+                        (0, _) | (_, 0) => 1.0,
+                        (w, h) => (h as f32 / w as f32).clamp(0.25, 4.0),
+                    };
+                    let image_size = egui::vec2(width, width * ratio);
+                    let container_size = image_size + egui::vec2(0.0, label_height);
+
+                    // item outside of the viewport only takes the size it's shown with.
+                    let size = match item_size.get(&i) {
+                        Some((measured, size)) if *measured == image_size => *size,
+                        _ => container_size,
+                    };
+                    if !ui
+                        .is_rect_visible(egui::Rect::from_min_size(ui.next_widget_position(), size))
+                    {
+                        let (_, response) = ui.allocate_exact_size(size, egui::Sense::click());
+                        responses.push(response);
+                        continue;
+                    }
+
+                    ui.push_id(i, |ui| {
+                        let response = ExplorerItemView::new(it, image_size)
+                            .container_size(container_size)
+                            .selected(clicks.selected.contains(&i))
+                            .current_index(i == current)
+                            .show(ui);
+                        let response = response.interact(egui::Sense::click());
+                        item_size.insert(i, (image_size, response.rect.size()));
+
+                        clicks.handle(ui, i, &response);
+                        responses.push(response);
+                    });
+                }
+            });
+        });
+
+        ExplorerOutput {
+            is_double_clicked: clicks.apply(explorer),
+            column,
+            grid_response: response.response,
+            is_filter_focused: false,
+            sort_by: None,
             responses,
+            indices,
+            masonry,
         }
     }
 
@@ -158,11 +618,28 @@ impl<'a, Item: ExplorerItem> ExplorerView<'a, Item> {
     ) -> bool {
         let mut handled = false;
 
-        let mut move_by = |step: isize| {
-            let changed = explorer.move_by(step);
+        // shift extends the selection while moving.
+        let is_extending = matches!(
+            event,
+            egui::Event::Key { modifiers, .. } if modifiers.shift_only()
+        );
+
+        // move the current item with `move_current`, returns true if it's moved.
+        let mut move_with = |move_current: &dyn Fn(&mut Explorer<Item>) -> bool| {
+            if is_extending {
+                explorer.anchor.get_or_insert(explorer.current);
+            }
+
+            let changed = move_current(explorer);
 
             if changed {
-                if let Some(response) = output.responses.get(explorer.current) {
+                if is_extending {
+                    explorer.select_range(explorer.current);
+                } else {
+                    explorer.clear_selection();
+                }
+
+                if let Some(response) = output.response(explorer.current) {
                     explorer.scroll.scroll_to_response(response, None);
                     // println!("scroll to me");
                     // response.scroll_to_me(None);
@@ -178,10 +655,19 @@ impl<'a, Item: ExplorerItem> ExplorerView<'a, Item> {
             event,
             egui::Key::ArrowUp,
             egui::Key::ArrowDown,
-            |it| it.is_none(),
+            |it| it.is_none() || it.shift_only(),
             |step| {
-                let step = (step as isize) * (output.column as isize);
-                move_by(step)
+                let step = step as isize;
+                if output.masonry.is_empty() {
+                    move_with(&|explorer| explorer.move_by(step * (output.column as isize)))
+                } else {
+                    // masonry moves within the column.
+                    move_with(&|explorer| {
+                        output
+                            .masonry_step(explorer.current, step)
+                            .is_some_and(|it| explorer.set_index(it))
+                    })
+                }
             },
         );
 
@@ -190,10 +676,97 @@ impl<'a, Item: ExplorerItem> ExplorerView<'a, Item> {
             event,
             egui::Key::ArrowLeft,
             egui::Key::ArrowRight,
-            |it| it.is_none(),
-            |step| move_by(step as isize),
+            |it| it.is_none() || it.shift_only(),
+            |step| move_with(&|explorer| explorer.move_by(step as isize)),
         );
 
         handled
     }
 }
+
+// indices of shown items in order.
+fn shown_indices<Item: ExplorerItem>(explorer: &Explorer<Item>) -> Vec<usize> {
+    match explorer.visible() {
+        Some(visible) => visible.to_vec(),
+        None => (0..explorer.items().len()).collect(),
+    }
+}
+
+// clicks on items of any layout, applied to the explorer after items are shown.
+struct ItemClicks {
+    selected: std::collections::HashSet<usize>,
+    clicked: Option<(usize, egui::Modifiers)>,
+    is_double_clicked: bool,
+}
+
+impl ItemClicks {
+    fn new<Item: ExplorerItem>(explorer: &Explorer<Item>) -> Self {
+        Self {
+            selected: explorer.selection().into_iter().collect(),
+            clicked: None,
+            is_double_clicked: false,
+        }
+    }
+
+    fn handle(&mut self, ui: &egui::Ui, index: usize, response: &egui::Response) {
+        if response.clicked() {
+            self.clicked = Some((index, ui.input(|input| input.modifiers)));
+            ui.ctx().request_repaint();
+        }
+
+        // act on the clicked item unless it's part of the selection.
+        if response.secondary_clicked() && !self.selected.contains(&index) {
+            self.clicked = Some((index, egui::Modifiers::NONE));
+        }
+
+        if response.double_clicked() {
+            self.is_double_clicked = true;
+        }
+    }
+
+    // select the clicked item, returns true if it's double clicked.
+    fn apply<Item: ExplorerItem>(self, explorer: &mut Explorer<Item>) -> bool {
+        if let Some((clicked, modifiers)) = self.clicked {
+            explorer.select(clicked, modifiers);
+        }
+
+        self.is_double_clicked
+    }
+}
+
+fn format_size(size: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+    let mut size = size as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{size} {}", UNITS[unit]),
+        _ => format!("{size:.1} {}", UNITS[unit]),
+    }
+}
+
+// date of `time` in UTC as YYYY-MM-DD.
+fn format_date(time: std::time::SystemTime) -> String {
+    let Ok(elapsed) = time.duration_since(std::time::UNIX_EPOCH) else {
+        return "-".to_string();
+    };
+
+    // days to civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let days = (elapsed.as_secs() / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
@@ -0,0 +1,228 @@
+use std::{
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+/// Format readable by [`ImageData`], including format decoded outside `image` crate.
+///
+/// [`ImageData`]: crate::ImageData
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    Image(image::ImageFormat),
+    #[cfg(feature = "jxl")]
+    Jxl,
+    #[cfg(feature = "heif")]
+    Heif,
+    #[cfg(feature = "svg")]
+    Svg,
+}
+
+impl Format {
+    /// Guess format from extension of `path`.
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+
+        match extension.as_str() {
+            #[cfg(feature = "jxl")]
+            "jxl" => Some(Self::Jxl),
+            #[cfg(feature = "heif")]
+            "heic" | "heif" | "hif" => Some(Self::Heif),
+            #[cfg(feature = "svg")]
+            "svg" | "svgz" => Some(Self::Svg),
+            _ => image::ImageFormat::from_path(path).ok().map(Self::Image),
+        }
+    }
+
+    /// Guess format from the magic bytes of `data`.
+    pub fn from_memory(data: &[u8]) -> Option<Self> {
+        if let Some(format) = guess_isobmff(data) {
+            return Some(format);
+        }
+
+        #[cfg(feature = "jxl")]
+        if data.starts_with(&[0xFF, 0x0A]) || data.starts_with(b"\0\0\0\x0cJXL \x0d\x0a\x87\x0a") {
+            return Some(Self::Jxl);
+        }
+
+        #[cfg(feature = "svg")]
+        if is_svg(data) {
+            return Some(Self::Svg);
+        }
+
+        image::guess_format(data).ok().map(Self::Image)
+    }
+
+    /// Guess format from the header of `read`, the position of `read` is restored afterward.
+    pub fn from_reader<R: Read + Seek>(read: &mut R) -> std::io::Result<Option<Self>> {
+        let start = read.stream_position()?;
+
+        let mut header = Vec::with_capacity(64);
+        read.by_ref().take(64).read_to_end(&mut header)?;
+        read.seek(SeekFrom::Start(start))?;
+
+        Ok(Self::from_memory(&header))
+    }
+
+    /// Returns `true` if this format can be decoded with enabled features.
+    pub fn can_read(&self) -> bool {
+        match self {
+            Self::Image(image::ImageFormat::Avif) => cfg!(feature = "avif"),
+            Self::Image(format) => format.can_read(),
+            #[cfg(feature = "jxl")]
+            Self::Jxl => true,
+            #[cfg(feature = "heif")]
+            Self::Heif => true,
+            #[cfg(feature = "svg")]
+            Self::Svg => true,
+        }
+    }
+
+    /// Returns the format when it's decoded by `image` crate.
+    pub fn image_format(&self) -> Option<image::ImageFormat> {
+        match self {
+            Self::Image(format) => Some(*format),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    /// Decode `read` as a static image of this format.
+    pub fn decode(&self, mut read: impl Read) -> image::ImageResult<image::DynamicImage> {
+        match self {
+            Self::Image(format) => {
+                let mut data = Vec::new();
+                read.read_to_end(&mut data)?;
+
+                image::load_from_memory_with_format(&data, *format)
+            }
+            #[cfg(feature = "jxl")]
+            Self::Jxl => decode_jxl(read),
+            #[cfg(feature = "heif")]
+            Self::Heif => decode_heif(read),
+            #[cfg(feature = "svg")]
+            Self::Svg => crate::SvgImage::from_reader(read)?.render_original(),
+        }
+    }
+}
+
+// AVIF and HEIF are stored in ISO base media file with brand in the ftyp box.
+fn guess_isobmff(data: &[u8]) -> Option<Format> {
+    if data.get(4..8)? != b"ftyp" {
+        return None;
+    }
+
+    let size = u32::from_be_bytes(data.get(0..4)?.try_into().ok()?) as usize;
+    let ftyp = data.get(8..size.min(data.len()))?;
+
+    // major brand, minor version, then compatible brands.
+    let brands = ftyp
+        .chunks_exact(4)
+        .enumerate()
+        .filter(|(i, _)| *i != 1)
+        .map(|(_, it)| it);
+
+    if brands.clone().any(|it| it == b"avif" || it == b"avis") {
+        return Some(Format::Image(image::ImageFormat::Avif));
+    }
+
+    #[cfg(feature = "heif")]
+    if brands.clone().any(|it| {
+        [
+            b"heic", b"heix", b"hevc", b"hevx", b"heim", b"heis", b"mif1", b"msf1",
+        ]
+        .iter()
+        .any(|brand| it == *brand)
+    }) {
+        return Some(Format::Heif);
+    }
+
+    None
+}
+
+// SVG starts with the svg element, optionally preceded by xml declaration, doctype or comment.
+#[cfg(feature = "svg")]
+fn is_svg(data: &[u8]) -> bool {
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    let start = data.iter().position(|it| !it.is_ascii_whitespace());
+    let data = &data[start.unwrap_or(data.len())..];
+
+    data.starts_with(b"<svg")
+        || ((data.starts_with(b"<?xml") || data.starts_with(b"<!"))
+            && data.windows(4).any(|it| it == b"<svg" || it == b" svg"))
+}
+
+#[allow(dead_code)]
+pub(crate) fn decoding_error<E>(name: &'static str) -> impl FnOnce(E) -> image::ImageError
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    move |err| {
+        image::ImageError::Decoding(image::error::DecodingError::new(
+            image::error::ImageFormatHint::Name(name.to_string()),
+            err,
+        ))
+    }
+}
+
+#[cfg(feature = "jxl")]
+fn decode_jxl(read: impl Read) -> image::ImageResult<image::DynamicImage> {
+    let image = jxl_oxide::JxlImage::builder()
+        .read(read)
+        .map_err(decoding_error("jxl"))?;
+    let render = image.render_frame(0).map_err(decoding_error("jxl"))?;
+
+    let mut stream = render.stream();
+    let (width, height, channels) = (stream.width(), stream.height(), stream.channels());
+
+    let mut buffer = vec![0f32; width as usize * height as usize * channels as usize];
+    stream.write_to_buffer(&mut buffer);
+
+    let rgba = buffer
+        .chunks_exact(channels as usize)
+        .flat_map(|it| match *it {
+            [l] => [l, l, l, 1.0],
+            [l, a] => [l, l, l, a],
+            [r, g, b] => [r, g, b, 1.0],
+            [r, g, b, a, ..] => [r, g, b, a],
+            [] => [0.0; 4],
+        })
+        .collect();
+
+    image::Rgba32FImage::from_raw(width, height, rgba)
+        .map(image::DynamicImage::ImageRgba32F)
+        .ok_or_else(|| decoding_error("jxl")("invalid image size"))
+}
+
+#[cfg(feature = "heif")]
+fn decode_heif(mut read: impl Read) -> image::ImageResult<image::DynamicImage> {
+    let mut data = Vec::new();
+    read.read_to_end(&mut data)?;
+
+    let context =
+        libheif_rs::HeifContext::read_from_bytes(&data).map_err(decoding_error("heif"))?;
+    let handle = context
+        .primary_image_handle()
+        .map_err(decoding_error("heif"))?;
+    let image = libheif_rs::LibHeif::new()
+        .decode(
+            &handle,
+            libheif_rs::ColorSpace::Rgb(libheif_rs::RgbChroma::Rgba),
+            None,
+        )
+        .map_err(decoding_error("heif"))?;
+
+    let planes = image.planes();
+    let plane = planes
+        .interleaved
+        .ok_or_else(|| decoding_error("heif")("missing interleaved plane"))?;
+
+    let row_size = plane.width as usize * 4;
+    let mut buffer = Vec::with_capacity(row_size * plane.height as usize);
+    for row in plane.data.chunks(plane.stride).take(plane.height as usize) {
+        buffer.extend_from_slice(&row[..row_size]);
+    }
+
+    image::RgbaImage::from_raw(plane.width, plane.height, buffer)
+        .map(image::DynamicImage::ImageRgba8)
+        .ok_or_else(|| decoding_error("heif")("invalid image size"))
+}
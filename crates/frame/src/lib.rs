@@ -6,14 +6,26 @@ use std::{
     time::Duration,
 };
 
+mod format;
 mod reader;
+mod stream;
+#[cfg(feature = "svg")]
+mod svg;
 mod texture;
+#[cfg(feature = "video")]
+mod video;
 
 use eframe::egui;
 use eframe::epaint::mutex::RwLock as EguiRwLock;
 use eframe::epaint::TextureManager;
+pub use format::*;
 pub use reader::*;
+pub use stream::*;
+#[cfg(feature = "svg")]
+pub use svg::*;
 pub use texture::*;
+#[cfg(feature = "video")]
+pub use video::*;
 
 use image::AnimationDecoder;
 
@@ -64,12 +76,37 @@ impl ToEguiImage for image::DynamicImage {
     }
 }
 
+/// How many times an animation is played.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Repeat {
+    #[default]
+    Infinite,
+    /// number of play including the first one.
+    Finite(u32),
+}
+
 pub enum ImageData {
     StaticImage(image::DynamicImage),
-    AnimatedImage(Vec<FrameData>),
+    AnimatedImage(Vec<FrameData>, Repeat),
+    /// long animation decoded while it's played, with its first frame.
+    StreamedAnimation(AnimationStream, FrameData),
 }
 
 impl ImageData {
+    /// Width and height of the image, the first frame of animated image.
+    pub fn dimensions(&self) -> [u32; 2] {
+        let image = match self {
+            Self::StaticImage(image) => image,
+            Self::AnimatedImage(frames, _) => match frames.first() {
+                Some(frame) => &frame.image,
+                None => return [0, 0],
+            },
+            Self::StreamedAnimation(_, first) => &first.image,
+        };
+
+        [image.width(), image.height()]
+    }
+
     /// Resize this image using the specified filter algorithm.
     /// Returns a new image. The image's aspect ratio is preserved.
     /// The image is scaled to the maximum possible size that fits
@@ -79,7 +116,7 @@ impl ImageData {
             Self::StaticImage(image) => {
                 Self::StaticImage(image.resize(nwidth, nheight, filter.into()))
             }
-            Self::AnimatedImage(frames) => {
+            Self::AnimatedImage(frames, repeat) => {
                 let mut new = vec![];
                 for it in frames {
                     new.push(FrameData {
@@ -89,7 +126,10 @@ impl ImageData {
                         delay: it.delay,
                     });
                 }
-                Self::AnimatedImage(new)
+                Self::AnimatedImage(new, *repeat)
+            }
+            Self::StreamedAnimation(_, first) => {
+                Self::StaticImage(first.image.resize(nwidth, nheight, filter.into()))
             }
         }
     }
@@ -100,7 +140,7 @@ impl ImageData {
             ImageData::StaticImage(image) => {
                 SplittedImageData::StaticImage(SplittedImage::make_allocatable(&image, max_size))
             }
-            ImageData::AnimatedImage(images) => SplittedImageData::AnimatedImage(
+            ImageData::AnimatedImage(images, repeat) => SplittedImageData::AnimatedImage(
                 images
                     .into_iter()
                     .map(|it| SplittedFrameData {
@@ -108,11 +148,30 @@ impl ImageData {
                         image: SplittedImage::make_allocatable(&it.image, max_size),
                     })
                     .collect(),
+                repeat,
+            ),
+            ImageData::StreamedAnimation(stream, first) => SplittedImageData::StreamedAnimation(
+                stream,
+                SplittedFrameData {
+                    delay: first.delay.into(),
+                    image: SplittedImage::make_allocatable(&first.image, max_size),
+                },
+                max_size,
             ),
         }
     }
 
-    pub fn write_to<W, F>(&self, w: &mut W, static_format: F) -> image::ImageResult<()>
+    /// Write static image with `static_format` and animated image with `animated_format`,
+    /// only [`Gif`] and [`Png`] (APNG) can be used for animated image.
+    ///
+    /// [`Gif`]: image::ImageFormat::Gif
+    /// [`Png`]: image::ImageFormat::Png
+    pub fn write_to<W, F>(
+        &self,
+        w: &mut W,
+        static_format: F,
+        animated_format: image::ImageFormat,
+    ) -> image::ImageResult<()>
     where
         W: std::io::Write + Seek,
         F: Into<image::ImageOutputFormat>,
@@ -121,42 +180,132 @@ impl ImageData {
             ImageData::StaticImage(image) => {
                 image.write_to(w, static_format)?;
             }
-            ImageData::AnimatedImage(image) => {
-                // let mut encode = gif::Encoder;
-                let mut encode = image::codecs::gif::GifEncoder::new(w);
-                // encode.set_repeat(image::codecs::gif::Repeat::Infinite)?;
-
-                for it in image {
-                    let image = it.image.to_rgba8();
-                    let frame = image::Frame::from_parts(image, it.top, it.left, it.delay);
-                    encode.encode_frame(frame)?;
-                }
+            ImageData::StreamedAnimation(_, first) => {
+                first.image.write_to(w, static_format)?;
             }
+            ImageData::AnimatedImage(image, repeat) => match animated_format {
+                image::ImageFormat::Gif => {
+                    let mut encode = image::codecs::gif::GifEncoder::new(w);
+                    // GIF without loop count is played once.
+                    match *repeat {
+                        Repeat::Infinite => {
+                            encode.set_repeat(image::codecs::gif::Repeat::Infinite)?
+                        }
+                        Repeat::Finite(0 | 1) => {}
+                        Repeat::Finite(n) => encode.set_repeat(
+                            image::codecs::gif::Repeat::Finite((n - 1).min(u16::MAX as u32) as u16),
+                        )?,
+                    }
+
+                    for it in image {
+                        let image = it.image.to_rgba8();
+                        let frame = image::Frame::from_parts(image, it.left, it.top, it.delay);
+                        encode.encode_frame(frame)?;
+                    }
+                }
+                image::ImageFormat::Png => write_apng(w, image, *repeat)?,
+                format => {
+                    return Err(image::ImageError::Unsupported(
+                        image::error::UnsupportedError::from_format_and_kind(
+                            format.into(),
+                            image::error::UnsupportedErrorKind::Format(format.into()),
+                        ),
+                    ))
+                }
+            },
         };
 
         Ok(())
     }
 }
 
+fn write_apng<W: std::io::Write>(
+    w: W,
+    frames: &[FrameData],
+    repeat: Repeat,
+) -> image::ImageResult<()> {
+    let to_image_error = |err: png::EncodingError| {
+        image::ImageError::Encoding(image::error::EncodingError::new(
+            image::ImageFormat::Png.into(),
+            err,
+        ))
+    };
+
+    let (width, height) = frames.iter().fold((1, 1), |(width, height), it| {
+        (
+            width.max(it.left + it.image.width()),
+            height.max(it.top + it.image.height()),
+        )
+    });
+
+    let mut encoder = png::Encoder::new(w, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .set_animated(frames.len() as u32, repeat.into_plays())
+        .map_err(to_image_error)?;
+
+    let mut writer = encoder.write_header().map_err(to_image_error)?;
+
+    for it in frames {
+        // delay in millisecond
+        let delay = Duration::from(it.delay).as_millis().min(u16::MAX as u128) as u16;
+
+        let image = it.image.to_rgba8();
+        writer
+            .set_frame_dimension(image.width(), image.height())
+            .and_then(|_| writer.set_frame_position(it.left, it.top))
+            .and_then(|_| writer.set_frame_delay(delay, 1000))
+            .and_then(|_| writer.write_image_data(image.as_raw()))
+            .map_err(to_image_error)?;
+    }
+
+    writer.finish().map_err(to_image_error)
+}
+
+impl Repeat {
+    /// Number of play used by APNG and WebP, 0 means infinite.
+    pub fn from_plays(plays: u32) -> Self {
+        match plays {
+            0 => Self::Infinite,
+            n => Self::Finite(n),
+        }
+    }
+
+    pub fn into_plays(self) -> u32 {
+        match self {
+            Self::Infinite => 0,
+            Self::Finite(n) => n,
+        }
+    }
+}
+
 impl ImageData {
     pub fn can_read(name: &str) -> bool {
-        image::ImageFormat::from_path(name)
+        Format::from_path(name)
             .map(|it| it.can_read())
             .unwrap_or(false)
     }
 
     pub fn open(path: impl AsRef<Path>) -> image::ImageResult<Self> {
-        let image = match image::ImageFormat::from_path(&path) {
-            Ok(image::ImageFormat::Gif) => Self::load_gif(std::fs::File::open(path)?)?,
+        let image = match Format::from_path(&path) {
+            Some(Format::Image(
+                format @ (image::ImageFormat::Gif
+                | image::ImageFormat::Png
+                | image::ImageFormat::WebP),
+            )) => {
+                let read = std::io::BufReader::new(std::fs::File::open(path)?);
+                Self::load_with_format(read, format)?
+            }
             // guess format from memory
-            Ok(_) | Err(image::ImageError::Unsupported(_)) => {
-                let image = image::io::Reader::open(path)?
-                    .with_guessed_format()?
-                    .decode()?;
-
-                ImageData::StaticImage(image)
+            Some(Format::Image(_)) | None => {
+                Self::load(std::io::BufReader::new(std::fs::File::open(path)?))?
+            }
+            #[allow(unreachable_patterns)]
+            Some(format) => {
+                let read = std::io::BufReader::new(std::fs::File::open(path)?);
+                ImageData::StaticImage(format.decode(read)?)
             }
-            Err(err) => return Err(err),
         };
 
         Ok(image)
@@ -165,6 +314,22 @@ impl ImageData {
     pub fn load_gif(read: impl Read) -> image::ImageResult<Self> {
         let decoder = image::codecs::gif::GifDecoder::new(read)?;
 
+        Self::load_animation(decoder)
+    }
+
+    pub fn load_apng(read: impl Read) -> image::ImageResult<Self> {
+        let decoder = image::codecs::png::PngDecoder::new(read)?.apng();
+
+        Self::load_animation(decoder)
+    }
+
+    pub fn load_webp(read: impl Read) -> image::ImageResult<Self> {
+        let decoder = image::codecs::webp::WebPDecoder::new(read)?;
+
+        Self::load_animation(decoder)
+    }
+
+    pub fn load_animation<'a>(decoder: impl AnimationDecoder<'a>) -> image::ImageResult<Self> {
         let frames = decoder
             .into_frames()
             .collect_frames()?
@@ -172,43 +337,51 @@ impl ImageData {
             .map(FrameData::from)
             .collect::<Vec<_>>();
 
-        Ok(ImageData::AnimatedImage(frames))
+        Ok(ImageData::AnimatedImage(frames, Repeat::default()))
     }
 
     pub fn load(mut read: impl BufRead + Seek) -> image::ImageResult<Self> {
-        let format = image::io::Reader::new(&mut read).with_guessed_format()?;
-        let format = format.format();
-
-        if let Some(format) = format {
-            Self::load_with_format(read, format)
-        } else {
-            todo!();
+        match Format::from_reader(&mut read)? {
+            Some(Format::Image(format)) => Self::load_with_format(read, format),
+            #[allow(unreachable_patterns)]
+            Some(format) => Ok(ImageData::StaticImage(format.decode(read)?)),
+            None => Err(image::ImageError::Unsupported(
+                image::error::ImageFormatHint::Unknown.into(),
+            )),
         }
     }
 
     pub fn load_from_name(name: &str, mut read: impl Read) -> image::ImageResult<Self> {
-        let format = image::ImageFormat::from_path(std::path::PathBuf::from(name))?;
+        let format = Format::from_path(name).ok_or_else(|| {
+            image::ImageError::Unsupported(
+                image::error::ImageFormatHint::PathExtension(name.into()).into(),
+            )
+        })?;
 
         let mut reader = Vec::new();
         read.read_to_end(&mut reader)?;
 
-        ImageData::load_with_format(Cursor::new(reader), format)
+        match format {
+            Format::Image(format) => ImageData::load_with_format(Cursor::new(reader), format),
+            #[allow(unreachable_patterns)]
+            format => Ok(ImageData::StaticImage(format.decode(Cursor::new(reader))?)),
+        }
     }
 
     pub fn load_with_format(
-        read: impl BufRead + Seek,
+        mut read: impl BufRead + Seek,
         format: image::ImageFormat,
     ) -> image::ImageResult<Self> {
-        let image = match format {
-            image::ImageFormat::Gif => {
-                let decoder = image::codecs::gif::GifDecoder::new(read)?;
-
-                let frames = decoder.into_frames().collect_frames()?;
+        if let Some(stream) = AnimationStream::from_reader(&mut read, format)? {
+            let first = stream.first_frame()?;
+            return Ok(ImageData::StreamedAnimation(stream, first));
+        }
 
-                let frames = frames.into_iter().map(FrameData::from).collect();
-
-                ImageData::AnimatedImage(frames)
-            }
+        let repeat = reader::read_repeat(&mut read, format)?;
+        let image = match (format, reader::is_animated(&mut read, format)?) {
+            (image::ImageFormat::Gif, _) => Self::load_gif(read)?.with_repeat(repeat),
+            (image::ImageFormat::Png, true) => Self::load_apng(read)?.with_repeat(repeat),
+            (image::ImageFormat::WebP, true) => Self::load_webp(read)?.with_repeat(repeat),
 
             _ => {
                 let image = image::io::Reader::with_format(read, format).decode()?;
@@ -220,10 +393,22 @@ impl ImageData {
         Ok(image)
     }
 
-    pub fn load_from_memory(data: &[u8]) -> image::ImageResult<Self> {
-        let format = image::guess_format(data)?;
+    fn with_repeat(self, repeat: Repeat) -> Self {
+        match self {
+            Self::AnimatedImage(frames, _) => Self::AnimatedImage(frames, repeat),
+            image => image,
+        }
+    }
 
-        Self::load_from_memory_with_format(data, format)
+    pub fn load_from_memory(data: &[u8]) -> image::ImageResult<Self> {
+        match Format::from_memory(data) {
+            Some(Format::Image(format)) => Self::load_from_memory_with_format(data, format),
+            #[allow(unreachable_patterns)]
+            Some(format) => Ok(ImageData::StaticImage(format.decode(data)?)),
+            None => Err(image::ImageError::Unsupported(
+                image::error::ImageFormatHint::Unknown.into(),
+            )),
+        }
     }
 
     pub fn load_from_memory_with_format(
@@ -231,7 +416,9 @@ impl ImageData {
         format: image::ImageFormat,
     ) -> image::ImageResult<Self> {
         let image = match format {
-            image::ImageFormat::Gif => Self::load_gif(data)?,
+            image::ImageFormat::Gif | image::ImageFormat::Png | image::ImageFormat::WebP => {
+                Self::load_with_format(Cursor::new(data), format)?
+            }
             format => {
                 let image = image::load_from_memory_with_format(data, format)?;
 
@@ -342,7 +529,8 @@ pub struct SplittedFrameData {
 #[derive(Clone)]
 pub enum SplittedImageData {
     StaticImage(SplittedImage),
-    AnimatedImage(Vec<SplittedFrameData>),
+    AnimatedImage(Vec<SplittedFrameData>, Repeat),
+    StreamedAnimation(AnimationStream, SplittedFrameData, (u32, u32)),
 }
 
 impl SplittedImageData {
@@ -382,7 +570,8 @@ impl From<SplittedFrameData> for EguiSplittedFrameData {
 
 pub enum EguiSplittedImageData {
     StaticImage(EguiSplittedStaticImageData),
-    AnimatedImage(Vec<EguiSplittedFrameData>),
+    AnimatedImage(Vec<EguiSplittedFrameData>, Repeat),
+    StreamedAnimation(AnimationStream, EguiSplittedFrameData, (u32, u32)),
 }
 
 impl EguiSplittedImageData {
@@ -402,9 +591,12 @@ impl From<SplittedImageData> for EguiSplittedImageData {
             SplittedImageData::StaticImage(image) => {
                 Self::StaticImage(EguiSplittedStaticImageData::from(image))
             }
-            SplittedImageData::AnimatedImage(image) => {
-                //
-                Self::AnimatedImage(image.into_iter().map(EguiSplittedFrameData::from).collect())
+            SplittedImageData::AnimatedImage(image, repeat) => Self::AnimatedImage(
+                image.into_iter().map(EguiSplittedFrameData::from).collect(),
+                repeat,
+            ),
+            SplittedImageData::StreamedAnimation(stream, first, max_size) => {
+                Self::StreamedAnimation(stream, EguiSplittedFrameData::from(first), max_size)
             }
         }
     }
@@ -1,35 +1,241 @@
 use std::{
     fs::File,
-    io::{BufRead, BufReader, Cursor, Read, Seek},
+    io::{BufRead, BufReader, Cursor, Read, Seek, SeekFrom},
     path::Path,
 };
 
 use image::AnimationDecoder;
 
-use crate::ImageData;
+use crate::{AnimationStream, Format, ImageData, Repeat};
 
 pub enum Reader<T: Read> {
     Reader(Box<image::io::Reader<T>>),
-    Gif(Box<image::codecs::gif::GifDecoder<T>>),
+    Gif(Box<image::codecs::gif::GifDecoder<T>>, Repeat),
+    Apng(Box<image::codecs::png::ApngDecoder<T>>, Repeat),
+    WebP(Box<image::codecs::webp::WebPDecoder<T>>, Repeat),
+    /// format that isn't decoded by `image` crate.
+    Format(T, Format),
+    Stream(AnimationStream),
+}
+
+/// Check whether PNG or WebP in `read` is animated by looking at its header,
+/// the position of `read` is restored afterward.
+pub fn is_animated<R: Read + Seek>(
+    read: &mut R,
+    format: image::ImageFormat,
+) -> std::io::Result<bool> {
+    let start = read.stream_position()?;
+
+    let animated = match format {
+        image::ImageFormat::Png => is_apng(read),
+        image::ImageFormat::WebP => is_animated_webp(read),
+        image::ImageFormat::Gif => Ok(true),
+        _ => Ok(false),
+    };
+
+    read.seek(SeekFrom::Start(start))?;
+
+    // treat unreadable header as not animated and let the decoder report the error.
+    Ok(animated.unwrap_or(false))
+}
+
+// APNG has acTL chunk before the first IDAT chunk.
+fn is_apng<R: Read + Seek>(read: &mut R) -> std::io::Result<bool> {
+    let mut signature = [0; 8];
+    read.read_exact(&mut signature)?;
+
+    loop {
+        let mut header = [0; 8];
+        read.read_exact(&mut header)?;
+
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+
+        match &header[4..] {
+            b"acTL" => return Ok(true),
+            b"IDAT" | b"IEND" => return Ok(false),
+            // skip chunk data and crc
+            _ => read.seek(SeekFrom::Current(length as i64 + 4))?,
+        };
+    }
+}
+
+// animated WebP uses VP8X chunk with animation flag set.
+fn is_animated_webp<R: Read>(read: &mut R) -> std::io::Result<bool> {
+    let mut header = [0; 21];
+    read.read_exact(&mut header)?;
+
+    const ANIMATION_FLAG: u8 = 0b0000_0010;
+    Ok(&header[12..16] == b"VP8X" && header[20] & ANIMATION_FLAG != 0)
+}
+
+/// Read how many times the GIF, APNG or WebP animation in `read` is played,
+/// the position of `read` is restored afterward.
+pub fn read_repeat<R: Read + Seek>(
+    read: &mut R,
+    format: image::ImageFormat,
+) -> std::io::Result<Repeat> {
+    let start = read.stream_position()?;
+
+    let repeat = match format {
+        image::ImageFormat::Gif => gif_repeat(read),
+        image::ImageFormat::Png => apng_repeat(read),
+        image::ImageFormat::WebP => webp_repeat(read),
+        _ => Ok(Repeat::Infinite),
+    };
+
+    read.seek(SeekFrom::Start(start))?;
+
+    Ok(repeat.unwrap_or_default())
+}
+
+// NETSCAPE2.0 application extension is one of the extensions before the first image
+// descriptor, its loop count is the number of repetition after the first play.
+// GIF without the extension is played once.
+fn gif_repeat<R: Read + Seek>(read: &mut R) -> std::io::Result<Repeat> {
+    const NETSCAPE: &[u8] = b"NETSCAPE2.0";
+    const ANIMEXTS: &[u8] = b"ANIMEXTS1.0";
+
+    // header and logical screen descriptor.
+    let mut header = [0; 6 + 7];
+    read.read_exact(&mut header)?;
+
+    let flags = header[10];
+    if flags & 0b1000_0000 != 0 {
+        let color_table = 3 * (1 << ((flags & 0b111) + 1));
+        read.seek(SeekFrom::Current(color_table))?;
+    }
+
+    // skip data sub-blocks until the block terminator.
+    fn skip_sub_blocks<R: Read + Seek>(read: &mut R) -> std::io::Result<()> {
+        loop {
+            let mut size = [0];
+            read.read_exact(&mut size)?;
+            if size[0] == 0 {
+                return Ok(());
+            }
+            read.seek(SeekFrom::Current(size[0] as i64))?;
+        }
+    }
+
+    loop {
+        let mut introducer = [0; 2];
+        read.read_exact(&mut introducer[..1])?;
+
+        // anything other than extension is image descriptor, trailer or invalid.
+        if introducer[0] != 0x21 {
+            return Ok(Repeat::Finite(1));
+        }
+
+        read.read_exact(&mut introducer[1..])?;
+
+        if introducer[1] == 0xFF {
+            let mut application = [0; 12];
+            read.read_exact(&mut application)?;
+
+            let identifier = &application[1..];
+            if application[0] == 11 && (identifier == NETSCAPE || identifier == ANIMEXTS) {
+                // sub-block size, sub-block id and loop count.
+                let mut data = [0; 4];
+                read.read_exact(&mut data)?;
+
+                if data[0] >= 3 && data[1] == 1 {
+                    let repeat = match u16::from_le_bytes([data[2], data[3]]) {
+                        0 => Repeat::Infinite,
+                        count => Repeat::Finite(count as u32 + 1),
+                    };
+                    return Ok(repeat);
+                }
+
+                read.seek(SeekFrom::Current(data[0] as i64 - 3))?;
+                if data[0] == 0 {
+                    continue;
+                }
+            } else {
+                read.seek(SeekFrom::Current(application[0] as i64 - 11))?;
+            }
+        }
+
+        skip_sub_blocks(read)?;
+    }
+}
+
+// number of play is in acTL chunk before the first IDAT chunk.
+fn apng_repeat<R: Read + Seek>(read: &mut R) -> std::io::Result<Repeat> {
+    let mut signature = [0; 8];
+    read.read_exact(&mut signature)?;
+
+    loop {
+        let mut header = [0; 8];
+        read.read_exact(&mut header)?;
+
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+
+        match &header[4..] {
+            b"acTL" => {
+                let mut control = [0; 8];
+                read.read_exact(&mut control)?;
+
+                let plays = u32::from_be_bytes([control[4], control[5], control[6], control[7]]);
+                return Ok(Repeat::from_plays(plays));
+            }
+            b"IDAT" | b"IEND" => return Ok(Repeat::Infinite),
+            // skip chunk data and crc
+            _ => read.seek(SeekFrom::Current(length as i64 + 4))?,
+        };
+    }
+}
+
+// loop count is in ANIM chunk after VP8X chunk.
+fn webp_repeat<R: Read + Seek>(read: &mut R) -> std::io::Result<Repeat> {
+    let mut riff = [0; 12];
+    read.read_exact(&mut riff)?;
+
+    loop {
+        let mut header = [0; 8];
+        read.read_exact(&mut header)?;
+
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+
+        match &header[..4] {
+            b"ANIM" => {
+                let mut anim = [0; 6];
+                read.read_exact(&mut anim)?;
+
+                let plays = u16::from_le_bytes([anim[4], anim[5]]);
+                return Ok(Repeat::from_plays(plays as u32));
+            }
+            b"ANMF" => return Ok(Repeat::Infinite),
+            // chunk is padded to even size
+            _ => read.seek(SeekFrom::Current((size + size % 2) as i64))?,
+        };
+    }
 }
 
 impl Reader<BufReader<File>> {
     pub fn open(path: impl AsRef<Path>) -> Result<Self, image::ImageError> {
-        let load_gif = || Self::load_gif(BufReader::new(std::fs::File::open(&path)?));
+        let load_gif = || {
+            let read = BufReader::new(std::fs::File::open(&path)?);
+            Self::load_animation(read, image::ImageFormat::Gif)
+        };
 
-        let reader = match image::ImageFormat::from_path(&path) {
-            Ok(image::ImageFormat::Gif) => load_gif()?,
+        let reader = match Format::from_path(&path) {
+            Some(Format::Image(image::ImageFormat::Gif)) => load_gif()?,
             // guess format from memory
-            Ok(_) | Err(image::ImageError::Unsupported(_)) => {
-                let reader = image::io::Reader::open(&path)?.with_guessed_format()?;
+            Some(Format::Image(_)) | None => {
+                let mut read = BufReader::new(std::fs::File::open(&path)?);
 
-                if let Some(image::ImageFormat::Gif) = reader.format() {
-                    load_gif()?
-                } else {
-                    Self::Reader(Box::new(reader))
+                match Format::from_reader(&mut read)? {
+                    Some(Format::Image(image::ImageFormat::Gif)) => load_gif()?,
+                    Some(Format::Image(format)) => {
+                        Self::from_image_reader(image::io::Reader::with_format(read, format))?
+                    }
+                    #[allow(unreachable_patterns)]
+                    Some(format) => Self::Format(read, format),
+                    None => Self::from_image_reader(image::io::Reader::new(read))?,
                 }
             }
-            Err(err) => return Err(err),
+            #[allow(unreachable_patterns)]
+            Some(format) => Self::Format(BufReader::new(std::fs::File::open(&path)?), format),
         };
 
         Ok(reader)
@@ -38,24 +244,97 @@ impl Reader<BufReader<File>> {
 
 impl<T: AsRef<[u8]>> Reader<Cursor<T>> {
     pub fn load_from_memory(memory: T) -> Result<Self, image::ImageError> {
-        let reader = match image::guess_format(memory.as_ref())? {
-            image::ImageFormat::Gif => Self::load_gif(Cursor::new(memory))?,
-            format => {
-                let reader = image::io::Reader::with_format(Cursor::new(memory), format)
-                    .with_guessed_format()?;
-                Self::Reader(Box::new(reader))
+        let format = Format::from_memory(memory.as_ref()).ok_or_else(|| {
+            image::ImageError::Unsupported(image::error::ImageFormatHint::Unknown.into())
+        })?;
+
+        let reader = match format {
+            Format::Image(image::ImageFormat::Gif) => {
+                Self::load_animation(Cursor::new(memory), image::ImageFormat::Gif)?
             }
+            Format::Image(format) => {
+                let reader = image::io::Reader::with_format(Cursor::new(memory), format);
+                Self::from_image_reader(reader)?
+            }
+            #[allow(unreachable_patterns)]
+            format => Self::Format(Cursor::new(memory), format),
         };
 
         Ok(reader)
     }
 }
 
+impl<T: BufRead + Seek> Reader<T> {
+    /// Use animation decoder when the image in `reader` is animated PNG or WebP.
+    pub fn from_image_reader(reader: image::io::Reader<T>) -> Result<Self, image::ImageError> {
+        let format = match reader.format() {
+            Some(format @ (image::ImageFormat::Png | image::ImageFormat::WebP)) => format,
+            _ => return Ok(Self::Reader(Box::new(reader))),
+        };
+
+        let mut read = reader.into_inner();
+
+        if is_animated(&mut read, format)? {
+            Self::load_animation(read, format)
+        } else {
+            Ok(Self::Reader(Box::new(image::io::Reader::with_format(
+                read, format,
+            ))))
+        }
+    }
+
+    /// Load GIF, APNG or WebP animation in `read` with its repeat setting.
+    pub fn load_animation(
+        mut read: T,
+        format: image::ImageFormat,
+    ) -> Result<Self, image::ImageError> {
+        if let Some(stream) = AnimationStream::from_reader(&mut read, format)? {
+            return Ok(Self::Stream(stream));
+        }
+
+        let repeat = read_repeat(&mut read, format)?;
+
+        let reader = match format {
+            image::ImageFormat::Gif => Self::load_gif(read)?,
+            image::ImageFormat::Png => Self::load_apng(read)?,
+            image::ImageFormat::WebP => Self::load_webp(read)?,
+            _ => {
+                return Ok(Self::Reader(Box::new(image::io::Reader::with_format(
+                    read, format,
+                ))))
+            }
+        };
+
+        Ok(reader.with_repeat(repeat))
+    }
+}
+
 impl<T: Read> Reader<T> {
     pub fn load_gif(read: T) -> Result<Self, image::ImageError> {
         let decoder = image::codecs::gif::GifDecoder::new(read)?;
 
-        Ok(Self::Gif(Box::new(decoder)))
+        Ok(Self::Gif(Box::new(decoder), Repeat::default()))
+    }
+
+    pub fn load_apng(read: T) -> Result<Self, image::ImageError> {
+        let decoder = image::codecs::png::PngDecoder::new(read)?.apng();
+
+        Ok(Self::Apng(Box::new(decoder), Repeat::default()))
+    }
+
+    pub fn load_webp(read: T) -> Result<Self, image::ImageError> {
+        let decoder = image::codecs::webp::WebPDecoder::new(read)?;
+
+        Ok(Self::WebP(Box::new(decoder), Repeat::default()))
+    }
+
+    pub fn with_repeat(self, repeat: Repeat) -> Self {
+        match self {
+            Self::Gif(decoder, _) => Self::Gif(decoder, repeat),
+            Self::Apng(decoder, _) => Self::Apng(decoder, repeat),
+            Self::WebP(decoder, _) => Self::WebP(decoder, repeat),
+            reader => reader,
+        }
     }
 }
 
@@ -63,14 +342,20 @@ impl<'a, T: Read + 'a> Reader<T> {
     pub fn into_frames(self) -> Frames<'a, T> {
         match self {
             Reader::Reader(reader) => Frames::SingleImage(reader),
-            Reader::Gif(frames) => Frames::Frames(frames.into_frames()),
+            Reader::Gif(frames, repeat) => Frames::Frames(frames.into_frames(), repeat),
+            Reader::Apng(frames, repeat) => Frames::Frames(frames.into_frames(), repeat),
+            Reader::WebP(frames, repeat) => Frames::Frames(frames.into_frames(), repeat),
+            Reader::Format(read, format) => Frames::Format(read, format),
+            Reader::Stream(stream) => Frames::Stream(stream),
         }
     }
 }
 
 pub enum Frames<'a, T: Read + 'a> {
     SingleImage(Box<image::io::Reader<T>>),
-    Frames(image::Frames<'a>),
+    Frames(image::Frames<'a>, Repeat),
+    Format(T, Format),
+    Stream(AnimationStream),
 }
 
 impl<'a, T: BufRead + Seek> Frames<'a, T> {
@@ -79,9 +364,14 @@ impl<'a, T: BufRead + Seek> Frames<'a, T> {
             Self::SingleImage(reader) => {
                 FramesCollector::SingleImage(SingleImageEither::Reader(Some(reader)))
             }
-            Self::Frames(frames) => FramesCollector::Frames {
+            Self::Format(read, format) => {
+                FramesCollector::SingleImage(SingleImageEither::Format(Some((read, format))))
+            }
+            Self::Stream(stream) => FramesCollector::Stream(stream, None),
+            Self::Frames(frames, repeat) => FramesCollector::Frames {
                 frames,
                 collected: Vec::new(),
+                repeat,
             },
         }
     }
@@ -89,6 +379,7 @@ impl<'a, T: BufRead + Seek> Frames<'a, T> {
 
 pub enum SingleImageEither<T: Read> {
     Reader(Option<Box<image::io::Reader<T>>>),
+    Format(Option<(T, Format)>),
     Image(image::DynamicImage),
 }
 
@@ -100,7 +391,10 @@ pub enum FramesCollector<'a, T: Read + 'a> {
     Frames {
         frames: image::Frames<'a>,
         collected: Vec<super::FrameData>,
+        repeat: Repeat,
     },
+    /// only the first frame is decoded, the rest is decoded while it's played.
+    Stream(AnimationStream, Option<super::FrameData>),
 }
 
 unsafe impl<'a, T: Read + Send + 'a> Send for Frames<'a, T> {}
@@ -110,17 +404,28 @@ impl<'a, T: BufRead + Seek> FramesCollector<'a, T> {
     pub fn load_next(&mut self) -> Result<bool, image::ImageError> {
         match self {
             FramesCollector::SingleImage(image) => {
-                if let SingleImageEither::Reader(reader) = image {
-                    if let Some(reader) = reader.take() {
-                        let time = std::time::Instant::now();
-                        *image = SingleImageEither::Image(reader.decode()?);
-                        tracing::info!("decoding image in {:?}", time.elapsed());
+                let time = std::time::Instant::now();
+                match image {
+                    SingleImageEither::Reader(reader) => {
+                        if let Some(reader) = reader.take() {
+                            *image = SingleImageEither::Image(reader.decode()?);
+                            tracing::info!("decoding image in {:?}", time.elapsed());
+                        }
                     }
+                    SingleImageEither::Format(reader) => {
+                        if let Some((read, format)) = reader.take() {
+                            *image = SingleImageEither::Image(format.decode(read)?);
+                            tracing::info!("decoding {:?} in {:?}", format, time.elapsed());
+                        }
+                    }
+                    SingleImageEither::Image(_) => {}
                 }
 
                 Ok(false)
             }
-            FramesCollector::Frames { frames, collected } => {
+            FramesCollector::Frames {
+                frames, collected, ..
+            } => {
                 if let Some(image) = frames.next() {
                     let image = image?;
                     collected.push(image.into());
@@ -130,16 +435,28 @@ impl<'a, T: BufRead + Seek> FramesCollector<'a, T> {
                     Ok(false)
                 }
             }
+            FramesCollector::Stream(stream, first) => {
+                if first.is_none() {
+                    *first = Some(stream.first_frame()?);
+                }
+
+                Ok(false)
+            }
         }
     }
 
     pub fn into_image(self) -> Option<ImageData> {
         match self {
             FramesCollector::SingleImage(image) => match image {
-                SingleImageEither::Reader(_) => None,
+                SingleImageEither::Reader(_) | SingleImageEither::Format(_) => None,
                 SingleImageEither::Image(image) => Some(ImageData::StaticImage(image)),
             },
-            FramesCollector::Frames { collected, .. } => Some(ImageData::AnimatedImage(collected)),
+            FramesCollector::Frames {
+                collected, repeat, ..
+            } => Some(ImageData::AnimatedImage(collected, repeat)),
+            FramesCollector::Stream(stream, first) => {
+                first.map(|first| ImageData::StreamedAnimation(stream, first))
+            }
         }
     }
 
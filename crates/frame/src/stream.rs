@@ -0,0 +1,486 @@
+use std::{
+    collections::VecDeque,
+    io::{Cursor, Read, Seek, SeekFrom},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use eframe::epaint::mutex::RwLock as EguiRwLock;
+use eframe::epaint::TextureManager;
+use image::AnimationDecoder;
+use tokio::sync::mpsc;
+
+use crate::{
+    reader::read_repeat, EguiSplittedFrameData, FrameData, Repeat, SplittedFrameData,
+    SplittedImage, TextureFrameHandle, TextureOption,
+};
+
+/// Estimated memory of all decoded frames above which animation is streamed.
+pub const STREAM_THRESHOLD: u64 = 256 * 1024 * 1024;
+
+// number of frames decoded ahead of the shown frame.
+const DECODE_AHEAD: usize = 8;
+// number of decoded frames uploaded as texture ahead of the shown frame.
+const UPLOAD_AHEAD: usize = 2;
+
+/// Encoded animation decoded frame by frame while it's played.
+#[derive(Clone)]
+pub struct AnimationStream {
+    data: Arc<[u8]>,
+    format: image::ImageFormat,
+    frame_count: usize,
+    repeat: Repeat,
+}
+
+impl AnimationStream {
+    /// Returns `Some` when decoded frames of the animation in `read` is estimated to
+    /// take more than [`STREAM_THRESHOLD`], the position of `read` is restored otherwise.
+    pub fn from_reader<R: Read + Seek>(
+        read: &mut R,
+        format: image::ImageFormat,
+    ) -> std::io::Result<Option<Self>> {
+        let start = read.stream_position()?;
+
+        let info = match format {
+            image::ImageFormat::Gif => gif_info(read),
+            image::ImageFormat::Png => apng_info(read),
+            image::ImageFormat::WebP => webp_info(read),
+            _ => return Ok(None),
+        };
+
+        read.seek(SeekFrom::Start(start))?;
+
+        let (width, height, frame_count) = match info {
+            Ok(info @ (_, _, 2..)) => info,
+            _ => return Ok(None),
+        };
+
+        let estimated = width as u64 * height as u64 * 4 * frame_count as u64;
+        if estimated <= STREAM_THRESHOLD {
+            return Ok(None);
+        }
+
+        tracing::info!(
+            "streaming {} frames of {}x{}, estimated {} MiB",
+            frame_count,
+            width,
+            height,
+            estimated / 1024 / 1024
+        );
+
+        let repeat = read_repeat(read, format)?;
+
+        let mut data = Vec::new();
+        read.read_to_end(&mut data)?;
+
+        Ok(Some(Self {
+            data: data.into(),
+            format,
+            frame_count,
+            repeat,
+        }))
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    pub fn repeat(&self) -> Repeat {
+        self.repeat
+    }
+
+    /// Decode frames from the start.
+    pub fn frames(&self) -> image::ImageResult<image::Frames<'static>> {
+        let read = Cursor::new(self.data.clone());
+
+        let frames = match self.format {
+            image::ImageFormat::Gif => image::codecs::gif::GifDecoder::new(read)?.into_frames(),
+            image::ImageFormat::Png => image::codecs::png::PngDecoder::new(read)?
+                .apng()
+                .into_frames(),
+            _ => image::codecs::webp::WebPDecoder::new(read)?.into_frames(),
+        };
+
+        Ok(frames)
+    }
+
+    pub fn first_frame(&self) -> image::ImageResult<FrameData> {
+        let frame = self.frames()?.next().ok_or_else(|| {
+            image::ImageError::Decoding(image::error::DecodingError::new(
+                self.format.into(),
+                "animation without frame",
+            ))
+        })??;
+
+        Ok(frame.into())
+    }
+}
+
+// logical screen size and number of image descriptor.
+fn gif_info<R: Read>(read: &mut R) -> std::io::Result<(u32, u32, usize)> {
+    let mut buffer = [0; 768];
+
+    let mut skip = |read: &mut R, len: usize| read.read_exact(&mut buffer[..len]);
+    let color_table = |packed: u8| {
+        if packed & 0x80 != 0 {
+            3 * (1 << ((packed & 0x07) + 1))
+        } else {
+            0
+        }
+    };
+
+    let mut header = [0; 13];
+    read.read_exact(&mut header)?;
+
+    let width = u16::from_le_bytes([header[6], header[7]]) as u32;
+    let height = u16::from_le_bytes([header[8], header[9]]) as u32;
+    skip(read, color_table(header[10]))?;
+
+    let mut skip_sub_blocks = |read: &mut R| -> std::io::Result<()> {
+        loop {
+            let mut size = [0];
+            read.read_exact(&mut size)?;
+            if size[0] == 0 {
+                return Ok(());
+            }
+            skip(read, size[0] as usize)?;
+        }
+    };
+
+    let mut frames = 0;
+    loop {
+        let mut introducer = [0];
+        read.read_exact(&mut introducer)?;
+
+        match introducer[0] {
+            // extension, skip its label and data.
+            0x21 => {
+                read.read_exact(&mut [0])?;
+                skip_sub_blocks(read)?;
+            }
+            // image descriptor, skip local color table, LZW code size and data.
+            0x2C => {
+                frames += 1;
+
+                let mut descriptor = [0; 9];
+                read.read_exact(&mut descriptor)?;
+
+                let len = color_table(descriptor[8]) + 1;
+                std::io::copy(&mut read.by_ref().take(len as u64), &mut std::io::sink())?;
+                skip_sub_blocks(read)?;
+            }
+            // trailer
+            _ => break,
+        }
+    }
+
+    Ok((width, height, frames))
+}
+
+// size from IHDR chunk and number of frames from acTL chunk.
+fn apng_info<R: Read + Seek>(read: &mut R) -> std::io::Result<(u32, u32, usize)> {
+    let mut signature = [0; 8];
+    read.read_exact(&mut signature)?;
+
+    let mut size = (0, 0);
+    loop {
+        let mut header = [0; 8];
+        read.read_exact(&mut header)?;
+
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+
+        match &header[4..] {
+            b"IHDR" => {
+                let mut ihdr = [0; 8];
+                read.read_exact(&mut ihdr)?;
+                size = (
+                    u32::from_be_bytes([ihdr[0], ihdr[1], ihdr[2], ihdr[3]]),
+                    u32::from_be_bytes([ihdr[4], ihdr[5], ihdr[6], ihdr[7]]),
+                );
+
+                read.seek(SeekFrom::Current(length as i64 - 8 + 4))?;
+            }
+            b"acTL" => {
+                let mut control = [0; 4];
+                read.read_exact(&mut control)?;
+
+                let frames = u32::from_be_bytes(control) as usize;
+                return Ok((size.0, size.1, frames));
+            }
+            b"IDAT" | b"IEND" => return Ok((size.0, size.1, 1)),
+            // skip chunk data and crc
+            _ => {
+                read.seek(SeekFrom::Current(length as i64 + 4))?;
+            }
+        };
+    }
+}
+
+// canvas size from VP8X chunk and number of ANMF chunk.
+fn webp_info<R: Read + Seek>(read: &mut R) -> std::io::Result<(u32, u32, usize)> {
+    let mut riff = [0; 12];
+    read.read_exact(&mut riff)?;
+
+    let mut size = (0, 0);
+    let mut frames = 0;
+    loop {
+        let mut header = [0; 8];
+        match read.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err),
+        }
+
+        let length = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        // chunk is padded to even size
+        let mut skip = (length + length % 2) as i64;
+
+        match &header[..4] {
+            b"VP8X" => {
+                let mut vp8x = [0; 10];
+                read.read_exact(&mut vp8x)?;
+                skip -= 10;
+
+                let width = u32::from_le_bytes([vp8x[4], vp8x[5], vp8x[6], 0]) + 1;
+                let height = u32::from_le_bytes([vp8x[7], vp8x[8], vp8x[9], 0]) + 1;
+                size = (width, height);
+            }
+            b"ANMF" => frames += 1,
+            _ => {}
+        }
+
+        read.seek(SeekFrom::Current(skip))?;
+    }
+
+    Ok((size.0, size.1, frames.max(1)))
+}
+
+#[derive(Clone)]
+pub struct StreamedTextureHandle {
+    name: String,
+    stream: AnimationStream,
+    first: TextureFrameHandle,
+    tex_mgr: Arc<EguiRwLock<TextureManager>>,
+    option: TextureOption,
+    max_size: (u32, u32),
+}
+
+impl StreamedTextureHandle {
+    pub fn new(
+        tex_mgr: Arc<EguiRwLock<TextureManager>>,
+        name: String,
+        stream: AnimationStream,
+        first: EguiSplittedFrameData,
+        option: TextureOption,
+        max_size: (u32, u32),
+    ) -> Self {
+        let first =
+            TextureFrameHandle::from_frame(tex_mgr.clone(), format!("{}-0", name), first, option);
+
+        Self {
+            name,
+            stream,
+            first,
+            tex_mgr,
+            option,
+            max_size,
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn stream(&self) -> &AnimationStream {
+        &self.stream
+    }
+
+    pub fn first(&self) -> &TextureFrameHandle {
+        &self.first
+    }
+}
+
+struct StreamedFrame {
+    index: usize,
+    frame: EguiSplittedFrameData,
+}
+
+/// Frames of [`StreamedTextureHandle`] decoded in another thread and uploaded shortly
+/// before they're shown.
+pub(crate) struct StreamPlayer {
+    handle: StreamedTextureHandle,
+    receiver: Option<mpsc::Receiver<StreamedFrame>>,
+    // set to stop the decoder thread, it's checked before each frame.
+    stop: Arc<AtomicBool>,
+    current: (usize, TextureFrameHandle),
+    upcoming: VecDeque<(usize, TextureFrameHandle)>,
+    // decoder finished sending every frame.
+    ended: bool,
+}
+
+impl StreamPlayer {
+    pub fn new(handle: StreamedTextureHandle) -> Self {
+        let current = (0, handle.first.clone());
+
+        Self {
+            handle,
+            receiver: None,
+            stop: Default::default(),
+            current,
+            upcoming: VecDeque::new(),
+            ended: false,
+        }
+    }
+
+    pub fn handle(&self) -> &StreamedTextureHandle {
+        &self.handle
+    }
+
+    pub fn index(&self) -> usize {
+        self.current.0
+    }
+
+    pub fn frame(&self) -> &TextureFrameHandle {
+        &self.current.1
+    }
+
+    pub fn is_ended(&self) -> bool {
+        self.ended && self.upcoming.is_empty()
+    }
+
+    /// Show the first frame again and decode from the start, the running decoder is stopped
+    /// when the old player is dropped.
+    pub fn restart(&mut self) {
+        *self = Self::new(self.handle.clone());
+    }
+
+    fn start(&mut self) {
+        if self.receiver.is_some() || self.ended {
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel(DECODE_AHEAD);
+        let stream = self.handle.stream.clone();
+        let max_size = self.handle.max_size;
+        let stop = self.stop.clone();
+
+        // the first frame is already uploaded.
+        std::thread::spawn(move || decode(stream, sender, stop, max_size, 1));
+
+        self.receiver = Some(receiver);
+    }
+
+    fn fill(&mut self) {
+        self.start();
+
+        let Some(receiver) = &mut self.receiver else {
+            return;
+        };
+
+        while self.upcoming.len() < UPLOAD_AHEAD {
+            match receiver.try_recv() {
+                Ok(StreamedFrame { index, frame }) => {
+                    let handle = TextureFrameHandle::from_frame(
+                        self.handle.tex_mgr.clone(),
+                        format!("{}-{}", self.handle.name, index),
+                        frame,
+                        self.handle.option,
+                    );
+                    self.upcoming.push_back((index, handle));
+                }
+                Err(mpsc::error::TryRecvError::Empty) => break,
+                Err(mpsc::error::TryRecvError::Disconnected) => {
+                    self.ended = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Move to the next frame, returns `false` if it isn't decoded yet.
+    pub fn advance(&mut self) -> bool {
+        self.fill();
+
+        match self.upcoming.pop_front() {
+            Some(next) => {
+                self.current = next;
+                self.fill();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Drop for StreamPlayer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn decode(
+    stream: AnimationStream,
+    sender: mpsc::Sender<StreamedFrame>,
+    stop: Arc<AtomicBool>,
+    max_size: (u32, u32),
+    mut skip: usize,
+) {
+    let mut played = 0;
+
+    loop {
+        let frames = match stream.frames() {
+            Ok(frames) => frames,
+            Err(err) => {
+                tracing::error!("error decoding animation stream: {}", err);
+                return;
+            }
+        };
+
+        let mut frames = frames.enumerate();
+        loop {
+            // decoding a large frame takes a while, so stop before starting the next one.
+            if stop.load(Ordering::Relaxed) {
+                return;
+            }
+            let Some((index, frame)) = frames.next() else {
+                break;
+            };
+
+            let frame = match frame {
+                Ok(frame) => FrameData::from(frame),
+                Err(err) => {
+                    tracing::error!("error decoding frame {}: {}", index, err);
+                    return;
+                }
+            };
+
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+
+            let frame = SplittedFrameData {
+                delay: frame.delay.into(),
+                image: SplittedImage::make_allocatable(&frame.image, max_size),
+            };
+            let frame = EguiSplittedFrameData::from(frame);
+
+            // receiver is dropped when the page is unloaded.
+            if sender
+                .blocking_send(StreamedFrame { index, frame })
+                .is_err()
+            {
+                return;
+            }
+        }
+
+        played += 1;
+        if let Repeat::Finite(n) = stream.repeat {
+            if played >= n {
+                return;
+            }
+        }
+    }
+}
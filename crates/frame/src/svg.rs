@@ -0,0 +1,102 @@
+use std::{
+    io::Read,
+    path::Path,
+    sync::{Arc, OnceLock},
+};
+
+use resvg::{tiny_skia, usvg};
+
+use crate::{format::decoding_error, Format};
+
+// system fonts are loaded once and shared by every document.
+fn fontdb() -> Arc<usvg::fontdb::Database> {
+    static FONTDB: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+
+    FONTDB
+        .get_or_init(|| {
+            let mut fontdb = usvg::fontdb::Database::new();
+            fontdb.load_system_fonts();
+            Arc::new(fontdb)
+        })
+        .clone()
+}
+
+/// Parsed SVG document, rasterized to any size without losing sharpness.
+#[derive(Clone)]
+pub struct SvgImage {
+    tree: Arc<usvg::Tree>,
+}
+
+impl SvgImage {
+    /// Returns `true` if `path` has SVG extension.
+    pub fn can_read(path: impl AsRef<Path>) -> bool {
+        Format::from_path(path) == Some(Format::Svg)
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> image::ImageResult<Self> {
+        Self::from_reader(std::fs::File::open(path)?)
+    }
+
+    pub fn from_reader(mut read: impl Read) -> image::ImageResult<Self> {
+        let mut data = Vec::new();
+        read.read_to_end(&mut data)?;
+
+        Self::from_memory(&data)
+    }
+
+    /// Parse SVG or gzip compressed SVG.
+    pub fn from_memory(data: &[u8]) -> image::ImageResult<Self> {
+        let options = usvg::Options {
+            fontdb: fontdb(),
+            ..Default::default()
+        };
+
+        let tree = usvg::Tree::from_data(data, &options).map_err(decoding_error("svg"))?;
+
+        Ok(Self {
+            tree: Arc::new(tree),
+        })
+    }
+
+    /// Size of the document as declared by its `width` and `height`.
+    pub fn size(&self) -> [f32; 2] {
+        let size = self.tree.size();
+        [size.width(), size.height()]
+    }
+
+    /// Rasterize with the declared size.
+    pub fn render_original(&self) -> image::ImageResult<image::DynamicImage> {
+        self.render(self.size().map(|it| it.ceil() as u32))
+    }
+
+    /// Rasterize to the largest size fitting within `size`, preserving aspect ratio.
+    pub fn render(&self, size: [u32; 2]) -> image::ImageResult<image::DynamicImage> {
+        let [width, height] = self.size();
+        let scale = (size[0] as f32 / width).min(size[1] as f32 / height);
+
+        let width = (width * scale).round().max(1.0) as u32;
+        let height = (height * scale).round().max(1.0) as u32;
+
+        let mut pixmap = tiny_skia::Pixmap::new(width, height)
+            .ok_or_else(|| decoding_error("svg")("invalid image size"))?;
+        resvg::render(
+            &self.tree,
+            tiny_skia::Transform::from_scale(scale, scale),
+            &mut pixmap.as_mut(),
+        );
+
+        // pixmap is premultiplied while image expects straight alpha.
+        let buffer = pixmap
+            .pixels()
+            .iter()
+            .flat_map(|it| {
+                let it = it.demultiply();
+                [it.red(), it.green(), it.blue(), it.alpha()]
+            })
+            .collect();
+
+        image::RgbaImage::from_raw(width, height, buffer)
+            .map(image::DynamicImage::ImageRgba8)
+            .ok_or_else(|| decoding_error("svg")("invalid image size"))
+    }
+}
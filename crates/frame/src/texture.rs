@@ -1,9 +1,6 @@
 use std::{
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
-    time::Duration,
+    sync::Arc,
+    time::{Duration, Instant},
 };
 
 use eframe::egui;
@@ -11,7 +8,7 @@ use eframe::epaint::mutex::RwLock as EguiRwLock;
 use eframe::epaint::TextureManager;
 use parking_lot::Mutex;
 
-use crate::EguiSplittedFrameData;
+use crate::{stream::StreamPlayer, EguiSplittedFrameData, Repeat, StreamedTextureHandle};
 
 use super::EguiSplittedImageData;
 use serde::{Deserialize, Serialize};
@@ -33,14 +30,36 @@ impl Default for TextureOption {
 pub struct SplittedTextureHandle {
     name: String,
     handles: Vec<Vec<egui::TextureHandle>>,
+    // number of texture pixel shown in one point.
+    pixels_per_point: f32,
 }
 
 impl SplittedTextureHandle {
     pub fn new(name: String, handles: Vec<Vec<egui::TextureHandle>>) -> Self {
-        Self { name, handles }
+        Self {
+            name,
+            handles,
+            pixels_per_point: 1.0,
+        }
+    }
+
+    /// Texture rasterized for display with `pixels_per_point`, shown at its size in points.
+    pub fn with_pixels_per_point(mut self, pixels_per_point: f32) -> Self {
+        self.pixels_per_point = pixels_per_point;
+        self
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
     }
 
+    /// Size in points.
     pub fn size(&self) -> [usize; 2] {
+        self.pixel_size()
+            .map(|it| (it as f32 / self.pixels_per_point).round() as usize)
+    }
+
+    fn pixel_size(&self) -> [usize; 2] {
         let mut height = 0;
 
         let mut first_width = 0;
@@ -119,7 +138,8 @@ impl<'a> SplittedTextureWidget<'a> {
 
     pub fn paint_at(&self, ui: &mut egui::Ui, rect: egui::Rect) {
         let size = self.size;
-        let scale = size / self.texture.size_vec2();
+        let pixel_size: egui::Vec2 = self.texture.pixel_size().map(|it| it as f32).into();
+        let scale = size / pixel_size;
 
         let (mut x, mut y) = (0.0, 0.0);
         let mut size;
@@ -150,6 +170,7 @@ impl<'a> SplittedTextureWidget<'a> {
 pub enum TextureHandle {
     StaticTexture(SplittedTextureHandle),
     AnimatedTexture(AnimatedTextureHandle),
+    StreamedTexture(StreamedTextureHandle),
 }
 
 impl TextureHandle {
@@ -163,9 +184,14 @@ impl TextureHandle {
             EguiSplittedImageData::StaticImage(image) => {
                 TextureHandle::StaticTexture(image.alloc(tex_mgr, name, option))
             }
-            EguiSplittedImageData::AnimatedImage(frames) => TextureHandle::AnimatedTexture(
-                AnimatedTextureHandle::from_data(tex_mgr, name, frames, option),
+            EguiSplittedImageData::AnimatedImage(frames, repeat) => TextureHandle::AnimatedTexture(
+                AnimatedTextureHandle::from_data(tex_mgr, name, frames, option).with_repeat(repeat),
             ),
+            EguiSplittedImageData::StreamedAnimation(stream, first, max_size) => {
+                TextureHandle::StreamedTexture(StreamedTextureHandle::new(
+                    tex_mgr, name, stream, first, option, max_size,
+                ))
+            }
         }
     }
 
@@ -177,6 +203,16 @@ impl TextureHandle {
         match self {
             TextureHandle::StaticTexture(handle) => handle.size(),
             TextureHandle::AnimatedTexture(handle) => handle.max_size(),
+            TextureHandle::StreamedTexture(handle) => handle.first().handle().size(),
+        }
+    }
+
+    /// Returns the static texture or the first frame of animated texture.
+    pub fn first_frame(&self) -> &SplittedTextureHandle {
+        match self {
+            TextureHandle::StaticTexture(handle) => handle,
+            TextureHandle::AnimatedTexture(handle) => handle.items()[0].handle(),
+            TextureHandle::StreamedTexture(handle) => handle.first().handle(),
         }
     }
 }
@@ -185,11 +221,25 @@ impl TextureHandle {
 pub struct AnimatedTextureHandle {
     name: String,
     handles: Vec<TextureFrameHandle>,
+    repeat: Repeat,
 }
 
 impl AnimatedTextureHandle {
     pub fn new(name: String, handles: Vec<TextureFrameHandle>) -> Self {
-        Self { name, handles }
+        Self {
+            name,
+            handles,
+            repeat: Repeat::default(),
+        }
+    }
+
+    pub fn with_repeat(mut self, repeat: Repeat) -> Self {
+        self.repeat = repeat;
+        self
+    }
+
+    pub fn repeat(&self) -> Repeat {
+        self.repeat
     }
 
     pub fn from_data(
@@ -260,7 +310,10 @@ impl TextureFrameHandle {
 pub enum TextureViewState {
     StaticTexture(StaticTextureViewState),
     AnimatedTexture(AnimatedTextureViewState),
+    #[cfg(feature = "video")]
+    Video(crate::VideoTextureViewState),
     Loading(LoadingTexture),
+    Unsupported(UnsupportedTexture),
     Mutable(Arc<Mutex<Self>>),
 }
 
@@ -268,8 +321,11 @@ impl TextureViewState {
     pub fn max_size(&self) -> [usize; 2] {
         match self {
             TextureViewState::StaticTexture(state) => state.handle.size(),
-            TextureViewState::AnimatedTexture(state) => state.handles.max_size(),
+            TextureViewState::AnimatedTexture(state) => state.max_size(),
+            #[cfg(feature = "video")]
+            TextureViewState::Video(state) => state.poster().size(),
             TextureViewState::Loading(loading) => loading.size_2(),
+            TextureViewState::Unsupported(state) => state.size_2(),
             TextureViewState::Mutable(state) => state.lock().max_size(),
         }
     }
@@ -303,11 +359,36 @@ impl TextureViewState {
         // }
     }
 
+    /// Pause video playback, used when the page isn't shown anymore.
+    pub fn pause_video(&mut self) {
+        match self {
+            #[cfg(feature = "video")]
+            TextureViewState::Video(state) => state.pause(),
+            TextureViewState::Mutable(state) => state.lock().pause_video(),
+            _ => {}
+        }
+    }
+
+    /// Call `f` with the animation state, returns `None` if it's not animated.
+    pub fn with_animation<R>(
+        &mut self,
+        f: impl FnOnce(&mut AnimatedTextureViewState) -> R,
+    ) -> Option<R> {
+        match self {
+            TextureViewState::AnimatedTexture(state) => Some(f(state)),
+            TextureViewState::Mutable(state) => state.lock().with_animation(f),
+            _ => None,
+        }
+    }
+
     pub fn name(&self) -> String {
         match self {
             TextureViewState::StaticTexture(state) => state.handle.name.clone(),
-            TextureViewState::AnimatedTexture(state) => state.handles.name.clone(),
+            TextureViewState::AnimatedTexture(state) => state.name().clone(),
+            #[cfg(feature = "video")]
+            TextureViewState::Video(state) => state.name().clone(),
             TextureViewState::Loading(state) => state.name.clone(),
+            TextureViewState::Unsupported(state) => state.name.clone(),
             TextureViewState::Mutable(state) => state.lock().name(),
             //
         }
@@ -320,6 +401,9 @@ impl From<TextureHandle> for TextureViewState {
             TextureHandle::AnimatedTexture(handles) => {
                 Self::AnimatedTexture(AnimatedTextureViewState::new(handles))
             }
+            TextureHandle::StreamedTexture(handle) => {
+                Self::AnimatedTexture(AnimatedTextureViewState::from_stream(handle))
+            }
             TextureHandle::StaticTexture(handle) => {
                 Self::StaticTexture(StaticTextureViewState { handle })
             }
@@ -331,26 +415,236 @@ pub struct StaticTextureViewState {
     pub handle: SplittedTextureHandle,
 }
 
+enum AnimationFrames {
+    Collected(AnimatedTextureHandle),
+    Stream(Box<StreamPlayer>),
+}
+
 pub struct AnimatedTextureViewState {
     index: usize,
-    handles: AnimatedTextureHandle,
-    should_next: Arc<AtomicBool>,
+    frames: AnimationFrames,
+    // when current frame started to be shown, adjusted by speed.
+    frame_start: Option<Instant>,
+    paused: bool,
+    speed: f32,
+    // number of finished play.
+    played: u32,
 }
 
 impl AnimatedTextureViewState {
+    pub const MIN_SPEED: f32 = 0.25;
+    pub const MAX_SPEED: f32 = 4.0;
+
+    // browser show frame without delay with this delay instead.
+    const DEFAULT_DELAY: Duration = Duration::from_millis(100);
+    // wait for streamed frame being decoded.
+    const STREAM_POLL: Duration = Duration::from_millis(10);
+
     pub fn new(handles: AnimatedTextureHandle) -> Self {
+        Self::with_frames(AnimationFrames::Collected(handles))
+    }
+
+    pub fn from_stream(handle: StreamedTextureHandle) -> Self {
+        Self::with_frames(AnimationFrames::Stream(Box::new(StreamPlayer::new(handle))))
+    }
+
+    fn with_frames(frames: AnimationFrames) -> Self {
         Self {
             index: 0,
-            handles,
-            should_next: Arc::new(false.into()),
+            frames,
+            frame_start: None,
+            paused: false,
+            speed: 1.0,
+            played: 0,
         }
     }
-    pub fn next_index(&mut self) {
-        self.index = (self.index + 1) % (self.handles.items().len() - 1).max(1);
+
+    /// Move to the next frame, returns `false` if streamed frame isn't decoded yet.
+    pub fn next_index(&mut self) -> bool {
+        let handles = match &mut self.frames {
+            AnimationFrames::Collected(handles) => handles,
+            // decoder stops after the last repeat, so the stream ends with it.
+            AnimationFrames::Stream(player) => return player.advance(),
+        };
+
+        if self.index + 1 < handles.items().len() {
+            self.index += 1;
+            return true;
+        }
+
+        self.played = self.played.saturating_add(1);
+        if !self.is_finished() {
+            self.index = 0;
+        }
+
+        true
     }
 
     pub fn frame(&self) -> &TextureFrameHandle {
-        &self.handles.items()[self.index]
+        match &self.frames {
+            AnimationFrames::Collected(handles) => &handles.items()[self.index],
+            AnimationFrames::Stream(player) => player.frame(),
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        match &self.frames {
+            AnimationFrames::Collected(_) => self.index,
+            AnimationFrames::Stream(player) => player.index(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.frames {
+            AnimationFrames::Collected(handles) => handles.items().len(),
+            AnimationFrames::Stream(player) => player.handle().stream().frame_count(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the frames are decoded while it's played.
+    pub fn is_streamed(&self) -> bool {
+        matches!(self.frames, AnimationFrames::Stream(_))
+    }
+
+    pub fn name(&self) -> &String {
+        match &self.frames {
+            AnimationFrames::Collected(handles) => &handles.name,
+            AnimationFrames::Stream(player) => player.handle().name(),
+        }
+    }
+
+    pub fn max_size(&self) -> [usize; 2] {
+        match &self.frames {
+            AnimationFrames::Collected(handles) => handles.max_size(),
+            AnimationFrames::Stream(player) => player.handle().first().handle().size(),
+        }
+    }
+
+    /// Returns `true` if the animation is played as many as its repeat setting.
+    pub fn is_finished(&self) -> bool {
+        let repeat = match &self.frames {
+            AnimationFrames::Collected(handles) => handles.repeat(),
+            AnimationFrames::Stream(player) => return player.is_ended(),
+        };
+
+        match repeat {
+            Repeat::Infinite => false,
+            Repeat::Finite(n) => self.played >= n,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+        self.frame_start = None;
+    }
+
+    /// Resume playing, restart from the first frame if it's already finished.
+    pub fn resume(&mut self) {
+        if self.is_finished() {
+            self.played = 0;
+            self.index = 0;
+
+            if let AnimationFrames::Stream(player) = &mut self.frames {
+                player.restart();
+            }
+        }
+
+        self.paused = false;
+        self.frame_start = None;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        if self.paused || self.is_finished() {
+            self.resume();
+        } else {
+            self.pause();
+        }
+    }
+
+    /// Pause and move to the frame `step` away, wrapping around.
+    ///
+    /// Streamed animation can only step forward to the frames already decoded, stepping
+    /// backward is ignored since the frames before the current one aren't kept.
+    pub fn step(&mut self, step: isize) {
+        self.pause();
+
+        if let AnimationFrames::Stream(player) = &mut self.frames {
+            for _ in 0..step.max(0) {
+                if !player.advance() {
+                    break;
+                }
+            }
+            return;
+        }
+
+        let len = self.len() as isize;
+        self.index = (self.index as isize + step).rem_euclid(len.max(1)) as usize;
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.clamp(Self::MIN_SPEED, Self::MAX_SPEED);
+    }
+
+    /// Multiply speed by 2 for positive `step` and divide it by 2 for negative `step`.
+    pub fn change_speed_by(&mut self, step: i8) {
+        self.set_speed(self.speed * 2f32.powi(step as i32));
+    }
+
+    fn delay(&self) -> Duration {
+        let delay = match self.frame().delay() {
+            Duration::ZERO => Self::DEFAULT_DELAY,
+            delay => delay,
+        };
+
+        delay.div_f32(self.speed)
+    }
+
+    /// Advance frame by elapsed time, returns time until the next frame.
+    pub fn update(&mut self, now: Instant) -> Option<Duration> {
+        if self.paused || self.is_finished() || self.len() < 2 {
+            return None;
+        }
+
+        let mut frame_start = *self.frame_start.get_or_insert(now);
+
+        // skip at most one loop of frames when it wasn't shown for a while.
+        for _ in 0..self.len() {
+            let delay = self.delay();
+            if now.duration_since(frame_start) < delay {
+                break;
+            }
+
+            if !self.next_index() {
+                self.frame_start = Some(frame_start);
+                return Some(Self::STREAM_POLL);
+            }
+            frame_start += delay;
+
+            if self.is_finished() {
+                self.frame_start = None;
+                return None;
+            }
+        }
+
+        // still behind after a loop of frames, show current frame from now.
+        if now.duration_since(frame_start) >= self.delay() {
+            frame_start = now;
+        }
+        self.frame_start = Some(frame_start);
+
+        Some(self.delay().saturating_sub(now.duration_since(frame_start)))
     }
 }
 
@@ -380,6 +674,31 @@ impl LoadingTexture {
     // }
 }
 
+/// Page which can't be shown, e.g. a PDF page with vector content.
+pub struct UnsupportedTexture {
+    name: String,
+    size: egui::Vec2,
+    reason: String,
+}
+
+impl UnsupportedTexture {
+    pub fn new(name: String, size: egui::Vec2, reason: String) -> Self {
+        Self { name, size, reason }
+    }
+
+    pub fn name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn reason(&self) -> &String {
+        &self.reason
+    }
+
+    pub fn size_2(&self) -> [usize; 2] {
+        [self.size.x as usize, self.size.y as usize]
+    }
+}
+
 pub struct TextureView<'a> {
     state: &'a mut TextureViewState,
 }
@@ -411,20 +730,22 @@ impl<'a> TextureView<'a> {
         match state {
             TextureViewState::StaticTexture(state) => show_image(ui, &state.handle).show(ui),
             TextureViewState::AnimatedTexture(state) => {
-                if state.should_next.load(Ordering::Relaxed) {
-                    state.next_index();
-                    state.should_next.store(false, Ordering::Relaxed);
-                }
-
+                let next = state.update(Instant::now());
                 let response = show_image(ui, state.frame().handle()).show(ui);
 
-                if ui.is_rect_visible(response.rect) {
-                    ui.ctx().request_repaint_after(state.frame().delay());
+                if let (true, Some(next)) = (ui.is_rect_visible(response.rect), next) {
+                    ui.ctx().request_repaint_after(next);
                 };
 
                 response
             }
 
+            #[cfg(feature = "video")]
+            TextureViewState::Video(state) => {
+                let size = show_image(ui, state.poster()).size;
+                state.show(ui, size)
+            }
+
             TextureViewState::Loading(loading) => {
                 let size = [
                     ui.available_width().max(loading.size_2()[0] as f32),
@@ -438,6 +759,23 @@ impl<'a> TextureView<'a> {
                 })
                 .response
             }
+            TextureViewState::Unsupported(unsupported) => {
+                let size = [
+                    ui.available_width().max(unsupported.size.x),
+                    unsupported.size.y,
+                ];
+                ui.allocate_ui(size.into(), |ui| {
+                    ui.centered_and_justified(|ui| {
+                        let text = format!(
+                            "Unsupported page\n{}\n{}",
+                            unsupported.name(),
+                            unsupported.reason()
+                        );
+                        ui.add(egui::Label::new(egui::RichText::new(text).weak()));
+                    })
+                })
+                .response
+            }
             TextureViewState::Mutable(state) => Self::show_state(&mut state.lock(), ui, show_image),
         }
     }
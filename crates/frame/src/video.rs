@@ -0,0 +1,239 @@
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    sync::{Arc, Weak},
+};
+
+use eframe::egui;
+use ffmpeg_the_third as ffmpeg;
+
+use crate::{format::decoding_error, SplittedTextureHandle, SplittedTextureWidget};
+
+/// Extension of file played as video.
+pub const VIDEO_EXTENSIONS: &[&str] = &["mp4", "webm", "mkv"];
+
+/// Returns `true` if `path` has extension in [`VIDEO_EXTENSIONS`].
+pub fn is_video(path: impl AsRef<Path>) -> bool {
+    path.as_ref()
+        .extension()
+        .and_then(|it| it.to_str())
+        .map(|it| {
+            VIDEO_EXTENSIONS
+                .iter()
+                .any(|ext| it.eq_ignore_ascii_case(ext))
+        })
+        .unwrap_or(false)
+}
+
+/// Decode a frame at tenth of the video duration, used as thumbnail.
+pub fn load_video_frame(path: impl AsRef<Path>) -> image::ImageResult<image::DynamicImage> {
+    let error = |err: ffmpeg::Error| decoding_error("video")(err);
+
+    ffmpeg::init().map_err(error)?;
+    let mut input = ffmpeg::format::input(&path.as_ref()).map_err(error)?;
+
+    let stream = input
+        .streams()
+        .best(ffmpeg::media::Type::Video)
+        .ok_or(ffmpeg::Error::StreamNotFound)
+        .map_err(error)?;
+    let index = stream.index();
+
+    let mut decoder = ffmpeg::codec::context::Context::from_parameters(stream.parameters())
+        .and_then(|it| it.decoder().video())
+        .map_err(error)?;
+
+    let (width, height) = (decoder.width(), decoder.height());
+    let mut scaler = ffmpeg::software::scaling::Context::get(
+        decoder.format(),
+        width,
+        height,
+        ffmpeg::format::Pixel::RGBA,
+        width,
+        height,
+        ffmpeg::software::scaling::Flags::BILINEAR,
+    )
+    .map_err(error)?;
+
+    // the first frame is often blank, seeking may fail on some container.
+    let position = input.duration().max(0) / 10;
+    let _ = input.seek(position, ..position);
+
+    let mut frame = ffmpeg::frame::Video::empty();
+    let mut decoded = false;
+    for (stream, packet) in input.packets() {
+        if stream.index() != index {
+            continue;
+        }
+
+        decoder.send_packet(&packet).map_err(error)?;
+        if decoder.receive_frame(&mut frame).is_ok() {
+            decoded = true;
+            break;
+        }
+    }
+
+    if !decoded {
+        decoder.send_eof().map_err(error)?;
+        decoder.receive_frame(&mut frame).map_err(error)?;
+    }
+
+    let mut rgba = ffmpeg::frame::Video::empty();
+    scaler.run(&frame, &mut rgba).map_err(error)?;
+
+    let row_size = width as usize * 4;
+    let mut buffer = Vec::with_capacity(row_size * height as usize);
+    for row in rgba.data(0).chunks(rgba.stride(0)).take(height as usize) {
+        buffer.extend_from_slice(&row[..row_size]);
+    }
+
+    image::RgbaImage::from_raw(width, height, buffer)
+        .map(image::DynamicImage::ImageRgba8)
+        .ok_or_else(|| decoding_error("video")("invalid frame size"))
+}
+
+thread_local! {
+    // audio device shared by every player, opened with the first player.
+    static AUDIO_DEVICE: RefCell<Option<egui_video::AudioDevice>> = const { RefCell::new(None) };
+
+    // players are owned by the ui thread, keyed by the token of the state playing it.
+    // a player is dropped in the ui thread after its state is dropped.
+    static PLAYERS: RefCell<Vec<(Weak<()>, egui_video::Player)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Drop players whose [`VideoTextureViewState`] is dropped, must be called from the ui thread.
+pub fn drop_unused_players() {
+    PLAYERS.with_borrow_mut(|players| players.retain(|(token, _)| token.strong_count() > 0));
+}
+
+pub struct VideoTextureViewState {
+    path: PathBuf,
+    poster: SplittedTextureHandle,
+    token: Arc<()>,
+    error: Option<String>,
+}
+
+impl VideoTextureViewState {
+    /// Video at `path` showing `poster` until it's played.
+    pub fn new(path: PathBuf, poster: SplittedTextureHandle) -> Self {
+        Self {
+            path,
+            poster,
+            token: Arc::new(()),
+            error: None,
+        }
+    }
+
+    pub fn name(&self) -> &String {
+        self.poster.name()
+    }
+
+    pub fn poster(&self) -> &SplittedTextureHandle {
+        &self.poster
+    }
+
+    /// Call `f` with the player of this state, returns `None` if it isn't started
+    /// or when called outside the ui thread.
+    fn with_player<R>(&self, f: impl FnOnce(&mut egui_video::Player) -> R) -> Option<R> {
+        let token = Arc::downgrade(&self.token);
+        PLAYERS.with_borrow_mut(|players| {
+            players
+                .iter_mut()
+                .find(|(it, _)| it.ptr_eq(&token))
+                .map(|(_, player)| f(player))
+        })
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.with_player(|player| player.player_state.get() == egui_video::PlayerState::Playing)
+            .unwrap_or(false)
+    }
+
+    pub fn pause(&mut self) {
+        self.with_player(|player| {
+            if player.player_state.get() == egui_video::PlayerState::Playing {
+                player.pause();
+            }
+        });
+    }
+
+    fn start(&mut self, ctx: &egui::Context) {
+        if self.with_player(|_| ()).is_some() {
+            return;
+        }
+
+        let path = self.path.to_string_lossy().to_string();
+        let player = AUDIO_DEVICE.with_borrow_mut(|device| {
+            if device.is_none() {
+                *device = egui_video::AudioDevice::new()
+                    .map_err(|err| tracing::error!("can't open audio device: {}", err))
+                    .ok();
+            }
+
+            let player = egui_video::Player::new(ctx, &path)?;
+            match device {
+                Some(device) => player.with_audio(device),
+                None => Ok(player),
+            }
+        });
+
+        match player {
+            Ok(mut player) => {
+                player.start();
+                let token = Arc::downgrade(&self.token);
+                PLAYERS.with_borrow_mut(|players| players.push((token, player)));
+                self.error = None;
+            }
+            Err(err) => {
+                tracing::error!("can't play {}: {}", self.name(), err);
+                self.error = Some(err.to_string());
+            }
+        }
+    }
+
+    /// Show the poster with play button, then the player with its play, pause, seek and
+    /// volume control after it's clicked.
+    pub fn show(&mut self, ui: &mut egui::Ui, size: egui::Vec2) -> egui::Response {
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+
+        if !ui.is_rect_visible(rect) {
+            self.pause();
+            return response;
+        }
+
+        let shown = self.with_player(|player| {
+            ui.allocate_ui_at_rect(rect, |ui| player.ui(ui, size.into()));
+        });
+        if shown.is_some() {
+            return response;
+        }
+
+        SplittedTextureWidget::new(&self.poster, size).paint_at(ui, rect);
+
+        let (text, font) = match &self.error {
+            Some(error) => (
+                format!("Can't play {}: {}", self.name(), error),
+                egui::TextStyle::Body.resolve(ui.style()),
+            ),
+            None => (
+                "▶".to_string(),
+                egui::FontId::proportional((size.min_elem() / 6.0).clamp(14.0, 64.0)),
+            ),
+        };
+        ui.painter().text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            text,
+            font,
+            ui.visuals().strong_text_color(),
+        );
+
+        // separate from the returned response so clicking play doesn't change the page.
+        let play = ui.interact(rect, ui.id().with(&self.path), egui::Sense::click());
+        if play.clicked() {
+            self.start(ui.ctx());
+        }
+
+        response
+    }
+}
@@ -7,11 +7,19 @@ use path_absolutize::Absolutize;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    inspection::DebugUI, AppExplorer, AppExplorerOnOpen, AppExplorerSetting, AppExplorerView,
-    AppReader, AppReaderSetting, AppReaderView,
+    inspection::DebugUI, AppExplorer, AppExplorerAction, AppExplorerOnOpen, AppExplorerSetting,
+    AppExplorerView, AppReader, AppReaderSetting, AppReaderView,
 };
-use fmr_core::path::{path_serde, PathSorterSetting};
-use fmr_explorer::{ExplorerLoaderCache, PathExplorerItem};
+use fmr_core::{
+    exclude::ExcludeRules,
+    password::Passwords,
+    path::{copy_recursive, move_path, path_serde, PathSortKey, PathSorterSetting},
+};
+use fmr_egui::tools::{
+    archive_dir::{ArchiveDir, ArchivePath},
+    zip::NameEncoding,
+};
+use fmr_explorer::{DetailsColumn, ExplorerLayout, ExplorerLoaderCache, PathExplorerItem};
 use fmr_frame::FilterType;
 use fmr_reader::{ReaderMode, ReaderModeState};
 
@@ -23,6 +31,12 @@ pub struct AppSetting {
     reader: AppReaderSetting,
     explorer: AppExplorerSetting,
     path_sorter: PathSorterSetting,
+    // path ordering with the details sort of explorer applied.
+    #[serde(skip)]
+    explorer_sorter: PathSorterSetting,
+    zip_name_encoding: NameEncoding,
+    /// junk entries excluded from books and covers.
+    exclude: ExcludeRules,
 }
 
 pub struct App {
@@ -30,6 +44,8 @@ pub struct App {
     context: egui::Context, // context used to request repaint
     setting: AppSetting,
     reading_progress: crate::ReadingProgress,
+    passwords: Passwords,
+    password_prompt: PasswordPrompt,
     setting_storage: Option<crate::storage::FSStorage>,
     cache_storage: Option<crate::storage::FSStorage>,
     data_storage: Option<crate::storage::FSStorage>,
@@ -38,6 +54,46 @@ pub struct App {
     #[allow(dead_code)]
     tokio_runtime: tokio::runtime::Runtime,
     debug_ui: DebugUI,
+    // file being checked whether it's browsed or read, see `App::open`.
+    opening: Option<PendingOpen>,
+    // files being moved or copied, see `App::apply_explorer_action`.
+    file_operation: Option<PendingFileOperation>,
+    // errors and notices shown until they're dismissed.
+    messages: Vec<String>,
+}
+
+impl AppSetting {
+    // order explorer by the path ordering with the column clicked in details header.
+    fn sync_explorer_sorter(&mut self) {
+        let mut inner = self.path_sorter.inner().clone();
+        if let Some((column, ascending)) = self.explorer.details_sort {
+            inner.ascending = ascending;
+            inner.last_modified = column == DetailsColumn::Modified;
+            inner.key = match column {
+                DetailsColumn::Name | DetailsColumn::Modified => PathSortKey::Name,
+                DetailsColumn::Size => PathSortKey::Size,
+                DetailsColumn::PageCount => PathSortKey::PageCount,
+                DetailsColumn::Progress => PathSortKey::Progress,
+            };
+        }
+
+        self.explorer_sorter.set_inner(inner);
+        self.explorer_sorter.sync();
+    }
+}
+
+struct PendingOpen {
+    path: PathBuf,
+    select_entry: Option<PathBuf>,
+    // `None` if it isn't a file, otherwise whether it's browsed in explorer.
+    is_browsable: tokio::sync::oneshot::Receiver<Option<bool>>,
+}
+
+struct PendingFileOperation {
+    action: AppExplorerAction,
+    // explorer the files are moved from.
+    path: PathBuf,
+    errors: tokio::sync::oneshot::Receiver<Vec<String>>,
 }
 
 impl Drop for App {
@@ -54,6 +110,9 @@ pub struct AppCache {
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct AppData {
     pub reading_progress: crate::ReadingProgress,
+    /// passwords of encrypted archives saved by the user.
+    #[serde(default)]
+    pub passwords: Passwords,
 }
 
 pub enum AppMode {
@@ -100,17 +159,29 @@ impl App {
         };
 
         setting.explorer.cache = cache.explorer_cache;
+
+        let passwords = data.passwords;
+        let ctx = context.egui_ctx.clone();
+        passwords.set_notify(move || ctx.request_repaint());
+        setting.reader.passwords = passwords.clone();
+        setting.explorer.passwords = passwords.clone();
+        let sort_info = std::sync::Arc::new(crate::SortInfo::new(data.reading_progress.clone()));
+        setting.sync_explorer_sorter();
+        setting.explorer.sorter.0 = setting.explorer_sorter.subscribe_with(sort_info);
+
+        // next and previous folder of reader are found by name, since ordering by key reads
+        // the file system and progress or random order changes while reading.
         let sorter = setting.path_sorter.subscribe();
         setting
-            .explorer
-            .sorter
-            .replace(move |a, b| sorter.compare_path(a, b));
+            .reader
+            .folder_sorter
+            .replace(move |a, b| sorter.compare_path_name(a, b));
 
         let sorter = setting.path_sorter.subscribe();
         setting
             .reader
-            .folder_sorter
-            .replace(move |a, b| sorter.compare_path(a, b));
+            .page_sorter
+            .replace(move |a, b| sorter.compare_name(a, b));
 
         if let Some(s) = context.storage {
             if let Some(s) = s.get_string("style") {
@@ -125,6 +196,8 @@ impl App {
             mode: Default::default(),
             setting,
             reading_progress: data.reading_progress,
+            passwords,
+            password_prompt: Default::default(),
             setting_storage,
             cache_storage,
             data_storage,
@@ -133,6 +206,9 @@ impl App {
             // reader_option,
             debug_ui: Default::default(),
             tokio_runtime,
+            opening: None,
+            file_operation: None,
+            messages: vec![],
         }
     }
 
@@ -154,29 +230,189 @@ impl App {
     }
 
     pub fn open(&mut self, path: &Path, select_entry: Option<PathBuf>) {
-        if path.exists() {
-            if path.is_file() {
-                self.open_reader(path.to_path_buf());
-            } else if path.is_dir() {
-                self.open_explorer(path.to_path_buf(), select_entry);
-            }
+        if path.is_dir() {
+            self.opening = None;
+            self.open_explorer(path.to_path_buf(), select_entry);
+            return;
+        }
+
+        // archive with subdirectories or nested archives is browsed like a directory,
+        // it's checked in background since the archive is opened and listed.
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        let probe = path.to_path_buf();
+        let exclude = self.setting.exclude.clone();
+        let encoding = self.setting.zip_name_encoding;
+        let ctx = self.context();
+        tokio::task::spawn_blocking(move || {
+            let is_file = probe.is_file() || ArchivePath::is_virtual(&probe);
+            let is_browsable = is_file.then(|| {
+                ArchiveDir::open_path(&probe, encoding)
+                    .map(|it| it.is_browsable(&exclude))
+                    .unwrap_or(false)
+            });
+
+            sender.send(is_browsable).ok();
+            ctx.request_repaint();
+        });
+
+        self.opening = Some(PendingOpen {
+            path: path.to_path_buf(),
+            select_entry,
+            is_browsable: receiver,
+        });
+    }
+
+    // open file checked by `App::open` after the check is done.
+    fn poll_opening(&mut self) {
+        let Some(opening) = &mut self.opening else {
+            return;
+        };
+
+        let is_browsable = match opening.is_browsable.try_recv() {
+            Ok(it) => it,
+            Err(tokio::sync::oneshot::error::TryRecvError::Empty) => return,
+            Err(tokio::sync::oneshot::error::TryRecvError::Closed) => None,
+        };
+
+        let Some(PendingOpen {
+            path, select_entry, ..
+        }) = self.opening.take()
+        else {
+            return;
+        };
+
+        match is_browsable {
+            Some(true) => self.open_explorer(path, select_entry),
+            Some(false) => self.open_reader(path),
+            None => {}
         }
     }
 
     pub fn open_reader(&mut self, path: PathBuf) {
-        let reader = AppReader::new(
-            path,
-            self.setting.reader.clone(),
-            self.reading_progress.clone(),
-            self.context(),
-        );
+        let mut setting = self.setting.reader.clone();
+        setting.exclude.clone_from(&self.setting.exclude);
+        setting.zip_name_encoding = self.setting.zip_name_encoding;
+
+        let reader = AppReader::new(path, setting, self.reading_progress.clone(), self.context());
         self.mode = Some(AppMode::Reader(reader));
     }
 
+    /// Read books at `paths` as one book, see [`AppReader::new_combined`]. PDF and text
+    /// book can't be part of it, they're skipped with a message.
+    pub fn open_reader_combined(&mut self, mut paths: Vec<PathBuf>) {
+        let mut setting = self.setting.reader.clone();
+        setting.exclude.clone_from(&self.setting.exclude);
+        setting.zip_name_encoding = self.setting.zip_name_encoding;
+
+        paths.retain(|it| {
+            let is_pdf = it
+                .extension()
+                .is_some_and(|it| it.eq_ignore_ascii_case("pdf"));
+            let is_supported = !is_pdf && !fmr_reader::loader::is_text(it);
+            if !is_supported {
+                let name = it.file_name().unwrap_or_default().to_string_lossy();
+                self.messages.push(format!(
+                    "{name} is skipped, PDF and text can't be read as one book."
+                ));
+            }
+
+            is_supported
+        });
+
+        if let Some(reader) = AppReader::new_combined(paths, setting, self.context()) {
+            self.mode = Some(AppMode::Reader(reader));
+        }
+    }
+
+    /// Apply `action` to `paths` selected in explorer opened in `path`, returns true if
+    /// it's applied.
+    pub fn apply_explorer_action(
+        &mut self,
+        path: &Path,
+        action: AppExplorerAction,
+        paths: Vec<PathBuf>,
+    ) -> bool {
+        match action {
+            AppExplorerAction::MarkRead => paths
+                .iter()
+                .for_each(|it| self.reading_progress.mark_finished(it)),
+            AppExplorerAction::MarkUnread => {
+                paths.iter().for_each(|it| self.reading_progress.remove(it))
+            }
+            AppExplorerAction::ClearThumbnail => {
+                for it in &paths {
+                    self.setting.explorer.cache.remove_path(it);
+                }
+            }
+            AppExplorerAction::MoveTo | AppExplorerAction::CopyTo => {
+                if self.file_operation.is_some() {
+                    let message = "Wait until the files being moved or copied are done.";
+                    self.messages.push(message.to_string());
+                    return false;
+                }
+
+                let Some(target) = rfd::FileDialog::new().set_directory(path).pick_folder() else {
+                    return false;
+                };
+
+                // copying may take a while, errors are shown once it's done.
+                let (sender, receiver) = tokio::sync::oneshot::channel();
+                let ctx = self.context();
+                tokio::task::spawn_blocking(move || {
+                    let errors = paths
+                        .iter()
+                        .filter_map(|it| transfer(action, it, &target).err())
+                        .collect();
+
+                    sender.send(errors).ok();
+                    ctx.request_repaint();
+                });
+
+                self.file_operation = Some(PendingFileOperation {
+                    action,
+                    path: path.to_path_buf(),
+                    errors: receiver,
+                });
+            }
+            AppExplorerAction::OpenCombined => self.open_reader_combined(paths),
+        }
+
+        true
+    }
+
+    // show errors of files moved or copied after it's done.
+    fn poll_file_operation(&mut self) {
+        let Some(operation) = &mut self.file_operation else {
+            return;
+        };
+
+        let errors = match operation.errors.try_recv() {
+            Ok(errors) => errors,
+            Err(tokio::sync::oneshot::error::TryRecvError::Empty) => return,
+            Err(tokio::sync::oneshot::error::TryRecvError::Closed) => vec![],
+        };
+
+        let Some(PendingFileOperation { action, path, .. }) = self.file_operation.take() else {
+            return;
+        };
+        self.messages.extend(errors);
+
+        // show the moved items are gone.
+        let is_shown = matches!(&self.mode, Some(AppMode::Explorer(it)) if *it.path() == path);
+        if action == AppExplorerAction::MoveTo && is_shown {
+            self.open_explorer(path, None);
+        }
+    }
+
     pub fn open_explorer(&mut self, path: PathBuf, select_entry: Option<PathBuf>) {
+        let mut setting = self.setting.explorer.clone();
+        setting.exclude.clone_from(&self.setting.exclude);
+        setting.zip_name_encoding = self.setting.zip_name_encoding;
+
         let explorer = AppExplorer::new(
             path,
-            self.setting.explorer.clone(),
+            setting,
+            self.reading_progress.clone(),
             select_entry,
             self.context(),
         );
@@ -223,12 +459,11 @@ impl App {
             if let Some(AppMode::Explorer(explorer)) = &self.mode {
                 if *key == egui::Key::F5 {
                     if modifiers.is_none() {
-                        let Some(path) = explorer.selected_path() else {
-                            return false;
-                        };
-
-                        self.setting.explorer.cache.remove_path(&path);
-                        return true;
+                        let paths = explorer.selected_paths();
+                        for it in &paths {
+                            self.setting.explorer.cache.remove_path(it);
+                        }
+                        return !paths.is_empty();
                     } else if modifiers.shift_only() {
                         for it in explorer.all_child_paths() {
                             self.setting.explorer.cache.remove_path(&it);
@@ -262,6 +497,117 @@ impl App {
     }
 }
 
+// move or copy `from` into `target` directory, error is the message shown to the user.
+fn transfer(action: AppExplorerAction, from: &Path, target: &Path) -> Result<(), String> {
+    let name = from.file_name().unwrap_or_default();
+    let to = target.join(name);
+    let name = name.to_string_lossy();
+    if to.exists() {
+        return Err(format!(
+            "{name} isn't replaced, it already exists in the target."
+        ));
+    }
+
+    let result = match action {
+        AppExplorerAction::MoveTo => move_path(from, &to),
+        _ => copy_recursive(from, &to),
+    };
+
+    result.map_err(|err| {
+        log::error!("failed to {:?} {:?} to {:?}: {}", action, from, to, err);
+        match action {
+            AppExplorerAction::MoveTo => format!("Failed to move {name}: {err}"),
+            _ => format!("Failed to copy {name}: {err}"),
+        }
+    })
+}
+
+/// Input of the prompt asking password of encrypted archive.
+#[derive(Default)]
+struct PasswordPrompt {
+    password: String,
+    save: bool,
+}
+
+impl App {
+    fn show_password_prompt(&mut self, ctx: &egui::Context) {
+        let Some(pending) = self.passwords.pending() else {
+            return;
+        };
+
+        let name = pending.archive.file_name().unwrap_or_default();
+        let mut answer = None;
+
+        egui::Window::new("Password Required")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                let prompt = &mut self.password_prompt;
+                ui.label(format!("{} is encrypted.", name.to_string_lossy()));
+                if pending.is_retry {
+                    ui.colored_label(ui.visuals().error_fg_color, "Wrong password.");
+                }
+
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut prompt.password)
+                        .password(true)
+                        .hint_text("Password"),
+                );
+                response.request_focus();
+                let is_entered =
+                    response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+
+                ui.checkbox(
+                    &mut prompt.save,
+                    "Remember for this archive (stored unencrypted in the profile)",
+                );
+
+                ui.horizontal(|ui| {
+                    if ui.button("Open").clicked() || is_entered {
+                        answer = Some(Some(std::mem::take(&mut prompt.password)));
+                    }
+
+                    let is_escaped = ui.input(|i| i.key_pressed(egui::Key::Escape));
+                    if ui.button("Cancel").clicked() || is_escaped {
+                        answer = Some(None);
+                    }
+                });
+            });
+
+        if let Some(password) = answer {
+            let save = std::mem::take(&mut self.password_prompt.save);
+            self.password_prompt.password.clear();
+            self.passwords.answer(&pending.archive, password, save);
+        }
+    }
+}
+
+impl App {
+    fn show_messages(&mut self, ctx: &egui::Context) {
+        if self.messages.is_empty() {
+            return;
+        }
+
+        let mut is_dismissed = false;
+        egui::Window::new("Message")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                for it in &self.messages {
+                    ui.label(it);
+                }
+
+                is_dismissed = ui.button("OK").clicked();
+            });
+
+        if is_dismissed {
+            self.messages.clear();
+        }
+    }
+}
+
 pub struct AppOpenParentSetting {
     canonicalize_path: bool,
 }
@@ -287,6 +633,7 @@ impl eframe::App for App {
         if let Some(storage) = self.data_storage.as_mut() {
             let data = AppData {
                 reading_progress: self.reading_progress.clone(),
+                passwords: self.passwords.clone(),
             };
 
             storage
@@ -301,6 +648,15 @@ impl eframe::App for App {
     }
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        #[cfg(feature = "video")]
+        fmr_frame::drop_unused_players();
+
+        self.poll_opening();
+        self.poll_file_operation();
+
+        // input goes to the password prompt while it's shown.
+        let is_prompting = self.passwords.pending().is_some();
+
         egui::TopBottomPanel::top("top-panel").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 ui.menu_button("File", |ui| {
@@ -320,6 +676,11 @@ impl eframe::App for App {
                         ui.label("Set minimum column width of each items in explorer");
                     });
 
+                    ui.checkbox(&mut setting.hide_finished, "Hide Finished")
+                        .on_hover_ui(|ui| {
+                            ui.label("Hide books and folders which are read to the end");
+                        });
+
                     let setting = &mut app_setting.loader;
 
                     let setting = &mut setting.entry_setting;
@@ -336,16 +697,90 @@ impl eframe::App for App {
 
                     ui.menu_button("Path Ordering", |ui| {
                         let setting = &mut self.setting.path_sorter;
+                        let inner = setting.inner();
+                        let sort_order = (inner.key, inner.ascending, inner.last_modified);
 
                         ui.checkbox(setting.natural_mut(), "Natural");
+                        let natural = *setting.natural_mut();
+                        ui.add_enabled(
+                            natural,
+                            egui::Checkbox::new(setting.chapter_mut(), "Chapter Aware"),
+                        )
+                        .on_hover_text(
+                            "Order by volume, chapter and part number, e.g. Vol.2 Ch.10.5",
+                        );
                         ui.checkbox(setting.ascending_mut(), "Ascending");
                         ui.checkbox(setting.last_modified_mut(), "Last Modified");
+                        ui.checkbox(setting.directories_first_mut(), "Directories First");
+
+                        ui.separator();
+                        ui.label("Sort By");
+                        for it in PathSortKey::ALL {
+                            ui.radio_value(setting.key_mut(), it, it.label());
+                        }
+
+                        if *setting.key_mut() == PathSortKey::Random
+                            && ui.button("Shuffle").clicked()
+                        {
+                            setting.reshuffle();
+                        }
 
                         setting.sync();
+
+                        // order chosen here replaces the one clicked in details header.
+                        let inner = setting.inner();
+                        let order = (inner.key, inner.ascending, inner.last_modified);
+                        if order != sort_order {
+                            self.setting.explorer.details_sort = None;
+                        }
+                        self.setting.sync_explorer_sorter();
+                    });
+
+                    ui.menu_button("Zip Name Encoding", |ui| {
+                        let encoding = &mut self.setting.zip_name_encoding;
+                        for it in NameEncoding::ALL {
+                            ui.radio_value(encoding, it, it.label());
+                        }
+                    })
+                    .response
+                    .on_hover_ui(|ui| {
+                        ui.label("Tried first for names that aren't UTF-8, applied when reopened");
+                    });
+
+                    ui.menu_button("Exclude", |ui| {
+                        let rules = &mut self.setting.exclude;
+                        ui.checkbox(&mut rules.builtin, "Exclude System Files")
+                            .on_hover_ui(|ui| {
+                                ui.label(fmr_core::exclude::BUILTIN_PATTERNS.join("\n"));
+                            });
+
+                        ui.add(
+                            DragValue::new(&mut rules.min_dimension)
+                                .prefix("Minimum Dimension: ")
+                                .range(0..=u32::MAX),
+                        )
+                        .on_hover_ui(|ui| {
+                            ui.label("Exclude image smaller than this, 0 to disable");
+                        });
+
+                        ui.label("Patterns:");
+                        let mut patterns = rules.patterns.join("\n");
+                        let response = ui.add(
+                            egui::TextEdit::multiline(&mut patterns)
+                                .hint_text("*credit*\nsubdir/**")
+                                .desired_rows(4),
+                        );
+                        if response.changed() {
+                            rules.patterns = patterns.split('\n').map(String::from).collect();
+                        }
+                    })
+                    .response
+                    .on_hover_ui(|ui| {
+                        ui.label("Applied to pages and covers when reopened");
                     });
 
                     ui.menu_button("Scale Filter", |ui| {
-                        let filter = &mut setting.filter;
+                        let filter = &mut self.setting.explorer.loader.entry_setting.filter;
                         ui.radio_value(filter, FilterType::Nearest, "Nearest");
                         ui.radio_value(filter, FilterType::Triangle, "Triangle");
                         ui.radio_value(filter, FilterType::CatmullRom, "CatmullRom");
@@ -362,6 +797,7 @@ impl eframe::App for App {
                         let mode = &mut setting.mode;
                         ui.radio_value(mode, ReaderMode::Paged, "Paged");
                         ui.radio_value(mode, ReaderMode::Vertical, "Vertical");
+                        ui.radio_value(mode, ReaderMode::Text, "Text");
                     });
 
                     ui.menu_button("Scale Option", |ui| {
@@ -392,6 +828,33 @@ impl eframe::App for App {
                         let option = &mut setting.paged;
                         ui.checkbox(&mut option.read_from_right, "Read From Right");
                     });
+                    ui.menu_button("Vertical Option", |ui| {
+                        let option = &mut setting.vertical;
+                        ui.checkbox(&mut option.continuous, "Continuous Across Folders")
+                            .on_hover_ui(|ui| {
+                                ui.label("Append next and prepend previous folder while scrolling");
+                            });
+                    });
+                    ui.menu_button("Text Option", |ui| {
+                        let option = &mut setting.text;
+                        ui.add(
+                            DragValue::new(&mut option.font_size)
+                                .prefix("Font Size: ")
+                                .range(8..=96),
+                        );
+
+                        let mut line_spacing = option.line_spacing as f64 / 100f64;
+                        ui.add(
+                            DragValue::new(&mut line_spacing)
+                                .prefix("Line Spacing: ")
+                                .speed(0.05)
+                                .range(1.0..=3.0)
+                                .max_decimals(2),
+                        );
+                        option.line_spacing = (line_spacing * 100.0).round() as u32;
+
+                        ui.checkbox(&mut option.paginate, "Paginate");
+                    });
 
                     ui.menu_button("Scroll", |ui| {
                         ui.checkbox(
@@ -421,11 +884,33 @@ impl eframe::App for App {
                                 .prefix("Preload Next Image: ")
                                 .range(usize::MIN..=usize::MAX),
                         );
+                        ui.add(
+                            DragValue::new(&mut app_setting.prefetch_next_folder)
+                                .prefix("Prefetch Next Folder at Pages Left: ")
+                                .range(usize::MIN..=usize::MAX),
+                        )
+                        .on_hover_ui(|ui| {
+                            ui.label("Start loading next folder near the end, 0 to disable");
+                        });
+                        ui.add(
+                            DragValue::new(&mut app_setting.prefetch_pages)
+                                .prefix("Prefetch Next Folder Image: ")
+                                .range(usize::MIN..=usize::MAX),
+                        );
+                        ui.add(
+                            DragValue::new(&mut app_setting.decode_permits)
+                                .prefix("Concurrent Decoding: ")
+                                .range(usize::MIN..=usize::MAX),
+                        )
+                        .on_hover_ui(|ui| {
+                            ui.label("Pages decoded at the same time, 0 to use number of CPU");
+                        });
                     });
 
                     if let Some(AppMode::Reader(reader)) = &self.mode {
                         let mut reader = reader.reader_mut();
                         let image_len = reader.images.len();
+                        let chapter_len = reader.text.len();
                         match &mut reader.state {
                             ReaderModeState::Vertical(_) => {
                                 ui.add_enabled(false, DragValue::new(&mut 0_u64).prefix("Pages: "));
@@ -445,6 +930,22 @@ impl eframe::App for App {
                                     state.reset(0);
                                 }
                             }
+                            ReaderModeState::Text(state) => {
+                                let mut it = state.position.chapter + 1;
+
+                                ui.add(
+                                    DragValue::new(&mut it)
+                                        .prefix("Chapter: ")
+                                        .range(1..=chapter_len.max(1)),
+                                );
+
+                                if state.position.chapter != it - 1 {
+                                    state.set_position(fmr_reader::TextPosition {
+                                        chapter: it - 1,
+                                        offset: 0,
+                                    });
+                                }
+                            }
                         }
                     }
                 });
@@ -469,6 +970,40 @@ impl eframe::App for App {
                 self.app.open_reader(it.path.clone());
                 true
             }
+
+            fn on_action(
+                &mut self,
+                path: &Path,
+                action: AppExplorerAction,
+                paths: Vec<PathBuf>,
+            ) -> bool {
+                self.app.apply_explorer_action(path, action, paths)
+            }
+
+            fn on_sort(&mut self, column: DetailsColumn) -> bool {
+                // only explorer is ordered by it, the reader keeps the path ordering.
+                let setting = &mut self.app.setting;
+                let sorter = setting.explorer_sorter.inner();
+                let ascending = crate::sorted_column(sorter) != Some(column) || !sorter.ascending;
+                setting.explorer.details_sort = Some((column, ascending));
+                setting.sync_explorer_sorter();
+
+                true
+            }
+
+            fn on_layout(&mut self, path: &Path, layout: ExplorerLayout) -> bool {
+                let Some(path) = path.to_str() else {
+                    return false;
+                };
+
+                let layouts = &mut self.app.setting.explorer.layouts;
+                match layout {
+                    ExplorerLayout::Grid => layouts.remove(path),
+                    _ => layouts.insert(path.to_string(), layout),
+                };
+
+                true
+            }
         }
 
         let inner = egui::CentralPanel::default().show(ctx, |ui| match self.mode.take() {
@@ -493,7 +1028,7 @@ impl eframe::App for App {
         let mut mode = inner.inner;
         let input_event_len = ctx.input(|i| i.events.len());
 
-        if let (true, Some(mode)) = (response.hovered(), &mut mode) {
+        if let (true, Some(mode)) = (response.hovered() && !is_prompting, &mut mode) {
             fmr_egui::event::handles(ctx, |it| {
                 let mut handled = false;
 
@@ -501,7 +1036,7 @@ impl eframe::App for App {
                     AppMode::Explorer(explorer) => {
                         explorer.handle_event(Some(OnOpen { app: self }), it)
                     }
-                    AppMode::Reader(reader) => reader.handle_event(&self.setting.reader, ctx, it),
+                    AppMode::Reader(reader) => reader.handle_event(ctx, it),
                 };
 
                 handled
@@ -539,7 +1074,7 @@ impl eframe::App for App {
             }
         }
 
-        if response.hovered() {
+        if response.hovered() && !is_prompting {
             ctx.input_mut(|input| {
                 if input.pointer.button_clicked(egui::PointerButton::Primary)
                     && input.pointer.button_clicked(egui::PointerButton::Secondary)
@@ -592,11 +1127,17 @@ impl eframe::App for App {
             });
         }
 
+        let is_filtering =
+            matches!(&self.mode, Some(AppMode::Explorer(it)) if it.is_filter_focused());
         if input_event_len != ctx.input(|i| i.events.len()) {
             ctx.request_repaint();
-            ctx.memory_mut(|memory| memory.stop_text_input());
+            if !is_filtering {
+                ctx.memory_mut(|memory| memory.stop_text_input());
+            }
         }
 
+        self.show_password_prompt(ctx);
+        self.show_messages(ctx);
         self.debug_ui.show_window(ctx);
     }
 }
@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeMap,
     path::{Path, PathBuf},
     sync::Arc,
 };
@@ -8,11 +9,21 @@ use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use tokio::sync::watch;
 
-use fmr_core::{path::{compare_natural, PathSorterType}, AbortOnDropHandle};
+use fmr_core::{
+    exclude::ExcludeRules,
+    password::Passwords,
+    path::{PathSortKey, PathSorterInner, PathSorterSubscriber},
+    AbortOnDropHandle,
+};
+use fmr_egui::tools::zip::NameEncoding;
 use fmr_explorer::{
-    Explorer, ExplorerEntryLoaderSetting, ExplorerLoader, ExplorerLoaderCache,
-    ExplorerLoaderSetting, ExplorerOutput, ExplorerSetting, ExplorerView, PathExplorerItem,
+    DetailsColumn, Explorer, ExplorerEntryLoaderSetting, ExplorerLayout, ExplorerLoader,
+    ExplorerLoaderCache, ExplorerLoaderSetting, ExplorerOutput, ExplorerSetting, ExplorerView,
+    PathExplorerItem,
 };
+
+use crate::ReadingProgress;
+
 pub struct AppExplorer {
     path: PathBuf,
     inner: Arc<RwLock<Explorer<PathExplorerItem>>>,
@@ -31,31 +42,25 @@ pub struct AppExplorerSetting {
     pub loader: ExplorerLoaderSetting,
     #[serde(skip)]
     pub cache: ExplorerLoaderCache,
+    #[serde(skip)]
+    pub passwords: Passwords,
+    #[serde(skip)]
+    pub exclude: ExcludeRules,
+    #[serde(skip)]
+    pub zip_name_encoding: NameEncoding,
     #[serde(default)]
     pub entry: ExplorerEntryLoaderSetting,
     #[serde(skip)]
     pub sorter: ExplorerSorter,
+    /// layout of each folder that isn't [`ExplorerLayout::Grid`].
+    pub layouts: BTreeMap<String, ExplorerLayout>,
+    /// column and ascending clicked in the details header, it overrides the path ordering
+    /// of explorer only.
+    pub details_sort: Option<(DetailsColumn, bool)>,
 }
 
-#[derive(Clone)]
-pub struct ExplorerSorter(pub PathSorterType);
-
-impl Default for ExplorerSorter {
-    fn default() -> Self {
-        Self(Arc::new(|a, b| {
-            compare_natural(a.file_name().unwrap(), b.file_name().unwrap())
-        }))
-    }
-}
-
-impl ExplorerSorter {
-    pub fn replace(
-        &mut self,
-        fun: impl Fn(&Path, &Path) -> std::cmp::Ordering + Send + Sync + 'static,
-    ) {
-        self.0 = Arc::new(fun);
-    }
-}
+#[derive(Clone, Default)]
+pub struct ExplorerSorter(pub PathSorterSubscriber);
 
 impl std::fmt::Debug for ExplorerSorter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -67,10 +72,17 @@ impl AppExplorer {
     pub fn new(
         path: PathBuf,
         setting: AppExplorerSetting,
+        progress: ReadingProgress,
         selected_entry: Option<PathBuf>,
         ctx: egui::Context,
     ) -> Self {
-        let explorer = Arc::new(RwLock::new(Explorer::default()));
+        let mut explorer = Explorer::default();
+        explorer.layout = path
+            .to_str()
+            .and_then(|it| setting.layouts.get(it).copied())
+            .unwrap_or_default();
+
+        let explorer = Arc::new(RwLock::new(explorer));
         let (setting_sender, setting_receiver) = watch::channel(setting.loader);
 
         let loader = ExplorerLoader {
@@ -79,7 +91,11 @@ impl AppExplorer {
             selected_entry,
             setting_receiver,
             cache: setting.cache,
+            passwords: setting.passwords,
+            exclude: setting.exclude,
+            zip_name_encoding: setting.zip_name_encoding,
             sorter: setting.sorter.0,
+            reading_status: Some(Arc::new(progress)),
             ctx,
         };
 
@@ -109,10 +125,29 @@ impl AppExplorer {
             .collect()
     }
 
+    /// Is the filter bar focused, see [`ExplorerOutput::is_filter_focused`].
+    pub fn is_filter_focused(&self) -> bool {
+        self.prev_output
+            .as_ref()
+            .is_some_and(|it| it.is_filter_focused)
+    }
+
     pub fn selected_path(&self) -> Option<PathBuf> {
         self.inner.read().current_item().map(|it| it.path.clone())
     }
 
+    /// Paths of every selected item, see [`Explorer::selection`].
+    ///
+    /// [`Explorer::selection`]: fmr_explorer::Explorer::selection
+    pub fn selected_paths(&self) -> Vec<PathBuf> {
+        let inner = self.inner.read();
+        inner
+            .selected_items()
+            .into_iter()
+            .map(|it| it.path.clone())
+            .collect()
+    }
+
     pub fn handle_event(
         &mut self,
         mut on_open: Option<impl AppExplorerOnOpen>,
@@ -147,6 +182,58 @@ impl AppExplorer {
 pub trait AppExplorerOnOpen {
     fn on_open(&mut self, explorer: &PathExplorerItem) -> bool;
     fn on_open_with_shift(&mut self, explorer: &PathExplorerItem) -> bool;
+    /// Apply `action` to selected `paths` of explorer opened in `path`.
+    fn on_action(&mut self, path: &Path, action: AppExplorerAction, paths: Vec<PathBuf>) -> bool;
+    /// Sort items by `column`, the order is reversed if they're sorted by it already.
+    fn on_sort(&mut self, column: DetailsColumn) -> bool;
+    /// Layout of explorer opened in `path` is changed.
+    fn on_layout(&mut self, path: &Path, layout: ExplorerLayout) -> bool;
+}
+
+/// Column of the details layout `sorter` orders items by.
+pub fn sorted_column(sorter: &PathSorterInner) -> Option<DetailsColumn> {
+    match sorter.key {
+        PathSortKey::Name if sorter.last_modified => Some(DetailsColumn::Modified),
+        PathSortKey::Name => Some(DetailsColumn::Name),
+        PathSortKey::Size => Some(DetailsColumn::Size),
+        PathSortKey::PageCount => Some(DetailsColumn::PageCount),
+        PathSortKey::Progress => Some(DetailsColumn::Progress),
+        PathSortKey::Created | PathSortKey::Extension | PathSortKey::Random => None,
+    }
+}
+
+/// Action applied to every selected item from the context menu of the explorer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AppExplorerAction {
+    MarkRead,
+    MarkUnread,
+    ClearThumbnail,
+    MoveTo,
+    CopyTo,
+    /// open selected books in the reader as one book.
+    OpenCombined,
+}
+
+impl AppExplorerAction {
+    pub const ALL: [Self; 6] = [
+        Self::OpenCombined,
+        Self::MarkRead,
+        Self::MarkUnread,
+        Self::ClearThumbnail,
+        Self::MoveTo,
+        Self::CopyTo,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::MarkRead => "Mark as Read",
+            Self::MarkUnread => "Mark as Unread",
+            Self::ClearThumbnail => "Clear Thumbnail Cache",
+            Self::MoveTo => "Move to...",
+            Self::CopyTo => "Copy to...",
+            Self::OpenCombined => "Open as One Book",
+        }
+    }
 }
 
 pub struct AppExplorerView<'a, OnOpen> {
@@ -176,6 +263,10 @@ impl<'a, OnOpen: AppExplorerOnOpen> AppExplorerView<'a, OnOpen> {
             mut on_open,
         } = self;
 
+        let sorter = setting.sorter.0.inner();
+        let sorted_by = sorted_column(&sorter).map(|it| (it, sorter.ascending));
+        let layout = explorer.inner.read().layout;
+
         let response = ui
             .scope(|ui| {
                 ui.centered_and_justified(|ui| {
@@ -183,8 +274,13 @@ impl<'a, OnOpen: AppExplorerOnOpen> AppExplorerView<'a, OnOpen> {
 
                     let output = ExplorerView::new(&mut explorer.inner.write(), &setting.explorer)
                         .id_source("explorer")
+                        .sorted_by(sorted_by)
                         .show(ui);
 
+                    if let Some(column) = output.sort_by {
+                        on_open.as_mut().map(|it| it.on_sort(column));
+                    }
+
                     let inner = explorer.inner.read();
                     let item = inner.current_item();
                     if let Some(item) = item {
@@ -192,16 +288,61 @@ impl<'a, OnOpen: AppExplorerOnOpen> AppExplorerView<'a, OnOpen> {
                             on_open.as_mut().map(|it| it.on_open(item));
                         }
                     }
+
+                    let selected = inner.selection().len();
+                    drop(inner);
+
+                    let mut action = None;
+                    output.context_menu(|ui| {
+                        ui.label(format!("{selected} selected"));
+                        ui.separator();
+
+                        for it in AppExplorerAction::ALL {
+                            if ui.button(it.label()).clicked() {
+                                action = Some(it);
+                                ui.close_menu();
+                            }
+                        }
+                    });
+
+                    if let Some(action) = action {
+                        let paths = explorer.selected_paths();
+                        let path = explorer.path.clone();
+                        on_open
+                            .as_mut()
+                            .map(|it| it.on_action(&path, action, paths));
+                    }
+
                     explorer.prev_output = Some(output);
                 })
             })
             .response;
 
-        let index = explorer.inner.read().current_index();
+        let (index, visible, search, new_layout) = {
+            let inner = explorer.inner.read();
+            let visible = match inner.filter.is_searching() {
+                true => None,
+                false => inner.visible().map(<[usize]>::to_vec),
+            };
+            (
+                inner.current_index(),
+                visible,
+                inner.filter.search(),
+                inner.layout,
+            )
+        };
+
+        if new_layout != layout {
+            let path = explorer.path.clone();
+            on_open.as_mut().map(|it| it.on_layout(&path, new_layout));
+        }
 
         let setting = ExplorerLoaderSetting {
             index,
             entry_setting: setting.entry.clone(),
+            visible,
+            search,
+            details: new_layout == ExplorerLayout::Details,
         };
 
         if setting != *explorer.setting_sender.borrow() {
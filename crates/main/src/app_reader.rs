@@ -1,6 +1,10 @@
 use std::{
+    ops::RangeInclusive,
     path::{Path, PathBuf},
-    sync::{atomic::AtomicBool, Arc},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
 use eframe::egui;
@@ -8,13 +12,29 @@ use parking_lot::{RwLock, RwLockReadGuard};
 use serde::{Deserialize, Serialize};
 use tokio::sync::watch;
 
-use fmr_core::path::{compare_natural, get_sorted_folder_by, PathSorterType};
-use fmr_core::AbortOnDropHandle;
-use fmr_egui::{key, Vec2Ext};
+use fmr_core::path::{
+    compare_natural, get_sorted_folder_by, NameSorterType, PathSorterInner, PathSorterType,
+};
+use fmr_core::{
+    exclude::ExcludeRules,
+    password::Passwords,
+    spawn_and_abort_on_drop,
+    watch::{DirChange, DirWatcher},
+    AbortOnDropHandle,
+};
+use fmr_egui::{
+    key,
+    tools::{
+        archive_dir::{self, ArchivePath},
+        zip::NameEncoding,
+    },
+    Vec2Ext,
+};
 use fmr_frame::TextureOption;
 use fmr_reader::{
-    loader::{ReaderLoader, ReaderLoaderSetting},
-    PagedReaderState, Reader, ReaderMode, ReaderModeState, ReaderSetting, ReaderView,
+    loader::{ReaderLoader, ReaderLoaderSetting, RenderTarget},
+    PagedReaderState, Reader, ReaderMode, ReaderModeState, ReaderSetting, ReaderView, TextPosition,
+    VerticalChapter,
 };
 
 #[derive(Clone, Serialize, Deserialize, Default, Debug)]
@@ -25,11 +45,36 @@ pub struct AppReaderSetting {
     pub preload_prev: usize,
     pub preload_next: usize,
 
+    /// start loading next folder when current page is within this many pages
+    /// from the end, 0 to disable.
+    pub prefetch_next_folder: usize,
+    /// number of pages of next folder decoded ahead.
+    pub prefetch_pages: usize,
+    /// number of pages decoded at the same time, 0 to use the number of CPU.
+    pub decode_permits: usize,
+
     #[serde(default)]
     pub texture_option: TextureOption,
 
     #[serde(skip)]
     pub folder_sorter: AppReaderFolderSorter,
+    #[serde(skip)]
+    pub page_sorter: AppReaderPageSorter,
+    #[serde(skip)]
+    pub passwords: Passwords,
+    #[serde(skip)]
+    pub exclude: ExcludeRules,
+    #[serde(skip)]
+    pub zip_name_encoding: NameEncoding,
+}
+
+impl AppReaderSetting {
+    pub fn decode_permits(&self) -> usize {
+        match self.decode_permits {
+            0 => std::thread::available_parallelism().map_or(2, |it| it.get()),
+            permits => permits,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -66,6 +111,64 @@ impl std::fmt::Debug for AppReaderFolderSorter {
     }
 }
 
+#[derive(Clone)]
+pub struct AppReaderPageSorter(pub NameSorterType);
+
+impl PartialEq for AppReaderPageSorter {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for AppReaderPageSorter {}
+
+impl Default for AppReaderPageSorter {
+    fn default() -> Self {
+        Self(Arc::new(|a, b| {
+            PathSorterInner::default().compare_name(a, b)
+        }))
+    }
+}
+
+impl AppReaderPageSorter {
+    pub fn replace(
+        &mut self,
+        fun: impl Fn(&str, &str) -> std::cmp::Ordering + Send + Sync + 'static,
+    ) {
+        self.0 = Arc::new(fun);
+    }
+}
+
+impl std::fmt::Debug for AppReaderPageSorter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("AppReaderPageSorter").finish()
+    }
+}
+
+#[derive(Default)]
+enum AppReaderChapter {
+    #[default]
+    Unchecked,
+    Missing,
+    Loaded(Box<AppReader>),
+}
+
+impl AppReaderChapter {
+    fn as_ref(&self) -> Option<&AppReader> {
+        match self {
+            Self::Loaded(reader) => Some(reader),
+            _ => None,
+        }
+    }
+
+    fn take(&mut self) -> Option<Box<AppReader>> {
+        match std::mem::take(self) {
+            Self::Loaded(reader) => Some(reader),
+            _ => None,
+        }
+    }
+}
+
 pub struct AppReader {
     pub path: PathBuf,
     pub setting: AppReaderSetting,
@@ -76,6 +179,21 @@ pub struct AppReader {
     is_done_initial_loading: Arc<AtomicBool>,
 
     pub reading_progress: crate::ReadingProgress,
+    // next folder loaded in background, taken over by `change_folder(1)`.
+    prefetch: Option<Box<AppReader>>,
+    is_prefetch_checked: bool,
+    // sibling folders shown around this one in continuous vertical mode.
+    prev_chapter: AppReaderChapter,
+    next_chapter: AppReaderChapter,
+    // position of text book, restored from reading progress once it's loaded.
+    text_position: Option<TextPosition>,
+    // books read as this one, see `new_combined`.
+    combined: Vec<PathBuf>,
+    // set when pages are added to the folder while it's read.
+    has_new_pages: Arc<AtomicBool>,
+    // watch of the folder, started once the reader is shown.
+    page_watch: Option<AbortOnDropHandle<()>>,
+    is_page_watch_checked: bool,
     #[allow(dead_code)]
     handle: AbortOnDropHandle<()>,
 }
@@ -86,10 +204,39 @@ impl AppReader {
         setting: AppReaderSetting,
         reading_progress: crate::ReadingProgress,
         ctx: egui::Context,
+    ) -> Self {
+        let decode_permits = setting.decode_permits();
+        Self::with_loader(path, vec![], setting, reading_progress, ctx, decode_permits)
+    }
+
+    /// Read books at `paths` one after another as a single book.
+    ///
+    /// Its progress isn't saved, since page of the combined book isn't page of any of them.
+    pub fn new_combined(
+        paths: Vec<PathBuf>,
+        setting: AppReaderSetting,
+        ctx: egui::Context,
+    ) -> Option<Self> {
+        let path = paths.first()?.clone();
+        let decode_permits = setting.decode_permits();
+        let reading_progress = crate::ReadingProgress::default();
+        let reader = Self::with_loader(path, paths, setting, reading_progress, ctx, decode_permits);
+
+        Some(reader)
+    }
+
+    fn with_loader(
+        path: PathBuf,
+        combined: Vec<PathBuf>,
+        setting: AppReaderSetting,
+        reading_progress: crate::ReadingProgress,
+        ctx: egui::Context,
+        decode_permits: usize,
     ) -> Self {
         let images = Vec::new();
 
         let mode = match setting.reader.mode {
+            _ if fmr_reader::loader::is_text(&path) => ReaderModeState::Text(Default::default()),
             ReaderMode::Paged => {
                 let mut paged = PagedReaderState::default();
                 paged.read_from_right = setting.reader.paged.read_from_right;
@@ -97,6 +244,7 @@ impl AppReader {
                 ReaderModeState::Paged(paged)
             }
             ReaderMode::Vertical => ReaderModeState::Vertical(Default::default()),
+            ReaderMode::Text => ReaderModeState::Text(Default::default()),
         };
 
         let reader = Arc::new(RwLock::new(Reader::new(images, mode)));
@@ -106,6 +254,10 @@ impl AppReader {
             preload_next: setting.preload_next,
             preload_prev: setting.preload_prev,
             texture_option: setting.texture_option,
+            render_target: RenderTarget {
+                sizer: setting.reader.sizer,
+                ..Default::default()
+            },
         };
         let (index_sender, index_receiver) = watch::channel(current_index);
         index_sender.send(current_index).ok();
@@ -120,6 +272,12 @@ impl AppReader {
             ctx,
             setting_receiver: index_receiver.clone(),
             is_done_initial_loading: is_done_initial_loading.clone(),
+            decode_permits,
+            passwords: setting.passwords.clone(),
+            exclude: setting.exclude.clone(),
+            zip_name_encoding: setting.zip_name_encoding,
+            page_sorter: setting.page_sorter.0.clone(),
+            combined: combined.clone(),
         };
 
         let handle = tokio::spawn(loader.load());
@@ -132,16 +290,97 @@ impl AppReader {
             index_sender,
             index_receiver,
             is_done_initial_loading,
+            prefetch: None,
+            is_prefetch_checked: false,
+            prev_chapter: AppReaderChapter::Unchecked,
+            next_chapter: AppReaderChapter::Unchecked,
+            text_position: None,
+            combined,
+            has_new_pages: Default::default(),
+            page_watch: None,
+            is_page_watch_checked: false,
             handle: AbortOnDropHandle(handle),
         }
     }
 
+    /// Load pages of the folder again keeping the current page, e.g. pages are added.
+    pub fn refresh(&mut self, ctx: egui::Context) {
+        let (index, offset) = match &self.reader().state {
+            ReaderModeState::Paged(state) => (Some(state.index), None),
+            ReaderModeState::Vertical(state) => (None, Some(state.scroll_state.offset)),
+            ReaderModeState::Text(_) => (None, None),
+        };
+
+        let decode_permits = self.setting.decode_permits();
+        *self = Self::with_loader(
+            self.path.clone(),
+            std::mem::take(&mut self.combined),
+            self.setting.clone(),
+            self.reading_progress.clone(),
+            ctx,
+            decode_permits,
+        );
+
+        match &mut self.reader_mut().state {
+            ReaderModeState::Paged(state) => state.index = index.unwrap_or_default(),
+            ReaderModeState::Vertical(state) => {
+                state.scroll_state.offset = offset.unwrap_or_default();
+            }
+            ReaderModeState::Text(_) => {}
+        }
+    }
+
+    /// Start watching the folder for added pages, see [`Self::has_new_pages`].
+    fn watch_pages(&mut self, ctx: &egui::Context) {
+        if self.is_page_watch_checked {
+            return;
+        }
+        self.is_page_watch_checked = true;
+
+        // pages of archive or combined books don't change.
+        if !self.combined.is_empty() || !self.path.is_dir() {
+            return;
+        }
+        let Ok(mut watcher) = DirWatcher::new(&self.path) else {
+            return;
+        };
+
+        let has_new_pages = self.has_new_pages.clone();
+        let exclude = self.setting.exclude.clone();
+        let ctx = ctx.clone();
+        self.page_watch = Some(spawn_and_abort_on_drop(async move {
+            while let Some(changes) = watcher.changed().await {
+                // rewriting a page doesn't add one.
+                let is_page_added = changes.iter().any(|it| match it {
+                    DirChange::Created(path) | DirChange::Renamed { to: path, .. } => {
+                        path.file_name().is_some_and(|it| {
+                            let name = it.to_string_lossy();
+                            !exclude.is_excluded(&name) && fmr_frame::ImageData::can_read(&name)
+                        })
+                    }
+                    DirChange::Removed(_) | DirChange::Modified(_) | DirChange::Rescan => false,
+                });
+
+                if is_page_added {
+                    has_new_pages.store(true, Ordering::Relaxed);
+                    ctx.request_repaint();
+                }
+            }
+        }));
+    }
+
+    /// Are pages added to the folder since it's opened.
+    pub fn has_new_pages(&self) -> bool {
+        self.has_new_pages.load(Ordering::Relaxed)
+    }
+
     pub fn change_scale(&mut self, before: u64, after: u64) {
         let mut reader = self.reader_mut();
 
         let scroll_state = match &mut reader.state {
             ReaderModeState::Vertical(state) => &mut state.scroll_state,
             ReaderModeState::Paged(state) => &mut state.scroll,
+            ReaderModeState::Text(_) => return,
         };
 
         let scale = after as f32 / before as f32;
@@ -174,27 +413,236 @@ impl AppReader {
         &self.path
     }
 
-    pub fn change_folder(&mut self, direction: isize, ctx: &egui::Context) -> bool {
-        let path = get_sorted_folder_by(self.path.clone(), direction, |a, b| {
+    fn sorted_folder_by(&self, direction: isize) -> Option<PathBuf> {
+        // combined book has no sibling.
+        if !self.combined.is_empty() {
+            return None;
+        }
+
+        if ArchivePath::is_virtual(&self.path) {
+            let (sorter, rules) = (&self.setting.folder_sorter.0, &self.setting.exclude);
+            let encoding = self.setting.zip_name_encoding;
+            return archive_dir::sibling_dir(&self.path, direction, rules, encoding, |a, b| {
+                sorter(a, b)
+            });
+        }
+
+        get_sorted_folder_by(self.path.clone(), direction, |a, b| {
             self.setting.folder_sorter.0(&a.path(), &b.path())
-        });
+        })
+    }
+
+    pub fn change_folder(&mut self, direction: isize, ctx: &egui::Context) -> bool {
+        let path = self.sorted_folder_by(direction);
 
         match path {
             Some(path) => {
                 self.reading_progress.insert_finish(&self.path);
-                self.open(path, ctx.clone());
+
+                match self.prefetch.take() {
+                    Some(mut prefetch) if prefetch.path == path => {
+                        log::info!("using prefetched {:?}", path);
+                        prefetch.setting = self.setting.clone();
+                        *self = *prefetch;
+                    }
+                    _ => self.open(path, ctx.clone()),
+                }
                 true
             }
             _ => false,
         }
     }
 
-    pub fn handle_event(
+    /// Start loading next folder in background when `index` is near the end.
+    pub fn prefetch_next_folder(
         &mut self,
         setting: &AppReaderSetting,
+        index: usize,
+        total: usize,
+        ctx: &egui::Context,
+    ) {
+        let distance = setting.prefetch_next_folder;
+
+        if self.is_prefetch_checked || distance == 0 || total == 0 {
+            return;
+        }
+
+        if total.saturating_sub(index + 1) >= distance {
+            return;
+        }
+
+        self.is_prefetch_checked = true;
+
+        if let Some(path) = self.sorted_folder_by(1) {
+            log::info!("prefetching {:?}", path);
+            let mut setting = setting.clone();
+            setting.preload_prev = 0;
+            setting.preload_next = setting.prefetch_pages;
+
+            let prefetch = Self::with_loader(
+                path,
+                vec![],
+                setting,
+                self.reading_progress.clone(),
+                ctx.clone(),
+                1,
+            );
+            self.prefetch = Some(Box::new(prefetch));
+        }
+    }
+
+    fn chapter_id(&self) -> String {
+        self.path.to_string_lossy().to_string()
+    }
+
+    fn chapter_name(&self) -> String {
+        if let Some(title) = &self.reader().book.title {
+            return title.clone();
+        }
+
+        self.path
+            .file_name()
+            .unwrap_or(self.path.as_os_str())
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn open_chapter(
+        &self,
+        direction: isize,
+        setting: &AppReaderSetting,
         ctx: &egui::Context,
-        event: &egui::Event,
-    ) -> bool {
+    ) -> AppReaderChapter {
+        match self.sorted_folder_by(direction) {
+            Some(path) => {
+                log::info!("open chapter {:?}", path);
+                // its window follows the viewport once it's shown, see `AppReaderView::show`.
+                let setting = setting.clone();
+                let chapter = Self::new(path, setting, self.reading_progress.clone(), ctx.clone());
+                AppReaderChapter::Loaded(Box::new(chapter))
+            }
+            None => AppReaderChapter::Missing,
+        }
+    }
+
+    /// Make next (`direction` = 1) or previous chapter as the current one.
+    fn shift_chapter(&mut self, direction: isize) {
+        let chapter = if direction.is_positive() {
+            self.next_chapter.take()
+        } else {
+            self.prev_chapter.take()
+        };
+
+        let Some(mut chapter) = chapter else {
+            return;
+        };
+
+        log::info!("moving chapter from {:?} to {:?}", self.path, chapter.path);
+        std::mem::swap(
+            &mut self.reader.write().state,
+            &mut chapter.reader.write().state,
+        );
+        chapter.setting = self.setting.clone();
+
+        std::mem::swap(self, &mut chapter);
+        // `chapter` is the previous current one now.
+        chapter.prefetch = None;
+
+        if direction.is_positive() {
+            self.reading_progress.insert_finish(&chapter.path);
+        } else {
+            self.save_progress_of(&chapter);
+        }
+        self.save_progress_of(self);
+
+        if direction.is_positive() {
+            chapter.prev_chapter = AppReaderChapter::Unchecked;
+            self.prev_chapter = AppReaderChapter::Loaded(chapter);
+        } else {
+            chapter.next_chapter = AppReaderChapter::Unchecked;
+            self.next_chapter = AppReaderChapter::Loaded(chapter);
+        }
+    }
+
+    /// Save the page of `chapter` shown in continuous vertical mode of this reader.
+    fn save_progress_of(&self, chapter: &AppReader) {
+        let pages = match &self.reader().state {
+            ReaderModeState::Vertical(state) => state.visible_pages(&chapter.chapter_id()),
+            _ => None,
+        };
+
+        let value = match pages {
+            Some(pages) => {
+                let total = chapter.reader().images.len();
+                crate::ReadingProgressValue::new(pages.start() + 1, total)
+            }
+            // chapter isn't laid out yet, keep progress of chapter read before.
+            None if self.reading_progress.get(&chapter.path).is_some() => return,
+            None => crate::ReadingProgressValue::new(0, 1),
+        };
+        self.reading_progress.insert(&chapter.path, value);
+    }
+
+    // decode `pages` shown in vertical mode and the configured window around them.
+    fn vertical_loader_setting(
+        setting: &AppReaderSetting,
+        pages: RangeInclusive<usize>,
+        render_target: RenderTarget,
+    ) -> ReaderLoaderSetting {
+        ReaderLoaderSetting {
+            index: *pages.start(),
+            preload_prev: setting.preload_prev,
+            // the window after the index includes the page at it.
+            preload_next: pages.end() - pages.start() + setting.preload_next.max(1),
+            texture_option: setting.texture_option,
+            render_target,
+        }
+    }
+
+    /// Load sibling chapters near the edge of the strip and
+    /// change the current one when crossing to them in continuous vertical mode.
+    pub fn update_chapters(&mut self, setting: &AppReaderSetting, ctx: &egui::Context) {
+        let position = match &self.reader().state {
+            ReaderModeState::Vertical(state) if setting.reader.vertical.continuous => Some((
+                state.visible_chapter().map(str::to_string),
+                state.is_near_start(),
+                state.is_near_end(),
+            )),
+            _ => None,
+        };
+
+        let Some((visible, is_near_start, is_near_end)) = position else {
+            self.prev_chapter = AppReaderChapter::Unchecked;
+            self.next_chapter = AppReaderChapter::Unchecked;
+            return;
+        };
+
+        let is_visible =
+            |chapter: &AppReaderChapter| chapter.as_ref().map(|it| it.chapter_id()) == visible;
+
+        if is_visible(&self.next_chapter) {
+            self.shift_chapter(1);
+        } else if is_visible(&self.prev_chapter) {
+            self.shift_chapter(-1);
+        }
+
+        if !self
+            .is_done_initial_loading
+            .load(std::sync::atomic::Ordering::Relaxed)
+        {
+            return;
+        }
+
+        if is_near_end && matches!(self.next_chapter, AppReaderChapter::Unchecked) {
+            self.next_chapter = self.open_chapter(1, setting, ctx);
+        }
+
+        if is_near_start && matches!(self.prev_chapter, AppReaderChapter::Unchecked) {
+            self.prev_chapter = self.open_chapter(-1, setting, ctx);
+        }
+    }
+
+    pub fn handle_event(&mut self, ctx: &egui::Context, event: &egui::Event) -> bool {
         let Self { reader, .. } = self;
 
         if reader.write().handle_event(event) {
@@ -202,7 +650,7 @@ impl AppReader {
         }
 
         let is_vertical = reader.read().state.is_vertical();
-        let read_from_right = setting.reader.paged.read_from_right;
+        let read_from_right = reader.read().is_read_from_right();
 
         let mut change_folder = |direction: isize| self.change_folder(direction, ctx);
 
@@ -251,6 +699,8 @@ impl<'a> AppReaderView<'a> {
         let Self { setting, state } = self;
         let mut read_from_right = false;
         let mut is_vertical = false;
+        let mut is_text = false;
+        let available = ui.available_size();
 
         let response = ui
             .centered_and_justified(|ui| {
@@ -258,11 +708,41 @@ impl<'a> AppReaderView<'a> {
                 // reader.setting = setting.reader.clone();
                 read_from_right = reader.is_read_from_right();
                 is_vertical = reader.state.is_vertical();
+                is_text = reader.state.is_text();
 
-                ReaderView::new(&mut reader, &setting.reader).show(ui)
+                let view = ReaderView::new(&mut reader, &setting.reader);
+
+                if is_vertical && setting.reader.vertical.continuous {
+                    let mut prev = state.prev_chapter.as_ref().map(|it| (it, it.reader_mut()));
+                    let mut next = state.next_chapter.as_ref().map(|it| (it, it.reader_mut()));
+
+                    let mut view = view.chapter(state.chapter_id(), state.chapter_name());
+
+                    if let Some((chapter, reader)) = &mut prev {
+                        view = view.prev_chapter(VerticalChapter {
+                            id: chapter.chapter_id(),
+                            name: chapter.chapter_name(),
+                            images: &mut reader.images,
+                        });
+                    }
+
+                    if let Some((chapter, reader)) = &mut next {
+                        view = view.next_chapter(VerticalChapter {
+                            id: chapter.chapter_id(),
+                            name: chapter.chapter_name(),
+                            images: &mut reader.images,
+                        });
+                    }
+
+                    view.show(ui)
+                } else {
+                    view.show(ui)
+                }
             })
             .inner;
 
+        state.update_chapters(setting, ui.ctx());
+
         {
             let pointer = ui.input(|it| it.pointer.clone());
             let any_down = pointer.any_down();
@@ -271,8 +751,10 @@ impl<'a> AppReaderView<'a> {
             let change_folder_with_scroll_wheel =
                 setting.change_folder_with_scroll_wheel && !any_down;
 
-            let should_change_folder =
-                (change_folder_with_scroll_wheel || secondary_down) && response.hovered();
+            // scroll wheel is used to scroll text.
+            let should_change_folder = (change_folder_with_scroll_wheel || secondary_down)
+                && response.hovered()
+                && !is_text;
 
             fmr_egui::event::retains(ui.ctx(), |event| {
                 if should_change_folder {
@@ -308,13 +790,42 @@ impl<'a> AppReaderView<'a> {
             });
         }
 
-        let reader = state.reader();
-        if let ReaderModeState::Paged(paged) = &reader.state {
+        if state.text_position.is_none() {
+            let mut reader = state.reader.write();
+            let is_loaded = !reader.text.is_empty();
+
+            if let (ReaderModeState::Text(text), true) = (&mut reader.state, is_loaded) {
+                let progress = state.reading_progress.get(&state.path);
+                if let Some(position) = progress.and_then(|it| it.text_position) {
+                    text.set_position(position);
+                }
+                state.text_position = Some(text.position);
+            }
+        }
+
+        let mut page = None;
+        let reader = state.reader.read();
+        if let ReaderModeState::Text(text) = &reader.state {
+            if state.text_position.is_some_and(|it| it != text.position) {
+                state.text_position = Some(text.position);
+                state.reading_progress.insert(
+                    &state.path,
+                    crate::ReadingProgressValue::new(text.position.chapter + 1, reader.text.len())
+                        .with_text_position(text.position),
+                );
+            }
+        } else if let ReaderModeState::Paged(paged) = &reader.state {
             let current = ReaderLoaderSetting {
                 index: paged.index,
                 preload_prev: setting.preload_prev,
                 preload_next: setting.preload_next,
                 texture_option: setting.texture_option,
+                render_target: RenderTarget {
+                    sizer: setting.reader.sizer,
+                    available: [available.x as u32, available.y as u32],
+                    fit_range: [true, true],
+                    pixels_per_point: ui.ctx().pixels_per_point(),
+                },
             };
             if *state.index_sender.borrow() != current {
                 state.index_sender.send(current).ok();
@@ -323,19 +834,93 @@ impl<'a> AppReaderView<'a> {
                     crate::ReadingProgressValue::new(paged.index + 1, reader.images.len()),
                 );
             }
+
+            page = Some((paged.index, reader.images.len()));
         } else {
-            let current = ReaderLoaderSetting {
-                index: 0,
-                preload_prev: usize::MAX,
-                preload_next: usize::MAX,
-                texture_option: setting.texture_option,
+            let render_target = RenderTarget {
+                sizer: setting.reader.sizer,
+                available: [available.x as u32, available.y as u32],
+                fit_range: [true, false],
+                pixels_per_point: ui.ctx().pixels_per_point(),
+            };
+            let continuous = setting.reader.vertical.continuous;
+            let visible_pages = |id: &str| match &reader.state {
+                ReaderModeState::Vertical(vertical) => vertical.visible_pages(id),
+                _ => None,
+            };
+
+            // the chapter isn't named unless it's shown with its siblings.
+            let id = if continuous {
+                state.chapter_id()
+            } else {
+                String::new()
             };
+            let pages = visible_pages(&id).unwrap_or(0..=0);
+            let current = AppReader::vertical_loader_setting(setting, pages, render_target);
             if *state.index_sender.borrow() != current {
                 state.index_sender.send(current).ok();
-                state
-                    .reading_progress
-                    .insert(&state.path, crate::ReadingProgressValue::new(0, 1))
-                // state.reading_progress.insert()
+                // chapter shifted in continuous mode keeps its progress.
+                if state.reading_progress.get(&state.path).is_none() {
+                    state
+                        .reading_progress
+                        .insert(&state.path, crate::ReadingProgressValue::new(0, 1))
+                }
+            }
+
+            // siblings decode the pages next to the boundary until they're scrolled into.
+            let siblings = [(&state.prev_chapter, true), (&state.next_chapter, false)];
+            for (chapter, is_prev) in siblings.into_iter().filter(|_| continuous) {
+                let Some(chapter) = chapter.as_ref() else {
+                    continue;
+                };
+
+                let pages = visible_pages(&chapter.chapter_id()).unwrap_or_else(|| {
+                    let index = match is_prev {
+                        true => chapter.reader().images.len().saturating_sub(1),
+                        false => 0,
+                    };
+                    index..=index
+                });
+                let window = AppReader::vertical_loader_setting(setting, pages, render_target);
+                if *chapter.index_sender.borrow() != window {
+                    chapter.index_sender.send(window).ok();
+                }
+            }
+
+            // next chapter is already loaded in continuous mode.
+            if let ReaderModeState::Vertical(vertical) = &reader.state {
+                if !setting.reader.vertical.continuous {
+                    page = vertical
+                        .last_visible_page()
+                        .map(|index| (index, reader.images.len()));
+                }
+            }
+        }
+        drop(reader);
+
+        if let Some((index, total)) = page {
+            state.prefetch_next_folder(setting, index, total, ui.ctx());
+        }
+
+        state.watch_pages(ui.ctx());
+        if state.has_new_pages() {
+            let (mut refresh, mut dismiss) = (false, false);
+            egui::Area::new(egui::Id::new("reader-new-pages"))
+                .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 8.0))
+                .show(ui.ctx(), |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("New pages are added to this folder");
+                            refresh = ui.button("Refresh").clicked();
+                            dismiss = ui.button("Dismiss").clicked();
+                        });
+                    });
+                });
+
+            if refresh {
+                state.refresh(ui.ctx().clone());
+            } else if dismiss {
+                state.has_new_pages.store(false, Ordering::Relaxed);
             }
         }
 
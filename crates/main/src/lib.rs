@@ -4,13 +4,19 @@ mod app_reader;
 // pub mod image_search;
 pub mod inspection;
 mod storage;
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
 
 pub use app::*;
 pub use app_explorer::*;
 pub use app_reader::*;
 
 use eframe::egui;
+use fmr_core::path::PathSortInfo;
 use parking_lot::Mutex;
 
 pub fn setup_custom_fonts(ctx: &egui::Context) {
@@ -59,6 +65,9 @@ pub fn setup_custom_fonts(ctx: &egui::Context) {
 #[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ReadingProgress {
     pub progress: Arc<Mutex<BTreeMap<String, ReadingProgressValue>>>,
+    // notified when progress is changed.
+    #[serde(skip)]
+    changed: Arc<tokio::sync::watch::Sender<()>>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -69,6 +78,9 @@ pub struct ReadingProgressValue {
 
     #[serde(default)]
     pub is_finished: bool,
+    /// chapter and char offset of text book.
+    #[serde(default)]
+    pub text_position: Option<fmr_reader::TextPosition>,
 }
 
 impl Default for ReadingProgressValue {
@@ -78,11 +90,17 @@ impl Default for ReadingProgressValue {
             total_page: 0,
             time: std::time::SystemTime::now(),
             is_finished: false,
+            text_position: None,
         }
     }
 }
 
 impl ReadingProgress {
+    pub fn get(&self, path: &std::path::Path) -> Option<ReadingProgressValue> {
+        let path = path.as_os_str().to_str()?;
+        self.progress.lock().get(path).cloned()
+    }
+
     pub fn insert(&self, path: &std::path::Path, mut value: ReadingProgressValue) {
         if let Some(it) = path.as_os_str().to_str() {
             match self.progress.lock().entry(it.to_string()) {
@@ -101,6 +119,62 @@ impl ReadingProgress {
                     *entry = value;
                 }
             }
+            self.changed.send_replace(());
+        }
+    }
+
+    /// Mark `path` as finished even if it's never opened.
+    pub fn mark_finished(&self, path: &std::path::Path) {
+        if let Some(it) = path.as_os_str().to_str() {
+            let mut progress = self.progress.lock();
+            let entry = progress.entry(it.to_string()).or_insert_with(|| {
+                let mut value = ReadingProgressValue::new(1, 1);
+                value.is_finished = true;
+                value
+            });
+
+            entry.page = entry.total_page;
+            entry.is_finished = true;
+            drop(progress);
+            self.changed.send_replace(());
+        }
+    }
+
+    /// Forget progress of `path`, it's unread again.
+    pub fn remove(&self, path: &std::path::Path) {
+        if let Some(it) = path.as_os_str().to_str() {
+            self.progress.lock().remove(it);
+            self.changed.send_replace(());
+        }
+    }
+
+    /// Reading status of `path`, a folder never opened as a book is rolled up from its
+    /// `children` books.
+    pub fn status(&self, path: &Path, children: &[PathBuf]) -> fmr_explorer::ReadingStatus {
+        use fmr_explorer::ReadingStatus;
+
+        let progress = self.progress.lock();
+        let get = |path: &Path| progress.get(path.as_os_str().to_str()?);
+
+        if let Some(value) = get(path) {
+            return value.status();
+        }
+
+        let (read, finished) = children.iter().filter_map(|it| get(it)).fold(
+            (0.0, 0),
+            |(read, finished), it| match it.status() {
+                ReadingStatus::Reading(ratio) => (read + ratio, finished),
+                ReadingStatus::Finished => (read + 1.0, finished + 1),
+                ReadingStatus::New => (read, finished),
+            },
+        );
+
+        if children.is_empty() || read == 0.0 {
+            ReadingStatus::New
+        } else if finished == children.len() {
+            ReadingStatus::Finished
+        } else {
+            ReadingStatus::Reading(read / children.len() as f32)
         }
     }
 
@@ -113,10 +187,86 @@ impl ReadingProgress {
                     item.get_mut().is_finished = true;
                 }
             }
+            self.changed.send_replace(());
+        }
+    }
+}
+
+impl fmr_explorer::ReadingStatusInfo for ReadingProgress {
+    fn status(&self, path: &Path, children: &[PathBuf]) -> fmr_explorer::ReadingStatus {
+        ReadingProgress::status(self, path, children)
+    }
+
+    fn subscribe(&self) -> tokio::sync::watch::Receiver<()> {
+        self.changed.subscribe()
+    }
+}
+
+/// Page count and reading progress used to sort paths.
+pub struct SortInfo {
+    progress: ReadingProgress,
+    page_count: Mutex<HashMap<PathBuf, CachedPageCount>>,
+}
+
+// page count of path and its last modified time.
+type CachedPageCount = (Option<SystemTime>, Option<usize>);
+
+impl SortInfo {
+    pub fn new(progress: ReadingProgress) -> Self {
+        Self {
+            progress,
+            page_count: Default::default(),
+        }
+    }
+}
+
+impl PathSortInfo for SortInfo {
+    fn page_count(&self, path: &Path) -> Option<usize> {
+        let modified = path.metadata().and_then(|it| it.modified()).ok();
+        if let Some((time, count)) = self.page_count.lock().get(path) {
+            if *time == modified {
+                return *count;
+            }
         }
+
+        let count = page_count(path);
+        self.page_count
+            .lock()
+            .insert(path.to_path_buf(), (modified, count));
+
+        count
+    }
+
+    fn progress(&self, path: &Path) -> Option<f32> {
+        let progress = self.progress.get(path)?;
+        if progress.is_finished {
+            return Some(1.0);
+        }
+
+        Some(progress.page as f32 / progress.total_page.max(1) as f32)
     }
 }
 
+fn page_count(path: &Path) -> Option<usize> {
+    #[cfg(feature = "pdf")]
+    if fmr_egui::tools::pdf::is_pdf(path) {
+        return fmr_egui::tools::pdf::PdfBook::open(path)
+            .ok()
+            .map(|it| it.len());
+    }
+
+    // names are only checked by their extension, it's the same in every encoding.
+    let encoding = fmr_egui::tools::zip::NameEncoding::Cp437;
+    let mut source = fmr_egui::tools::source::open(path, encoding)?;
+    let entries = source.entries().ok()?;
+    let count = entries
+        .iter()
+        .filter(|it| fmr_frame::ImageData::can_read(&it.name))
+        .count();
+
+    Some(count)
+}
+
 impl ReadingProgressValue {
     pub fn new(page: usize, total_page: usize) -> Self {
         Self {
@@ -124,6 +274,21 @@ impl ReadingProgressValue {
             total_page,
             time: std::time::SystemTime::now(),
             is_finished: false,
+            text_position: None,
+        }
+    }
+
+    pub fn status(&self) -> fmr_explorer::ReadingStatus {
+        if self.is_finished {
+            fmr_explorer::ReadingStatus::Finished
+        } else {
+            let ratio = self.page as f32 / self.total_page.max(1) as f32;
+            fmr_explorer::ReadingStatus::Reading(ratio.min(1.0))
         }
     }
+
+    pub fn with_text_position(mut self, position: fmr_reader::TextPosition) -> Self {
+        self.text_position = Some(position);
+        self
+    }
 }
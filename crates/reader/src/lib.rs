@@ -1,10 +1,12 @@
 pub mod loader;
 mod paged;
 mod sizer;
+mod text;
 mod vertical;
 pub use paged::*;
 use serde::{Deserialize, Serialize};
 pub use sizer::*;
+pub use text::*;
 pub use vertical::*;
 
 use fmr_frame::TextureViewState;
@@ -14,6 +16,8 @@ use fmr_frame::TextureViewState;
 pub struct ReaderSetting {
     pub mode: ReaderMode,
     pub paged: ReaderPagedSetting,
+    pub vertical: ReaderVerticalSetting,
+    pub text: ReaderTextSetting,
 
     pub sizer: Sizer,
     pub scroll_per_arrow: i32,
@@ -25,6 +29,8 @@ impl Default for ReaderSetting {
         Self {
             mode: Default::default(),
             paged: Default::default(),
+            vertical: Default::default(),
+            text: Default::default(),
             sizer: Sizer::default(),
             scroll_per_page: 900,
             scroll_per_arrow: 300,
@@ -38,21 +44,35 @@ pub struct ReaderPagedSetting {
     pub read_from_right: bool,
 }
 
-#[derive(Clone, PartialEq, Eq, Deserialize, Serialize, Debug)]
+#[derive(Clone, PartialEq, Eq, Deserialize, Serialize, Default, Debug)]
+#[serde(default)]
+pub struct ReaderVerticalSetting {
+    /// append next and prepend previous folder when scrolling near the end.
+    pub continuous: bool,
+}
+
+#[derive(Clone, PartialEq, Eq, Deserialize, Serialize, Default, Debug)]
 pub enum ReaderMode {
     Vertical,
+    #[default]
     Paged,
-}
-
-impl Default for ReaderMode {
-    fn default() -> Self {
-        Self::Paged
-    }
+    /// reflowable text, used for books without image.
+    Text,
 }
 
 pub struct Reader {
     pub images: Vec<TextureViewState>,
     pub state: ReaderModeState,
+    pub book: BookInfo,
+    /// chapters of text book, shown by [`ReaderModeState::Text`].
+    pub text: Vec<TextChapter>,
+}
+
+/// Metadata read from the opened book, overriding the setting when it's some.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct BookInfo {
+    pub title: Option<String>,
+    pub read_from_right: Option<bool>,
 }
 
 impl Reader {
@@ -60,6 +80,8 @@ impl Reader {
         Self {
             images,
             state: mode,
+            book: BookInfo::default(),
+            text: Vec::new(),
         }
     }
 
@@ -74,7 +96,8 @@ impl Reader {
     pub fn handle_event(&mut self, event: &egui::Event) -> bool {
         match &mut self.state {
             ReaderModeState::Vertical(state) => state.handle_event(event),
-            ReaderModeState::Paged(state) => state.handle_event(&self.images, event),
+            ReaderModeState::Paged(state) => state.handle_event(&mut self.images, event),
+            ReaderModeState::Text(state) => state.handle_event(event),
         }
     }
 }
@@ -82,6 +105,7 @@ impl Reader {
 pub enum ReaderModeState {
     Vertical(VerticalReaderState),
     Paged(PagedReaderState),
+    Text(TextReaderState),
 }
 
 impl ReaderModeState {
@@ -111,34 +135,99 @@ impl ReaderModeState {
     pub fn is_paged(&self) -> bool {
         matches!(self, Self::Paged(..))
     }
+
+    /// Returns `true` if the manga reader mode is [`Text`].
+    ///
+    /// [`Text`]: ReaderMode::Text
+    #[must_use]
+    pub fn is_text(&self) -> bool {
+        matches!(self, Self::Text(..))
+    }
 }
 
 pub struct ReaderView<'a> {
     pub reader: &'a mut Reader,
     pub setting: &'a ReaderSetting,
+    chapter: Option<(String, String)>,
+    prev_chapter: Option<VerticalChapter<'a>>,
+    next_chapter: Option<VerticalChapter<'a>>,
 }
 
 impl<'a> ReaderView<'a> {
     pub fn new(reader: &'a mut Reader, setting: &'a ReaderSetting) -> Self {
-        Self { reader, setting }
+        Self {
+            reader,
+            setting,
+            chapter: None,
+            prev_chapter: None,
+            next_chapter: None,
+        }
+    }
+
+    /// Show reader's images as chapter with `id` and `name`, only used in vertical mode.
+    pub fn chapter(mut self, id: String, name: String) -> Self {
+        self.chapter = Some((id, name));
+        self
+    }
+
+    /// Chapter shown before reader's images, only used in vertical mode.
+    pub fn prev_chapter(mut self, chapter: VerticalChapter<'a>) -> Self {
+        self.prev_chapter = Some(chapter);
+        self
+    }
+
+    /// Chapter shown after reader's images, only used in vertical mode.
+    pub fn next_chapter(mut self, chapter: VerticalChapter<'a>) -> Self {
+        self.next_chapter = Some(chapter);
+        self
     }
 
     pub fn show(self, ui: &mut egui::Ui) -> egui::Response {
-        let Self { reader, setting } = self;
+        let Self {
+            reader,
+            setting,
+            chapter,
+            prev_chapter,
+            next_chapter,
+        } = self;
         match &mut reader.state {
             ReaderModeState::Vertical(state) => {
-                state.sizer = setting.sizer.clone();
+                state.sizer = setting.sizer;
                 state.scroll_state.scroll_per_page = setting.scroll_per_page as f32;
                 state.scroll_state.scroll_per_arrow = setting.scroll_per_arrow as f32;
-                VerticalReader::new(&mut reader.images, state).show(ui)
+
+                match chapter {
+                    Some((id, name)) => {
+                        let current = VerticalChapter {
+                            id,
+                            name,
+                            images: &mut reader.images,
+                        };
+                        let chapters = prev_chapter
+                            .into_iter()
+                            .chain(Some(current))
+                            .chain(next_chapter)
+                            .collect();
+
+                        VerticalReader::with_chapters(chapters, state).show(ui)
+                    }
+                    None => VerticalReader::new(&mut reader.images, state).show(ui),
+                }
             }
             ReaderModeState::Paged(state) => {
-                state.sizer = setting.sizer.clone();
-                state.read_from_right = setting.paged.read_from_right;
+                state.sizer = setting.sizer;
+                state.read_from_right = reader
+                    .book
+                    .read_from_right
+                    .unwrap_or(setting.paged.read_from_right);
                 state.scroll.scroll_per_page = setting.scroll_per_page as f32;
                 state.scroll.scroll_per_arrow = setting.scroll_per_arrow as f32;
                 PagedReader::new(&mut reader.images, state).show(ui)
             }
+            ReaderModeState::Text(state) => {
+                state.setting = setting.text.clone();
+                TextReader::new(&reader.text, state).show(ui)
+            }
         }
     }
 }
@@ -9,10 +9,21 @@ use futures::{Future, FutureExt};
 use parking_lot::{Mutex, RwLock};
 use tokio::sync::{watch, OwnedSemaphorePermit, Semaphore};
 
-use crate::Reader;
-use fmr_core::{spawn_and_abort_on_drop, split_and_interleave_at, AbortOnDropHandle};
+use crate::{BookInfo, Reader, ReaderModeState, Sizer, TextChapter};
+use fmr_core::{
+    exclude::ExcludeRules,
+    password::Passwords,
+    path::NameSorterType,
+    source::{CombinedSource, FolderSource, PageSource, PasswordRequired},
+    spawn_and_abort_on_drop, split_and_interleave_at, AbortOnDropHandle,
+};
+use fmr_egui::tools::{
+    archive_dir::{ArchiveDir, ArchivePath},
+    zip::NameEncoding,
+};
 use fmr_frame::{
     EguiSplittedImageData, ImageData, LoadingTexture, TextureOption, TextureViewState,
+    UnsupportedTexture,
 };
 
 pub struct ReaderLoader {
@@ -21,14 +32,182 @@ pub struct ReaderLoader {
     pub ctx: egui::Context,
     pub setting_receiver: watch::Receiver<ReaderLoaderSetting>,
     pub is_done_initial_loading: Arc<AtomicBool>,
+    /// number of page decoded concurrently.
+    pub decode_permits: usize,
+    pub passwords: Passwords,
+    /// rules excluding junk pages of folder and archive.
+    pub exclude: ExcludeRules,
+    /// tried first for zip entry name that isn't UTF-8.
+    pub zip_name_encoding: NameEncoding,
+    /// order of pages by their name.
+    pub page_sorter: NameSorterType,
+    /// books read one after another as a single book instead of `path` when not empty.
+    pub combined: Vec<PathBuf>,
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Clone, Copy, Debug)]
 pub struct ReaderLoaderSetting {
     pub index: usize,
     pub preload_prev: usize,
     pub preload_next: usize,
     pub texture_option: TextureOption,
+    pub render_target: RenderTarget,
+}
+
+/// Where pages are shown, used to rasterize vector page to the size it's shown.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct RenderTarget {
+    pub sizer: Sizer,
+    /// size available for the page, pages keep their own size when it's zero.
+    pub available: [u32; 2],
+    /// side which is fitted by the sizer, vertical reader only fits the width.
+    pub fit_range: [bool; 2],
+    /// pixels per point of the screen, vector page is rasterized with it to stay sharp.
+    pub pixels_per_point: f32,
+}
+
+impl Default for RenderTarget {
+    fn default() -> Self {
+        Self {
+            sizer: Sizer::default(),
+            available: [0, 0],
+            fit_range: [true, true],
+            pixels_per_point: 1.0,
+        }
+    }
+}
+
+impl RenderTarget {
+    /// Size of page with original `size` when it's shown.
+    pub fn size_of(&self, size: [f32; 2]) -> [u32; 2] {
+        let scale = if self.available.contains(&0) {
+            1.0
+        } else {
+            let available = self.available.map(|it| it as f32);
+            self.sizer
+                .calc_range(size.into(), available.into(), self.fit_range)
+        };
+
+        size.map(|it| (it * scale).round().max(1.0) as u32)
+    }
+
+    /// Size in pixels of page with original `size` when it's shown.
+    pub fn pixel_size_of(&self, size: [f32; 2]) -> [u32; 2] {
+        self.size_of(size)
+            .map(|it| (it as f32 * self.pixels_per_point).round().max(1.0) as u32)
+    }
+}
+
+// vector page and the size in pixels it's rasterized to.
+#[cfg(feature = "svg")]
+type RenderedSvg = Arc<Mutex<Option<(fmr_frame::SvgImage, [u32; 2])>>>;
+
+/// Page returned by opener of [`ReaderLoader::schedule`].
+pub enum LoaderPage {
+    Image(ImageData),
+    /// video at path, shown with the image as its poster until played.
+    #[cfg(feature = "video")]
+    Video(PathBuf, ImageData),
+    /// vector image rasterized with [`RenderTarget`] when it's loaded.
+    #[cfg(feature = "svg")]
+    Svg(fmr_frame::SvgImage),
+    /// page that can't be shown, with its size and the reason shown in its place.
+    Unsupported([f32; 2], String),
+}
+
+impl From<ImageData> for LoaderPage {
+    fn from(image: ImageData) -> Self {
+        Self::Image(image)
+    }
+}
+
+impl LoaderPage {
+    /// Returns `true` if file at `path` can be opened as a page.
+    pub fn can_open(path: &std::path::Path) -> bool {
+        let can_read = ImageData::can_read(&path.to_string_lossy());
+        #[cfg(feature = "video")]
+        let can_read = can_read || fmr_frame::is_video(path);
+
+        can_read || fmr_frame::Reader::open(path).is_ok()
+    }
+
+    /// Open page from file at `path`.
+    pub async fn open(path: PathBuf) -> Option<Self> {
+        #[cfg(feature = "video")]
+        if fmr_frame::is_video(&path) {
+            let poster = fmr_frame::load_video_frame(&path).ok()?;
+            return Some(Self::Video(path, ImageData::StaticImage(poster)));
+        }
+
+        #[cfg(feature = "svg")]
+        if fmr_frame::SvgImage::can_read(&path) {
+            return fmr_frame::SvgImage::open(&path).ok().map(Self::Svg);
+        }
+
+        let image_reader = fmr_frame::Reader::open(path).ok()?;
+        image_reader
+            .into_frames()
+            .into_collector()
+            .collect_as_option_vector()
+            .await
+            .map(Self::from)
+    }
+
+    /// Decode page named `name` read from archive.
+    #[cfg_attr(not(feature = "svg"), allow(unused_variables))]
+    pub async fn from_memory(name: &str, data: Option<Vec<u8>>) -> Option<Self> {
+        #[cfg(feature = "svg")]
+        if fmr_frame::SvgImage::can_read(name) {
+            let svg = fmr_frame::SvgImage::from_memory(&data?);
+            return svg.ok().map(Self::Svg);
+        }
+
+        fmr_egui::tools::image::load_image_from_memory_as_option(data)
+            .await
+            .map(Self::from)
+    }
+}
+
+// page of `PageSource` to be loaded.
+enum SourceData {
+    Local(PathBuf),
+    Shared(Box<dyn PageSource>),
+    Read(std::io::Result<Vec<u8>>),
+}
+
+// size of placeholder of page that can't be read, same as A4 page of PDF.
+const UNREADABLE_PAGE_SIZE: [f32; 2] = [595.0, 842.0];
+
+// read `name` from `source`, asking password of `archive` until it's right or the prompt is
+// dismissed.
+async fn read_with_password(
+    mut source: Box<dyn PageSource>,
+    mut name: String,
+    archive: &std::path::Path,
+    passwords: &Passwords,
+) -> std::io::Result<Vec<u8>> {
+    let mut rejected = passwords.get(archive);
+
+    loop {
+        let read = tokio::task::spawn_blocking(move || {
+            let data = source.read(&name);
+            (source, name, data)
+        });
+        let data;
+        (source, name, data) = read.await.map_err(std::io::Error::other)?;
+
+        match data {
+            Err(err) if PasswordRequired::is(&err) => {
+                let Some(password) = passwords.request(archive, rejected.as_deref()).await else {
+                    return Err(err);
+                };
+
+                source.set_password(&password);
+                rejected = Some(password);
+            }
+            data => return data,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -37,6 +216,24 @@ struct LoaderEntry {
     name: String,
     item: Weak<Mutex<TextureViewState>>,
     handle: Arc<Mutex<Option<AbortOnDropHandle<()>>>>,
+    #[cfg(feature = "svg")]
+    svg: RenderedSvg,
+}
+
+impl LoaderEntry {
+    // rasterized vector page that doesn't match the current render target anymore.
+    #[cfg(feature = "svg")]
+    fn needs_render(&self, target: &RenderTarget) -> bool {
+        match &mut *self.svg.lock() {
+            Some((svg, size)) => {
+                let target = target.pixel_size_of(svg.size());
+                let changed = *size != target;
+                *size = target;
+                changed
+            }
+            None => false,
+        }
+    }
 }
 
 impl ReaderLoaderSetting {
@@ -58,13 +255,14 @@ impl ReaderLoaderSetting {
 
 impl ReaderLoader {
     pub async fn load(self) {
+        if !self.combined.is_empty() {
+            let paths = self.combined.clone();
+            return self.load_combined(paths).await;
+        }
+
         let open_path = self.path.clone();
         if open_path.exists() {
-            let path = if open_path.is_file()
-                && image::ImageFormat::from_path(&open_path)
-                    .map(|it| it.can_read())
-                    .unwrap_or(false)
-            {
+            let path = if open_path.is_file() && ImageData::can_read(&open_path.to_string_lossy()) {
                 open_path.parent().unwrap().to_path_buf()
             } else {
                 open_path.clone()
@@ -76,6 +274,17 @@ impl ReaderLoader {
             } else if path.is_dir() {
                 self.load_folder(path).await;
             };
+        } else if let Some(dir) = ArchiveDir::open_path(&open_path, self.zip_name_encoding) {
+            self.mark_done_initial_loading();
+            self.load_archive_dir(dir).await;
+        }
+    }
+
+    // archive pages are read from, password is remembered by it.
+    fn archive_path(&self) -> PathBuf {
+        match ArchivePath::from_path(&self.path) {
+            Some(path) => path.archive,
+            None => self.path.clone(),
         }
     }
 
@@ -85,121 +294,351 @@ impl ReaderLoader {
     }
 
     pub async fn load_folder(self, path: PathBuf) {
-        let mut dir = match tokio::fs::read_dir(path).await {
-            Ok(dir) => dir,
-            Err(_) => return,
-        };
+        self.load_source(FolderSource::new(path)).await;
+    }
 
-        let mut entries = vec![];
-        while let Ok(Some(it)) = dir.next_entry().await {
-            let can_read = ImageData::can_read(&it.file_name().to_string_lossy());
-            let can_read = can_read || fmr_frame::Reader::open(it.path()).is_ok();
+    pub async fn load_file(self, path: PathBuf) {
+        if is_text(&path) {
+            self.mark_done_initial_loading();
+            return self.load_text(path).await;
+        }
 
-            if can_read {
-                entries.push(it);
+        #[cfg(feature = "pdf")]
+        if fmr_egui::tools::pdf::is_pdf(&path) {
+            self.mark_done_initial_loading();
+            return self.load_pdf(path).await;
+        }
+
+        if let Ok(file) = fmr_egui::tools::zip::SharedFile::open(&path) {
+            self.mark_done_initial_loading();
+            if let Ok(mut zip) = zip::ZipArchive::new(file) {
+                #[allow(clippy::needless_return)]
+                return match fmr_egui::tools::epub::Epub::from_zip(&mut zip) {
+                    Some(epub) if !epub.pages.is_empty() => self.load_epub(zip, epub).await,
+                    Some(epub) => self.load_epub_text(zip, epub).await,
+                    _ => self.load_zip(zip).await,
+                };
             }
         }
+
+        #[cfg(feature = "libarchive")]
+        if fmr_egui::tools::archive::can_read(&path) {
+            self.mark_done_initial_loading();
+            self.load_file_archive(path).await
+        }
+    }
+
+    #[tracing::instrument(skip(self, zip))]
+    pub async fn load_zip<R>(self, zip: zip::ZipArchive<R>)
+    where
+        R: Read + std::io::Seek + Clone + Send + 'static,
+    {
+        let source = fmr_egui::tools::zip::ZipSource::new(zip, self.zip_name_encoding);
+        self.load_source(source).await;
+    }
+
+    /// Load images of directory inside archive, including its subdirectories.
+    #[tracing::instrument(skip(self, dir))]
+    pub async fn load_archive_dir(self, dir: ArchiveDir) {
+        self.load_source(dir).await;
+    }
+
+    /// Load folders and archives at `paths` as one book, in the order of `paths`.
+    #[tracing::instrument(skip(self))]
+    pub async fn load_combined(self, paths: Vec<PathBuf>) {
+        let encoding = self.zip_name_encoding;
+        let parts = tokio::task::spawn_blocking(move || {
+            paths
+                .into_iter()
+                .filter_map(|it| {
+                    let source = match fmr_egui::tools::source::open(&it, encoding) {
+                        Some(source) => source,
+                        // directory inside archive.
+                        None => match ArchiveDir::open_path(&it, encoding) {
+                            Some(dir) => Box::new(dir),
+                            None => {
+                                log::warn!("skipping {:?} that can't be opened", it);
+                                return None;
+                            }
+                        },
+                    };
+
+                    // password of the part is keyed by its own archive.
+                    let archive = ArchivePath::from_path(&it).map_or(it, |it| it.archive);
+                    Some((archive, source))
+                })
+                .collect::<Vec<_>>()
+        })
+        .await
+        .unwrap_or_default();
+
+        let source = CombinedSource::new(parts).with_sorter(self.page_sorter.clone());
+        self.load_source_with(source, true).await;
+    }
+
+    /// Load pages listed by `source` ordered by their name, page in file system is opened
+    /// from its path.
+    pub async fn load_source<S: PageSource + 'static>(self, source: S) {
+        self.load_source_with(source, false).await;
+    }
+
+    // same as `load_source`, but pages keep the order listed by `source` if `keep_order`.
+    #[tracing::instrument(skip(self, source))]
+    async fn load_source_with<S: PageSource + 'static>(self, mut source: S, keep_order: bool) {
+        let rules = self.exclude.clone();
+        let listed = tokio::task::spawn_blocking(move || {
+            let names = source.entries().map(|entries| {
+                let names = entries
+                    .into_iter()
+                    .map(|it| it.name)
+                    .filter(|it| match source.local_path(it) {
+                        Some(path) => LoaderPage::can_open(&path),
+                        None => ImageData::can_read(it),
+                    })
+                    .collect();
+
+                fmr_egui::tools::source::included_images(&mut source, names, &rules)
+            });
+            (source, names)
+        })
+        .await;
+
+        let (source, names) = match listed {
+            Ok((source, Ok(names))) => (source, names),
+            Ok((_, Err(err))) => {
+                log::error!("failed to list pages: {}", err);
+                return;
+            }
+            Err(_) => return,
+        };
         self.mark_done_initial_loading();
+        self.load_names(source, names, keep_order).await;
+    }
 
-        entries.sort_by(|a, b| fmr_core::path::compare_natural(&a.file_name(), &b.file_name()));
-        let map = entries
+    // load pages `names` of `source`, they keep the given order if `keep_order`.
+    async fn load_names<S: PageSource + 'static>(
+        self,
+        mut source: S,
+        names: Vec<String>,
+        keep_order: bool,
+    ) {
+        let map = names
             .iter()
             .enumerate()
-            .map(|(i, it)| (i, it.path().to_string_lossy().to_string()))
+            .map(|(i, it)| match source.local_path(it) {
+                Some(path) => (i, path.to_string_lossy().to_string()),
+                None => (i, it.clone()),
+            })
             .collect();
 
-        self.schedule(map, move |index| {
-            let it = entries[index].path();
+        let archive = self.archive_path();
+        let passwords = self.passwords.clone();
+
+        let opener = move |index: usize| {
+            let name = names[index].clone();
+            let archive = source.archive_of(&name).unwrap_or_else(|| archive.clone());
+            if let Some(password) = passwords.get(&archive) {
+                source.set_password(&password);
+            }
+
+            // read in the loading task when the source can be cloned, so pages are read in
+            // parallel instead of one by one here.
+            let data = match source.local_path(&name) {
+                Some(path) => SourceData::Local(path),
+                None => match source.try_clone() {
+                    Some(source) => SourceData::Shared(source),
+                    None => SourceData::Read(source.read(&name)),
+                },
+            };
+
+            let passwords = passwords.clone();
             async move {
-                let image_reader = fmr_frame::Reader::open(it);
-                if let Ok(image_reader) = image_reader {
-                    image_reader
-                        .into_frames()
-                        .into_collector()
-                        .collect_as_option_vector()
-                        .await
-                } else {
-                    None
+                let data = match data {
+                    SourceData::Local(path) => return LoaderPage::open(path).await,
+                    SourceData::Shared(source) => {
+                        read_with_password(source, name.clone(), &archive, &passwords).await
+                    }
+                    SourceData::Read(data) => data,
+                };
+
+                match data {
+                    Ok(data) => LoaderPage::from_memory(&name, Some(data)).await,
+                    // show why it can't be read instead of loading forever.
+                    Err(err) => Some(LoaderPage::Unsupported(
+                        UNREADABLE_PAGE_SIZE,
+                        err.to_string(),
+                    )),
                 }
             }
+        };
+
+        if keep_order {
+            self.schedule_in_order(map, opener).await;
+        } else {
+            self.schedule(map, opener).await;
+        }
+    }
+
+    /// Load pages of `epub` in its spine order.
+    #[tracing::instrument(skip(self, zip, epub))]
+    pub async fn load_epub<R>(self, zip: zip::ZipArchive<R>, epub: fmr_egui::tools::epub::Epub)
+    where
+        R: Read + std::io::Seek + Clone + Send + 'static,
+    {
+        self.reader.write().book = BookInfo {
+            title: epub.title,
+            read_from_right: epub.read_from_right,
+        };
+
+        let (encoding, rules) = (self.zip_name_encoding, self.exclude.clone());
+        let listed = tokio::task::spawn_blocking(move || {
+            let mut source = fmr_egui::tools::zip::ZipSource::new(zip, encoding);
+            let names = fmr_egui::tools::source::included_images(&mut source, epub.pages, &rules);
+            (source, names)
         })
         .await;
+
+        if let Ok((source, names)) = listed {
+            self.load_names(source, names, true).await;
+        }
     }
 
-    pub async fn load_file(self, path: PathBuf) {
-        if let Ok(file) = std::fs::File::open(path.clone()) {
-            self.mark_done_initial_loading();
-            if let Ok(zip) = zip::ZipArchive::new(file) {
-                #[allow(clippy::needless_return)]
-                return self.load_zip(zip).await;
+    /// Load reflowable text of `epub`, one chapter for each document in the spine.
+    #[tracing::instrument(skip(self, zip, epub))]
+    pub async fn load_epub_text<R: Read + std::io::Seek>(
+        self,
+        mut zip: zip::ZipArchive<R>,
+        epub: fmr_egui::tools::epub::Epub,
+    ) {
+        let mut chapters = vec![];
+        for path in &epub.documents {
+            let Some((title, text)) = fmr_egui::tools::epub::read_document_text(&mut zip, path)
+            else {
+                continue;
+            };
+
+            if !text.is_empty() {
+                let title = title.unwrap_or_else(|| format!("Chapter {}", chapters.len() + 1));
+                chapters.push(TextChapter { title, text });
             }
         }
 
-        #[cfg(feature = "libarchive")]
-        if fmr_egui::tools::archive::can_read(&path) {
-            self.mark_done_initial_loading();
-            self.load_file_archive(path).await
-        }
+        let book = BookInfo {
+            title: epub.title,
+            read_from_right: epub.read_from_right,
+        };
+        self.set_text(book, chapters);
     }
 
-    #[tracing::instrument(skip(self, zip))]
-    pub async fn load_zip<R: Read + std::io::Seek + 'static>(self, mut zip: zip::ZipArchive<R>) {
-        let names = zip
-            .file_names()
-            .filter(|it| ImageData::can_read(it))
-            .map(|it| it.to_string())
-            .collect::<Vec<_>>();
+    /// Load plain text file as a book with single chapter.
+    #[tracing::instrument(skip(self))]
+    pub async fn load_text(self, path: PathBuf) {
+        let Ok(bytes) = tokio::fs::read(&path).await else {
+            return;
+        };
 
-        let map = names.iter().cloned().enumerate().collect();
+        let title = path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let chapter = TextChapter::from_bytes(title, &bytes);
 
-        self.schedule(map, move |index| {
-            let name = names[index].clone();
+        self.set_text(BookInfo::default(), vec![chapter]);
+    }
 
-            fmr_egui::tools::zip::load_image_with_ref(&mut zip, |zip| {
-                let name = name;
-                zip.by_name(&name)
-            })
-        })
-        .await;
+    fn set_text(&self, book: BookInfo, chapters: Vec<TextChapter>) {
+        let mut reader = self.reader.write();
+        reader.book = book;
+        reader.text = chapters;
+        if !reader.state.is_text() {
+            reader.state = ReaderModeState::Text(Default::default());
+        }
+
+        self.ctx.request_repaint();
     }
 
-    #[cfg(feature = "libarchive")]
+    /// Load scanned pages of PDF in page order.
+    #[cfg(feature = "pdf")]
     #[tracing::instrument(skip(self))]
-    pub async fn load_file_archive(self, path: PathBuf) {
-        let open = || fmr_egui::tools::archive::open(&path).ok();
-
-        let names = if let Some(reader) = open() {
-            reader
-                .into_iter()
-                .filter_map(|it| it.ok())
-                .filter(|it| it.is_file())
-                .filter_map(|it| it.pathname())
-                .collect::<Vec<_>>()
-        } else {
-            return;
+    pub async fn load_pdf(self, path: PathBuf) {
+        use fmr_egui::tools::pdf::{PdfBook, PdfPage};
+
+        let book = tokio::task::spawn_blocking(move || PdfBook::open(&path)).await;
+        let book = match book {
+            Ok(Ok(book)) => Arc::new(book),
+            Ok(Err(err)) => {
+                log::error!("failed to open pdf: {}", err);
+                return;
+            }
+            Err(_) => return,
         };
 
-        let map = names.iter().cloned().enumerate().collect::<Vec<_>>();
+        self.reader.write().book = BookInfo {
+            title: book.title(),
+            read_from_right: None,
+        };
 
-        self.schedule(map, move |index| {
-            let name = names[index].clone();
+        let map = (0..book.len())
+            .map(|index| (index, PdfBook::page_name(index)))
+            .collect();
 
-            fmr_egui::tools::archive::load_path_to_image_from_opt(open(), name)
+        self.schedule_in_order(map, move |index| {
+            let book = book.clone();
+            async move {
+                let page = tokio::task::spawn_blocking(move || book.load_page(index)).await;
+                match page.ok()? {
+                    PdfPage::Image(image) => Some(LoaderPage::Image(image)),
+                    PdfPage::Unsupported { size, reason } => {
+                        Some(LoaderPage::Unsupported(size, reason))
+                    }
+                }
+            }
         })
         .await;
     }
 
+    #[cfg(feature = "libarchive")]
+    #[tracing::instrument(skip(self))]
+    pub async fn load_file_archive(self, path: PathBuf) {
+        // read the whole preload window in one pass.
+        let read_ahead = self.setting_receiver.borrow().preload_next;
+        let source = fmr_egui::tools::archive::ArchiveSource::new(path)
+            .with_read_ahead(read_ahead)
+            .with_sorter(self.page_sorter.clone());
+        self.load_source(source).await;
+    }
+
+    /// Schedule pages named by `map` ordered by their name, `opener` is called with the
+    /// index in `map` to load the page.
+    pub async fn schedule<F, R>(self, mut map: Vec<(usize, String)>, opener: F)
+    where
+        F: FnMut(usize) -> R,
+        R: Future<Output = Option<LoaderPage>> + Send + 'static,
+    {
+        map.sort_by(|(_, a), (_, b)| (self.page_sorter)(a, b));
+        self.schedule_in_order(map, opener).await;
+    }
+
+    /// Same as [`schedule`] but keep the order of `map`.
+    ///
+    /// [`schedule`]: Self::schedule
     #[tracing::instrument(skip(self, map, opener))]
-    pub async fn schedule<F, R>(mut self, mut map: Vec<(usize, String)>, mut opener: F)
+    pub async fn schedule_in_order<F, R>(mut self, map: Vec<(usize, String)>, mut opener: F)
     where
         F: FnMut(usize) -> R,
-        R: Future<Output = Option<ImageData>> + Send + 'static,
+        R: Future<Output = Option<LoaderPage>> + Send + 'static,
     {
-        // let index= reader.
-        map.sort_by(|(_, a), (_, b)| natord::compare_ignore_case(a, b));
         let mut entries = vec![];
 
-        self.reader.write().images.clear();
+        {
+            let mut reader = self.reader.write();
+            reader.images.clear();
+            // text mode only shows text book.
+            if reader.state.is_text() {
+                reader.state = ReaderModeState::paged();
+            }
+        }
         for (opener_index, name) in map {
             let texture = LoadingTexture::new(name.clone(), None);
             let item = Arc::new(Mutex::new(TextureViewState::Loading(texture)));
@@ -212,10 +651,12 @@ impl ReaderLoader {
                 name,
                 item: Arc::downgrade(&item),
                 handle: Arc::new(Mutex::new(None)),
+                #[cfg(feature = "svg")]
+                svg: Arc::new(Mutex::new(None)),
             });
         }
 
-        let semaphore = Arc::new(Semaphore::new(2));
+        let semaphore = Arc::new(Semaphore::new(self.decode_permits.max(1)));
         let mut setting_receiver = self.setting_receiver.clone();
         // let Self {
         //     path: _,
@@ -254,7 +695,7 @@ impl ReaderLoader {
         semaphore: Arc<Semaphore>,
     ) where
         F: FnMut(usize) -> R,
-        R: Future<Output = Option<ImageData>> + Send + 'static,
+        R: Future<Output = Option<LoaderPage>> + Send + 'static,
     {
         let Self {
             ctx,
@@ -277,6 +718,10 @@ impl ReaderLoader {
                 // remove handle from item that isnt in preload or the item is None.
                 (_, item) => {
                     *entry.handle.lock() = None;
+                    #[cfg(feature = "svg")]
+                    {
+                        *entry.svg.lock() = None;
+                    }
 
                     // make item that is some loading again.
                     if let Some(item) = item {
@@ -297,11 +742,53 @@ impl ReaderLoader {
                 async move {
                     let _permit = permit;
                     log::trace!("acquired permit for {} {}", entry.name, entry.opener_index);
-                    let texture =
-                        Self::load_texture(entry.name, ctx.clone(), fut, texture_option).await;
+                    let texture = match fut.await {
+                        Some(LoaderPage::Image(image)) => {
+                            let fut = std::future::ready(Some(image));
+                            Self::load_texture(entry.name, ctx.clone(), fut, texture_option)
+                                .await
+                                .map(TextureViewState::from)
+                        }
+                        #[cfg(feature = "video")]
+                        Some(LoaderPage::Video(path, poster)) => {
+                            let fut = std::future::ready(Some(poster));
+                            Self::load_texture(entry.name, ctx.clone(), fut, texture_option)
+                                .await
+                                .map(|poster| {
+                                    let poster = poster.first_frame().clone();
+                                    let video = fmr_frame::VideoTextureViewState::new(path, poster);
+                                    TextureViewState::Video(video)
+                                })
+                        }
+                        #[cfg(feature = "svg")]
+                        Some(LoaderPage::Svg(svg)) => {
+                            let target = setting.render_target;
+                            let size = target.pixel_size_of(svg.size());
+                            *entry.svg.lock() = Some((svg.clone(), size));
+
+                            let image = svg.render(size).ok().map(ImageData::StaticImage);
+                            let fut = std::future::ready(image);
+                            Self::load_texture(entry.name, ctx.clone(), fut, texture_option)
+                                .await
+                                .map(|texture| match texture {
+                                    fmr_frame::TextureHandle::StaticTexture(handle) => {
+                                        let handle =
+                                            handle.with_pixels_per_point(target.pixels_per_point);
+                                        fmr_frame::TextureHandle::StaticTexture(handle)
+                                    }
+                                    texture => texture,
+                                })
+                                .map(TextureViewState::from)
+                        }
+                        Some(LoaderPage::Unsupported(size, reason)) => {
+                            let state = UnsupportedTexture::new(entry.name, size.into(), reason);
+                            Some(TextureViewState::Unsupported(state))
+                        }
+                        None => None,
+                    };
                     if let Some(texture) = texture {
                         if let Some(item) = entry.item.upgrade() {
-                            *item.lock() = texture.into();
+                            *item.lock() = texture;
                             ctx.request_repaint();
                         }
                     }
@@ -311,7 +798,12 @@ impl ReaderLoader {
             };
 
             let mut handle = entry.handle.lock();
-            if handle.is_none() && item.lock().is_loading() {
+            let should_load = handle.is_none() && item.lock().is_loading();
+            // shown page is kept until it's rasterized again.
+            #[cfg(feature = "svg")]
+            let should_load = should_load || entry.needs_render(&setting.render_target);
+
+            if should_load {
                 log::trace!("spawn {}", entry.name);
                 *handle = Some(spawn_and_abort_on_drop(loader()));
             }
@@ -365,3 +857,10 @@ impl ReaderLoader {
         Some(texture)
     }
 }
+
+/// Returns `true` if `path` is plain text file, shown in text mode.
+pub fn is_text(path: &std::path::Path) -> bool {
+    path.extension()
+        .map(|it| it.eq_ignore_ascii_case("txt"))
+        .unwrap_or(false)
+}
@@ -1,4 +1,7 @@
-use fmr_egui::{key::handle_key, Vec2Ext};
+use fmr_egui::{
+    key::{handle_key, handle_key_to_option},
+    Vec2Ext,
+};
 use fmr_frame::{SplittedTextureWidget, TextureView, TextureViewState};
 use fmr_scroll::{ScrollArea, ScrollState};
 
@@ -22,6 +25,9 @@ pub struct PagedReaderState {
     pub read_from_right: bool,
 
     pub index_text: HideTextState,
+
+    // index of the page shown in last frame, to pause its video after changing page.
+    pub(crate) shown_index: Option<usize>,
 }
 
 pub struct HideTextState {
@@ -84,10 +90,16 @@ impl PagedReaderState {
         }
     }
 
-    pub fn handle_event(&mut self, images: &[TextureViewState], event: &egui::Event) -> bool {
+    pub fn handle_event(&mut self, images: &mut [TextureViewState], event: &egui::Event) -> bool {
         let mut handled = false;
         let mut first = true;
 
+        if let Some(image) = images.get_mut(self.index) {
+            if Self::handle_animation_event(image, event) {
+                return true;
+            }
+        }
+
         if self.scroll.handle_key_event(event) {
             return true;
         }
@@ -124,6 +136,48 @@ impl PagedReaderState {
 
         handled
     }
+
+    fn handle_animation_event(image: &mut TextureViewState, event: &egui::Event) -> bool {
+        let no_modifier = |it: &egui::Modifiers| it.is_none();
+
+        image
+            .with_animation(|animation| {
+                let pause = matches!(
+                    event,
+                    egui::Event::Key {
+                        key: egui::Key::Space,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } if modifiers.is_none()
+                );
+                if pause {
+                    animation.toggle_pause();
+                    return true;
+                }
+
+                let step = handle_key_to_option(
+                    true,
+                    event,
+                    egui::Key::Comma,
+                    egui::Key::Period,
+                    no_modifier,
+                    |step| animation.step(step as isize),
+                );
+
+                let speed = handle_key_to_option(
+                    true,
+                    event,
+                    egui::Key::OpenBracket,
+                    egui::Key::CloseBracket,
+                    no_modifier,
+                    |step| animation.change_speed_by(step),
+                );
+
+                step.is_some() || speed.is_some()
+            })
+            .unwrap_or(false)
+    }
 }
 
 impl<'a> PagedReader<'a> {
@@ -138,6 +192,13 @@ impl<'a> PagedReader<'a> {
     pub fn show(mut self, ui: &mut egui::Ui) -> egui::Response {
         let Self { images, state } = &mut self;
 
+        if let Some(shown) = state.shown_index.filter(|it| *it != state.index) {
+            if let Some(image) = images.get_mut(shown) {
+                image.pause_video();
+            }
+        }
+        state.shown_index = Some(state.index);
+
         let response = if state.index < images.len() {
             let image = &mut images[state.index];
 
@@ -180,6 +241,8 @@ impl<'a> PagedReader<'a> {
                 self.draw_index(ui);
             }
 
+            self.draw_animation_control(ui);
+
             output.inner
         } else {
             ui.centered_and_justified(|ui| ui.label("Loading")).inner
@@ -235,6 +298,61 @@ impl<'a> PagedReader<'a> {
         response
     }
 
+    fn draw_animation_control(&mut self, ui: &mut egui::Ui) {
+        let Self { state, images } = self;
+
+        let (Some(rect), Some(image)) = (state.scroll.inner_rect, images.get_mut(state.index))
+        else {
+            return;
+        };
+
+        // the control is in another layer, so check the pointer position directly.
+        let hovered = ui
+            .input(|input| input.pointer.hover_pos())
+            .is_some_and(|pos| rect.contains(pos));
+
+        image.with_animation(|animation| {
+            if !hovered && !animation.is_paused() {
+                return;
+            }
+
+            egui::Area::new(ui.id().with("animation_control"))
+                .fixed_pos(rect.center_bottom() - egui::vec2(0.0, 40.0))
+                .pivot(egui::Align2::CENTER_BOTTOM)
+                .show(ui.ctx(), |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            let is_playing = !animation.is_paused() && !animation.is_finished();
+
+                            let previous =
+                                ui.add_enabled(!animation.is_streamed(), egui::Button::new("⏴"));
+                            if previous.on_hover_text("Previous Frame (,)").clicked() {
+                                animation.step(-1);
+                            }
+                            let play = if is_playing { "⏸" } else { "▶" };
+                            if ui.button(play).on_hover_text("Pause (Space)").clicked() {
+                                animation.toggle_pause();
+                            }
+                            if ui.button("⏵").on_hover_text("Next Frame (.)").clicked() {
+                                animation.step(1);
+                            }
+                            ui.label(format!("{}/{}", animation.index() + 1, animation.len()));
+
+                            ui.separator();
+
+                            if ui.button("-").on_hover_text("Slower ([)").clicked() {
+                                animation.change_speed_by(-1);
+                            }
+                            ui.label(format!("{}x", animation.speed()));
+                            if ui.button("+").on_hover_text("Faster (])").clicked() {
+                                animation.change_speed_by(1);
+                            }
+                        });
+                    });
+                });
+        });
+    }
+
     fn draw_index(&mut self, ui: &mut egui::Ui) {
         let Self { state, images } = self;
 
@@ -0,0 +1,495 @@
+use std::sync::Arc;
+
+use egui::{
+    epaint::text::cursor::CCursor,
+    text::{LayoutJob, TextWrapping},
+    Color32, FontId, Galley, TextFormat,
+};
+use fmr_egui::key::handle_key_to_option;
+use serde::{Deserialize, Serialize};
+
+/// Chapter of reflowable text book.
+#[derive(Clone, Debug)]
+pub struct TextChapter {
+    pub title: String,
+    pub text: String,
+}
+
+impl TextChapter {
+    /// Decode plain text file, detecting UTF-8 and UTF-16 from its byte order mark.
+    /// Text without it that isn't UTF-8 is decoded with the legacy CJK or western encoding
+    /// whose decoded text looks the most like a text.
+    pub fn from_bytes(title: String, bytes: &[u8]) -> Self {
+        let text = match encoding_rs::Encoding::for_bom(bytes) {
+            Some((encoding, bom)) => encoding
+                .decode_without_bom_handling(&bytes[bom..])
+                .0
+                .into_owned(),
+            None => match std::str::from_utf8(bytes) {
+                Ok(text) => text.to_string(),
+                Err(_) => detect_encoding(bytes)
+                    .decode_without_bom_handling(bytes)
+                    .0
+                    .into_owned(),
+            },
+        };
+
+        Self {
+            title,
+            text: text.replace("\r\n", "\n"),
+        }
+    }
+}
+
+// encodings tried in order, the earlier one is used when they decode equally well.
+const LEGACY_ENCODINGS: &[&encoding_rs::Encoding] = &[
+    encoding_rs::SHIFT_JIS,
+    encoding_rs::GBK,
+    encoding_rs::WINDOWS_1252,
+];
+
+// decode the start of the text with each encoding and score the decoded chars by the
+// bytes they take, legacy text decoded with the wrong encoding gives replacement chars,
+// control chars, half-width katakana or private use chars instead of letters.
+fn detect_encoding(bytes: &[u8]) -> &'static encoding_rs::Encoding {
+    const SAMPLE_SIZE: usize = 64 * 1024;
+
+    let sample = &bytes[..bytes.len().min(SAMPLE_SIZE)];
+    let score = |char: char| match char {
+        '\0'..='\u{7F}' => 0,
+        '\u{C0}'..='\u{FF}' => 1,
+        '\u{3000}'..='\u{30FF}' | '\u{4E00}'..='\u{9FFF}' | '\u{FF01}'..='\u{FF5E}' => 2,
+        _ => -1,
+    };
+
+    LEGACY_ENCODINGS
+        .iter()
+        .copied()
+        .map(|encoding| {
+            let (text, ..) = encoding.decode_without_bom_handling(sample);
+            (encoding, text.chars().map(score).sum::<isize>())
+        })
+        // max_by_key returns the last maximum.
+        .rev()
+        .max_by_key(|(_, score)| *score)
+        .map(|(encoding, _)| encoding)
+        .unwrap_or(encoding_rs::UTF_8)
+}
+
+/// Position in text book, recorded in reading progress.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug, Serialize, Deserialize)]
+pub struct TextPosition {
+    pub chapter: usize,
+    /// number of char before the first shown line of the chapter.
+    pub offset: usize,
+}
+
+#[derive(Clone, PartialEq, Eq, Deserialize, Serialize, Debug)]
+#[serde(default)]
+pub struct ReaderTextSetting {
+    pub font_size: u32,
+    /// line height in percent of font size.
+    pub line_spacing: u32,
+    /// show text page by page instead of scrolling.
+    pub paginate: bool,
+}
+
+impl Default for ReaderTextSetting {
+    fn default() -> Self {
+        Self {
+            font_size: 20,
+            line_spacing: 160,
+            paginate: true,
+        }
+    }
+}
+
+enum TextAction {
+    Line(isize),
+    Page(isize),
+    Chapter(isize),
+}
+
+#[derive(PartialEq)]
+struct GalleyKey {
+    chapter: usize,
+    width: u32,
+    font_size: u32,
+    line_spacing: u32,
+    color: Color32,
+}
+
+pub struct TextReaderState {
+    pub position: TextPosition,
+    pub setting: ReaderTextSetting,
+    // shown text is moved to `position` on the next frame.
+    is_position_changed: bool,
+    actions: Vec<TextAction>,
+    galley: Option<(GalleyKey, Arc<Galley>)>,
+}
+
+impl Default for TextReaderState {
+    fn default() -> Self {
+        Self {
+            position: TextPosition::default(),
+            setting: ReaderTextSetting::default(),
+            is_position_changed: true,
+            actions: vec![],
+            galley: None,
+        }
+    }
+}
+
+impl TextReaderState {
+    // line are wrapped at this many font size to keep them readable on wide window.
+    const MAX_LINE_WIDTH: f32 = 45.0;
+    const MARGIN: f32 = 16.0;
+
+    /// Move to `position`, e.g. when it's restored from reading progress.
+    pub fn set_position(&mut self, position: TextPosition) {
+        self.position = position;
+        self.is_position_changed = true;
+    }
+
+    pub fn handle_event(&mut self, event: &egui::Event) -> bool {
+        let no_modifier = |it: &egui::Modifiers| it.is_none();
+        let command = |it: &egui::Modifiers| it.command_only();
+
+        let mut actions = vec![];
+        if self.setting.paginate {
+            for (up, down) in [
+                (egui::Key::ArrowUp, egui::Key::ArrowDown),
+                (egui::Key::ArrowLeft, egui::Key::ArrowRight),
+                (egui::Key::PageUp, egui::Key::PageDown),
+            ] {
+                actions.extend(handle_key_to_option(
+                    true,
+                    event,
+                    up,
+                    down,
+                    no_modifier,
+                    |it| TextAction::Page(it as isize),
+                ));
+            }
+        } else {
+            let keys = [
+                (
+                    egui::Key::ArrowUp,
+                    egui::Key::ArrowDown,
+                    TextAction::Line as fn(_) -> _,
+                ),
+                (egui::Key::PageUp, egui::Key::PageDown, TextAction::Page),
+                (
+                    egui::Key::ArrowLeft,
+                    egui::Key::ArrowRight,
+                    TextAction::Chapter,
+                ),
+            ];
+            for (up, down, action) in keys {
+                actions.extend(handle_key_to_option(
+                    true,
+                    event,
+                    up,
+                    down,
+                    no_modifier,
+                    |it| action(it as isize),
+                ));
+            }
+        }
+
+        actions.extend(handle_key_to_option(
+            true,
+            event,
+            egui::Key::ArrowLeft,
+            egui::Key::ArrowRight,
+            command,
+            |it| TextAction::Chapter(it as isize),
+        ));
+
+        if let egui::Event::Key {
+            key: egui::Key::Space,
+            pressed: true,
+            modifiers,
+            ..
+        } = event
+        {
+            if modifiers.is_none() || modifiers.shift_only() {
+                let step = if modifiers.shift { -1 } else { 1 };
+                actions.push(TextAction::Page(step));
+            }
+        }
+
+        let handled = !actions.is_empty();
+        self.actions.extend(actions);
+
+        handled
+    }
+
+    fn change_chapter(&mut self, chapters: &[TextChapter], step: isize) {
+        let chapter = self.position.chapter as isize + step;
+        if (0..chapters.len() as isize).contains(&chapter) {
+            self.set_position(TextPosition {
+                chapter: chapter as usize,
+                offset: 0,
+            });
+        }
+    }
+
+    fn galley(&mut self, ui: &egui::Ui, chapters: &[TextChapter], width: f32) -> Arc<Galley> {
+        let key = GalleyKey {
+            chapter: self.position.chapter,
+            width: width as u32,
+            font_size: self.setting.font_size,
+            line_spacing: self.setting.line_spacing,
+            color: ui.visuals().text_color(),
+        };
+
+        if let Some((cached, galley)) = &self.galley {
+            if *cached == key {
+                return galley.clone();
+            }
+        }
+
+        let font_size = key.font_size.max(1) as f32;
+        let mut job = LayoutJob {
+            wrap: TextWrapping {
+                max_width: key.width as f32,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        job.append(
+            &chapters[key.chapter].text,
+            0.0,
+            TextFormat {
+                font_id: FontId::proportional(font_size),
+                color: key.color,
+                line_height: Some(font_size * key.line_spacing as f32 / 100.0),
+                ..Default::default()
+            },
+        );
+
+        let galley = ui.fonts(|fonts| fonts.layout_job(job));
+        self.galley = Some((key, galley.clone()));
+
+        galley
+    }
+}
+
+pub struct TextReader<'a> {
+    chapters: &'a [TextChapter],
+    state: &'a mut TextReaderState,
+}
+
+impl<'a> TextReader<'a> {
+    pub fn new(chapters: &'a [TextChapter], state: &'a mut TextReaderState) -> Self {
+        Self { chapters, state }
+    }
+
+    pub fn show(self, ui: &mut egui::Ui) -> egui::Response {
+        let Self { chapters, state } = self;
+
+        if chapters.is_empty() {
+            return ui.spinner();
+        }
+        state.position.chapter = state.position.chapter.min(chapters.len() - 1);
+
+        let max_width = state.setting.font_size as f32 * TextReaderState::MAX_LINE_WIDTH;
+        let width = (ui.available_width() - TextReaderState::MARGIN * 2.0).clamp(1.0, max_width);
+
+        if state.setting.paginate {
+            Self::show_paged(ui, chapters, state, width)
+        } else {
+            Self::show_scroll(ui, chapters, state, width)
+        }
+    }
+
+    fn show_paged(
+        ui: &mut egui::Ui,
+        chapters: &[TextChapter],
+        state: &mut TextReaderState,
+        width: f32,
+    ) -> egui::Response {
+        let (rect, response) =
+            ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+
+        let footer_height = ui.text_style_height(&egui::TextStyle::Small);
+        let page_height = (rect.height() - TextReaderState::MARGIN * 3.0 - footer_height).max(1.0);
+
+        if let Some(pos) = response
+            .interact_pointer_pos()
+            .filter(|_| response.clicked())
+        {
+            let step = if pos.x < rect.center().x { -1 } else { 1 };
+            state.actions.push(TextAction::Page(step));
+        }
+
+        for action in std::mem::take(&mut state.actions) {
+            let galley = state.galley(ui, chapters, width);
+            let tops = page_tops(&galley, page_height);
+            let page = page_of(&tops, y_of(&galley, state.position.offset));
+
+            let step = match action {
+                TextAction::Line(step) | TextAction::Page(step) => step,
+                TextAction::Chapter(step) => {
+                    state.change_chapter(chapters, step);
+                    continue;
+                }
+            };
+
+            match page as isize + step {
+                // last page of previous chapter.
+                page if page < 0 => {
+                    if state.position.chapter > 0 {
+                        let chapter = state.position.chapter - 1;
+                        state.set_position(TextPosition {
+                            chapter,
+                            offset: chapters[chapter].text.chars().count(),
+                        });
+                    }
+                }
+                page if page as usize >= tops.len() => state.change_chapter(chapters, 1),
+                page => state.position.offset = offset_at(&galley, tops[page as usize]),
+            }
+        }
+
+        let galley = state.galley(ui, chapters, width);
+        let tops = page_tops(&galley, page_height);
+        let page = page_of(&tops, y_of(&galley, state.position.offset));
+        state.is_position_changed = false;
+
+        // start the page from its first line when resized or restored.
+        state.position.offset = offset_at(&galley, tops[page]);
+
+        let top = tops[page];
+        let bottom = tops.get(page + 1).copied().unwrap_or(galley.rect.max.y);
+
+        let text_rect = egui::Rect::from_min_size(
+            egui::pos2(
+                rect.center().x - width / 2.0,
+                rect.min.y + TextReaderState::MARGIN,
+            ),
+            egui::vec2(width, page_height),
+        );
+        let clip_rect = egui::Rect::from_min_size(
+            text_rect.min,
+            egui::vec2(width, (bottom - top).min(page_height)),
+        );
+
+        let painter = ui.painter_at(rect);
+        painter.with_clip_rect(clip_rect).galley(
+            text_rect.min - egui::vec2(0.0, top),
+            galley,
+            ui.visuals().text_color(),
+        );
+
+        let chapter = &chapters[state.position.chapter];
+        painter.text(
+            egui::pos2(rect.center().x, rect.max.y - TextReaderState::MARGIN),
+            egui::Align2::CENTER_BOTTOM,
+            format!("{}  {}/{}", chapter.title, page + 1, tops.len()),
+            egui::TextStyle::Small.resolve(ui.style()),
+            ui.visuals().weak_text_color(),
+        );
+
+        response
+    }
+
+    fn show_scroll(
+        ui: &mut egui::Ui,
+        chapters: &[TextChapter],
+        state: &mut TextReaderState,
+        width: f32,
+    ) -> egui::Response {
+        let mut delta = 0.0;
+        let line_height =
+            state.setting.font_size as f32 * state.setting.line_spacing as f32 / 100.0;
+        let page_height = ui.available_height() * 0.9;
+
+        for action in std::mem::take(&mut state.actions) {
+            match action {
+                TextAction::Line(step) => delta += step as f32 * line_height * 3.0,
+                TextAction::Page(step) => delta += step as f32 * page_height,
+                TextAction::Chapter(step) => state.change_chapter(chapters, step),
+            }
+        }
+
+        let galley = state.galley(ui, chapters, width);
+
+        let mut scroll = egui::ScrollArea::vertical()
+            .id_source(("text", state.position.chapter))
+            .auto_shrink([false, false]);
+        if state.is_position_changed {
+            state.is_position_changed = false;
+            scroll = scroll.vertical_scroll_offset(y_of(&galley, state.position.offset));
+        }
+
+        let mut change_chapter = 0;
+        let output = scroll.show(ui, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(TextReaderState::MARGIN);
+                ui.add(egui::Label::new(galley.clone()).selectable(false));
+                ui.add_space(TextReaderState::MARGIN);
+
+                ui.horizontal(|ui| {
+                    if state.position.chapter > 0 && ui.button("⏴ Previous Chapter").clicked() {
+                        change_chapter = -1;
+                    }
+                    if state.position.chapter + 1 < chapters.len()
+                        && ui.button("Next Chapter ⏵").clicked()
+                    {
+                        change_chapter = 1;
+                    }
+                });
+                ui.add_space(TextReaderState::MARGIN);
+            });
+
+            if delta != 0.0 {
+                ui.scroll_with_delta(egui::vec2(0.0, -delta));
+            }
+        });
+
+        let top = (output.state.offset.y - TextReaderState::MARGIN).max(0.0);
+        state.position.offset = offset_at(&galley, top);
+
+        if change_chapter != 0 {
+            state.change_chapter(chapters, change_chapter);
+        }
+
+        ui.interact(
+            output.inner_rect,
+            ui.id().with("text"),
+            egui::Sense::hover(),
+        )
+    }
+}
+
+// top of each page, so that a page shows only whole lines.
+fn page_tops(galley: &Galley, height: f32) -> Vec<f32> {
+    let mut tops = vec![0.0];
+    for row in &galley.rows {
+        let top = *tops.last().unwrap();
+        if row.rect.max.y - top > height && row.rect.min.y > top {
+            tops.push(row.rect.min.y);
+        }
+    }
+
+    tops
+}
+
+fn page_of(tops: &[f32], y: f32) -> usize {
+    tops.partition_point(|top| *top <= y + 0.5)
+        .saturating_sub(1)
+}
+
+fn y_of(galley: &Galley, offset: usize) -> f32 {
+    galley.pos_from_ccursor(CCursor::new(offset)).min.y
+}
+
+// char offset of the line at `y`.
+fn offset_at(galley: &Galley, y: f32) -> usize {
+    galley
+        .cursor_from_pos(egui::vec2(0.0, y + 0.5))
+        .ccursor
+        .index
+}
@@ -1,3 +1,5 @@
+use std::ops::RangeInclusive;
+
 use fmr_frame::{SplittedTextureWidget, TextureView, TextureViewState};
 
 use super::Sizer;
@@ -8,26 +10,169 @@ pub struct VerticalReaderState {
     pub scroll_state: ScrollState,
 
     pub sizer: Sizer,
+
+    // position of each chapter in previous frame as (id, top, bottom).
+    chapter_rects: Vec<(String, f32, f32)>,
+
+    // top position of each page in previous frame.
+    page_tops: Vec<f32>,
 }
 
 impl VerticalReaderState {
     pub fn handle_event(&mut self, event: &egui::Event) -> bool {
         self.scroll_state.handle_key_event(event)
     }
+
+    fn viewport_height(&self) -> Option<f32> {
+        self.scroll_state.inner_rect.map(|it| it.height())
+    }
+
+    fn chapter_at(&self, y: f32) -> Option<&(String, f32, f32)> {
+        self.chapter_rects
+            .iter()
+            .find(|(_, top, bottom)| (*top..*bottom).contains(&y))
+    }
+
+    /// Id of the chapter at the top of the viewport.
+    pub fn visible_chapter(&self) -> Option<&str> {
+        let offset = self.scroll_state.clamped_scroll()?;
+
+        self.chapter_at(offset.y).map(|(id, ..)| id.as_str())
+    }
+
+    /// Index of the last page shown in the viewport, counted across all chapters.
+    pub fn last_visible_page(&self) -> Option<usize> {
+        let offset = self.scroll_state.clamped_scroll()?;
+        let bottom = offset.y + self.viewport_height()?;
+
+        self.page_tops
+            .iter()
+            .take_while(|top| **top < bottom)
+            .count()
+            .checked_sub(1)
+    }
+
+    /// Pages of chapter with `id` shown in the viewport, counted within the chapter. It's the
+    /// page nearest to the viewport when none of them is shown.
+    pub fn visible_pages(&self, id: &str) -> Option<RangeInclusive<usize>> {
+        let offset = self.scroll_state.clamped_scroll()?;
+        let (start, end) = (offset.y, offset.y + self.viewport_height()?);
+        let (_, top, bottom) = self.chapter_rects.iter().find(|(it, ..)| it == id)?;
+
+        let tops = self
+            .page_tops
+            .iter()
+            .copied()
+            .filter(|it| (*top..*bottom).contains(it))
+            .collect::<Vec<_>>();
+        let last_page = tops.len().checked_sub(1)?;
+
+        let bottoms = tops.iter().skip(1).chain(Some(bottom));
+        let first = bottoms
+            .take_while(|it| **it <= start)
+            .count()
+            .min(last_page);
+        let last = tops
+            .iter()
+            .take_while(|it| **it < end)
+            .count()
+            .saturating_sub(1)
+            .max(first);
+
+        Some(first..=last)
+    }
+
+    /// Returns `true` if the viewport is within one viewport height of the start.
+    pub fn is_near_start(&self) -> bool {
+        match (self.scroll_state.clamped_scroll(), self.viewport_height()) {
+            (Some(offset), Some(height)) => offset.y < height,
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the viewport is within one viewport height of the end.
+    pub fn is_near_end(&self) -> bool {
+        let limit = self.scroll_state.scroll_limit();
+
+        match (
+            limit,
+            self.scroll_state.clamped_scroll(),
+            self.viewport_height(),
+        ) {
+            (Some(limit), Some(offset), Some(height)) => limit.y - offset.y < height,
+            _ => false,
+        }
+    }
+
+    /// Keep the chapter at the top of the viewport in place when
+    /// the chapters above it are added, removed or resized.
+    fn anchor(&mut self, rects: Vec<(String, f32, f32)>) -> bool {
+        let offset = self.scroll_state.offset.y;
+        let anchor = self
+            .chapter_at(offset)
+            .map(|(id, top, _)| (id.clone(), *top));
+
+        self.chapter_rects = rects;
+
+        let new_top = anchor.and_then(|(id, top)| {
+            self.chapter_rects
+                .iter()
+                .find(|(it, ..)| *it == id)
+                .map(|(_, new_top, _)| new_top - top)
+        });
+
+        match new_top {
+            Some(delta) if delta != 0.0 => {
+                self.scroll_state.offset.y += delta;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Images shown as one chapter in [`VerticalReader`].
+pub struct VerticalChapter<'a> {
+    /// unique id of the chapter, used to keep track the position of the chapter.
+    pub id: String,
+    /// name shown in the divider.
+    pub name: String,
+    pub images: &'a mut Vec<TextureViewState>,
 }
 
 pub struct VerticalReader<'a> {
-    images: &'a mut Vec<TextureViewState>,
+    chapters: Vec<VerticalChapter<'a>>,
     state: &'a mut VerticalReaderState,
     source_id: Option<egui::Id>,
+    show_divider: bool,
 }
 
 impl<'a> VerticalReader<'a> {
     pub fn new(images: &'a mut Vec<TextureViewState>, state: &'a mut VerticalReaderState) -> Self {
-        Self {
+        let chapter = VerticalChapter {
+            id: String::new(),
+            name: String::new(),
             images,
+        };
+
+        Self {
+            chapters: vec![chapter],
+            state,
+            source_id: None,
+            show_divider: false,
+        }
+    }
+
+    /// Show multiple chapter in one strip, separated with divider.
+    pub fn with_chapters(
+        chapters: Vec<VerticalChapter<'a>>,
+        state: &'a mut VerticalReaderState,
+    ) -> Self {
+        Self {
+            chapters,
             state,
             source_id: None,
+            show_divider: true,
         }
     }
 
@@ -38,34 +183,60 @@ impl<'a> VerticalReader<'a> {
 
     pub fn show(self, ui: &mut egui::Ui) -> egui::Response {
         let VerticalReader {
-            images,
+            mut chapters,
             source_id,
             state,
+            show_divider,
         } = self;
         ui.scope(|ui| {
+            let mut rects = vec![];
+            let mut page_tops = vec![];
             let scroll = ScrollArea::both(state.scroll_state.clone())
                 .id_source(source_id)
                 .show(ui, |ui, _| {
                     ui.centered_and_justified(|ui| {
                         ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
-                            for it in images.iter_mut() {
-                                let image_max_size = it.max_size().map(|it| it as f32);
-                                let available_size = ui.available_size() - egui::vec2(1.0, 1.0);
-                                let scale = state.sizer.calc_range(
-                                    image_max_size.into(),
-                                    available_size,
-                                    [true, false],
-                                );
-
-                                TextureView::new(it).show(ui, |_, handle| {
-                                    SplittedTextureWidget::new_with_scale(handle, scale)
-                                });
+                            let origin = ui.min_rect().min.y;
+
+                            for chapter in chapters.iter_mut() {
+                                let top = ui.cursor().min.y - origin;
+
+                                if show_divider {
+                                    ui.add_space(10.0);
+                                    ui.separator();
+                                    ui.heading(&chapter.name);
+                                    ui.separator();
+                                    ui.add_space(10.0);
+                                }
+
+                                for it in chapter.images.iter_mut() {
+                                    page_tops.push(ui.cursor().min.y - origin);
+                                    let image_max_size = it.max_size().map(|it| it as f32);
+                                    let available_size = ui.available_size() - egui::vec2(1.0, 1.0);
+                                    let scale = state.sizer.calc_range(
+                                        image_max_size.into(),
+                                        available_size,
+                                        [true, false],
+                                    );
+
+                                    TextureView::new(it).show(ui, |_, handle| {
+                                        SplittedTextureWidget::new_with_scale(handle, scale)
+                                    });
+                                }
+
+                                let bottom = ui.cursor().min.y - origin;
+                                rects.push((chapter.id.clone(), top, bottom));
                             }
                         })
                     })
                 });
 
             state.scroll_state = scroll.state;
+            state.page_tops = page_tops;
+
+            if state.anchor(rects) {
+                ui.ctx().request_repaint();
+            }
 
             scroll.inner.response
         })